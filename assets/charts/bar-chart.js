@@ -0,0 +1,65 @@
+// Horizontal bar chart, one bar per record in the order of the records
+//
+// Roles: label, value, color (optional; colour intensity of the bars)
+
+const label = field('label', '');
+const value = d => +field('value', 0)(d) || 0;
+const heatOf = d => +field('color', 0)(d) || 0;
+
+const barHeight = 22;
+const marginTop = 30;
+const marginRight = 40;
+const marginBottom = 10;
+const marginLeft = Math.min(400, 10 + 6 * (d3.max(data, d => String(label(d)).length) || 0));
+const width = 928;
+const height = Math.ceil((data.length + 0.1) * barHeight) + marginTop + marginBottom;
+
+const x = d3.scaleLinear()
+    .domain([0, d3.max(data, value) || 1])
+    .range([marginLeft, width - marginRight]);
+
+const y = d3.scaleBand()
+    .domain(d3.range(data.length))
+    .rangeRound([marginTop, height - marginBottom])
+    .padding(0.1);
+
+const heat = d3.scaleSequential([0, d3.max(data, heatOf) || 1], d3.interpolateReds);
+const format = d3.format(',');
+
+const svg = d3.create('svg')
+    .attr('width', width)
+    .attr('height', height)
+    .attr('viewBox', [0, 0, width, height])
+    .attr('style', 'max-width: 100%; height: auto; font: 10px sans-serif;');
+
+const bar = svg.append('g')
+  .selectAll('g')
+  .data(data)
+  .join('g')
+    .on('click', (event, d) => selectEntity(label(d)));
+
+bar.append('title').text(describe);
+
+bar.append('rect')
+    .attr('fill', d => (hasField('color') ? heat(heatOf(d)) : 'steelblue'))
+    .attr('x', x(0))
+    .attr('y', (d, i) => y(i))
+    .attr('width', d => x(value(d)) - x(0))
+    .attr('height', y.bandwidth());
+
+bar.append('text')
+    .attr('x', d => x(value(d)) + 4)
+    .attr('y', (d, i) => y(i) + y.bandwidth() / 2)
+    .attr('dy', '0.35em')
+    .text(d => format(value(d)));
+
+svg.append('g')
+    .attr('transform', `translate(0,${marginTop})`)
+    .call(d3.axisTop(x).ticks(width / 80))
+    .call(g => g.select('.domain').remove());
+
+svg.append('g')
+    .attr('transform', `translate(${marginLeft},0)`)
+    .call(d3.axisLeft(y).tickFormat(i => label(data[i])).tickSizeOuter(0));
+
+container.append(svg.node());
@@ -0,0 +1,110 @@
+// Zoomable circle packing (enclosure diagram) of a '/' separated hierarchy
+//
+// Roles: path, size, color (optional; colour intensity of the leaves,
+// without it leaves are coloured by top level directory)
+//
+// Clicking a directory zooms into it, clicking the background zooms out.
+
+const width = 928;
+const height = width;
+const size = field('size', 0);
+const heatOf = field('color', 0);
+
+const root = d3.pack()
+    .size([width, height])
+    .padding(3)(
+  d3.hierarchy(pathHierarchy(data, field('path')))
+    .sum(d => (d.record ? +size(d.record) || 0 : 0))
+    .sort((a, b) => b.value - a.value));
+
+// The colour value of a directory is the sum of its files
+root.eachAfter(d => {
+  d.heat = d.children ? d3.sum(d.children, c => c.heat) : +heatOf(d.data.record) || 0;
+});
+
+const heat = d3.scaleSequential([0, d3.max(root.leaves(), d => d.heat) || 1], d3.interpolateReds);
+const category = d3.scaleOrdinal(d3.schemeTableau10);
+const topLevel = d => d.ancestors().reverse()[1].data.name;
+const leafColor = d => (hasField('color') ? heat(d.heat) : category(topLevel(d)));
+const directoryColor = d3.scaleLinear()
+    .domain([0, root.height])
+    .range(['hsl(210,20%,95%)', 'hsl(210,20%,75%)'])
+    .interpolate(d3.interpolateHcl);
+
+const format = d3.format(',');
+const tooltip = d => (d.children
+  ? [nodePath(d), `entries: ${format(d.leaves().length)}`,
+    `${options.fields.size}: ${format(d.value)}`,
+    ...(hasField('color') ? [`${options.fields.color}: ${format(d.heat)}`] : [])].join('\n')
+  : describe(d.data.record));
+
+const svg = d3.create('svg')
+    .attr('viewBox', `-${width / 2} -${height / 2} ${width} ${height}`)
+    .attr('width', width)
+    .attr('height', height)
+    .attr('style', 'max-width: 100%; height: auto; display: block; background: white; cursor: pointer;');
+
+const stroke = d => (d.children ? 'hsl(210,20%,60%)' : null);
+
+const node = svg.append('g')
+  .selectAll('circle')
+  .data(root.descendants().slice(1))
+  .join('circle')
+    .attr('fill', d => (d.children ? directoryColor(d.depth) : leafColor(d)))
+    .attr('stroke', stroke)
+    .on('mouseover', function () { d3.select(this).attr('stroke', '#000'); })
+    .on('mouseout', function (event, d) { d3.select(this).attr('stroke', stroke(d)); })
+    .on('click', (event, d) => {
+      if (!d.children) selectEntity(field('path')(d.data.record));
+      const target = d.children ? d : d.parent;
+      if (focus !== target) {
+        zoom(event, target);
+        event.stopPropagation();
+      }
+    });
+
+node.append('title').text(tooltip);
+
+const label = svg.append('g')
+    .style('font', '10px sans-serif')
+    .attr('pointer-events', 'none')
+    .attr('text-anchor', 'middle')
+  .selectAll('text')
+  .data(root.descendants())
+  .join('text')
+    .style('fill-opacity', d => (d.parent === root ? 1 : 0))
+    .style('display', d => (d.parent === root ? 'inline' : 'none'))
+    .text(d => d.data.name);
+
+svg.on('click', event => zoom(event, root));
+
+let focus = root;
+let view;
+zoomTo([focus.x, focus.y, focus.r * 2]);
+
+function zoomTo(v) {
+  const k = width / v[2];
+  view = v;
+  label.attr('transform', d => `translate(${(d.x - v[0]) * k},${(d.y - v[1]) * k})`);
+  node.attr('transform', d => `translate(${(d.x - v[0]) * k},${(d.y - v[1]) * k})`);
+  node.attr('r', d => d.r * k);
+}
+
+function zoom(event, d) {
+  focus = d;
+  const transition = svg.transition()
+      .duration(event.altKey ? 7500 : 750)
+      .tween('zoom', () => {
+        const i = d3.interpolateZoom(view, [focus.x, focus.y, focus.r * 2]);
+        return t => zoomTo(i(t));
+      });
+
+  label
+    .filter(function (d) { return d.parent === focus || this.style.display === 'inline'; })
+    .transition(transition)
+      .style('fill-opacity', d => (d.parent === focus ? 1 : 0))
+      .on('start', function (d) { if (d.parent === focus) this.style.display = 'inline'; })
+      .on('end', function (d) { if (d.parent !== focus) this.style.display = 'none'; });
+}
+
+container.append(svg.node());
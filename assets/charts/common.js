@@ -0,0 +1,48 @@
+// Helpers shared by all chart templates
+//
+// In scope of every template: `data` (the records of the analysis),
+// `options.id`, `options.title` and `options.fields` (chart role -> field
+// name of the records).
+
+const container = document.getElementById(options.id);
+
+const hasField = role => options.fields[role] !== undefined;
+
+// Accessor of the record field mapped to the role
+const field = (role, fallback) => (hasField(role)
+  ? d => (d == null ? fallback : d[options.fields[role]])
+  : () => fallback);
+
+// Announce a click on an entity; pages combining several charts (e.g. the
+// report) listen to `ocs-select` to show details of the entity
+const selectEntity = entity => container.dispatchEvent(
+  new CustomEvent('ocs-select', { bubbles: true, detail: String(entity) }));
+
+// Tooltip text listing all fields of a record
+const describe = record => Object.entries(record)
+  .map(([key, value]) => `${key}: ${value}`)
+  .join('\n');
+
+// Nest records with '/' separated paths into a tree of {name, children}
+// directories and {name, record} leaves
+function pathHierarchy(records, path) {
+  const root = { name: '', children: [], index: new Map() };
+  records.forEach(record => {
+    const parts = String(path(record)).split('/');
+    const name = parts.pop();
+    let node = root;
+    parts.forEach(part => {
+      if (!node.index.has(part)) {
+        const directory = { name: part, children: [], index: new Map() };
+        node.children.push(directory);
+        node.index.set(part, directory);
+      }
+      node = node.index.get(part);
+    });
+    node.children.push({ name, record });
+  });
+  return root;
+}
+
+// Path of a node of a d3.hierarchy built from pathHierarchy
+const nodePath = d => d.ancestors().reverse().slice(1).map(a => a.data.name).join('/') || '/';
@@ -0,0 +1,55 @@
+// Heatmap of a value over two categorical dimensions
+//
+// Roles: x, y, value
+
+const x = d => String(field('x')(d));
+const y = d => String(field('y')(d));
+const value = d => +field('value', 0)(d) || 0;
+
+const xs = Array.from(new Set(data.map(x)));
+const ys = Array.from(new Set(data.map(y)));
+const cell = 16;
+const marginTop = Math.min(300, 10 + 6 * (d3.max(xs, s => s.length) || 0));
+const marginLeft = Math.min(400, 10 + 6 * (d3.max(ys, s => s.length) || 0));
+const marginRight = 10;
+const marginBottom = 10;
+const width = marginLeft + xs.length * cell + marginRight;
+const height = marginTop + ys.length * cell + marginBottom;
+
+const xScale = d3.scaleBand().domain(xs).range([marginLeft, width - marginRight]).padding(0.05);
+const yScale = d3.scaleBand().domain(ys).range([marginTop, height - marginBottom]).padding(0.05);
+const heat = d3.scaleSequential([0, d3.max(data, value) || 1], d3.interpolateReds);
+
+const svg = d3.create('svg')
+    .attr('width', width)
+    .attr('height', height)
+    .attr('viewBox', [0, 0, width, height])
+    .attr('style', 'max-width: 100%; height: auto; font: 10px sans-serif;');
+
+svg.append('g')
+  .selectAll('rect')
+  .data(data)
+  .join('rect')
+    .attr('x', d => xScale(x(d)))
+    .attr('y', d => yScale(y(d)))
+    .attr('width', xScale.bandwidth())
+    .attr('height', yScale.bandwidth())
+    .attr('fill', d => heat(value(d)))
+  .append('title')
+    .text(describe);
+
+svg.append('g')
+    .attr('transform', `translate(0,${marginTop})`)
+    .call(d3.axisTop(xScale).tickSizeOuter(0))
+    .call(g => g.selectAll('text')
+      .attr('text-anchor', 'start')
+      .attr('transform', 'rotate(-90)')
+      .attr('x', 9)
+      .attr('y', 0)
+      .attr('dy', '0.35em'));
+
+svg.append('g')
+    .attr('transform', `translate(${marginLeft},0)`)
+    .call(d3.axisLeft(yScale).tickSizeOuter(0));
+
+container.append(svg.node());
@@ -0,0 +1,85 @@
+// Network of weighted links between entities
+//
+// Roles: source, target, weight (optional; line width and opacity)
+//
+// Entities are placed on a circle ordered by name, so entities of the same
+// directory are neighbours. Hovering an entity highlights its links.
+
+const source = d => String(field('source')(d));
+const target = d => String(field('target')(d));
+const weight = d => +field('weight', 1)(d) || 0;
+
+const names = Array.from(new Set(data.flatMap(d => [source(d), target(d)]))).sort(d3.ascending);
+const width = 928;
+const height = width;
+const radius = width / 2 - Math.min(300, 10 + 6 * (d3.max(names, n => n.length) || 0));
+const angle = d3.scaleBand().domain(names).range([0, 2 * Math.PI]);
+const nodes = names.map(name => {
+  const a = angle(name) + angle.bandwidth() / 2 - Math.PI / 2;
+  return { name, angle: a, x: radius * Math.cos(a), y: radius * Math.sin(a) };
+});
+const byName = new Map(nodes.map(n => [n.name, n]));
+
+const strokeWidth = d3.scaleLinear().domain([0, d3.max(data, weight) || 1]).range([0.5, 5]);
+const opacity = d3.scaleLinear().domain([0, d3.max(data, weight) || 1]).range([0.2, 0.8]);
+
+const svg = d3.create('svg')
+    .attr('viewBox', [-width / 2, -height / 2, width, height])
+    .attr('width', width)
+    .attr('height', height)
+    .attr('style', 'max-width: 100%; height: auto; font: 10px sans-serif;');
+
+const link = svg.append('g')
+    .attr('fill', 'none')
+  .selectAll('path')
+  .data(data)
+  .join('path')
+    .attr('stroke', 'steelblue')
+    .attr('stroke-width', d => strokeWidth(weight(d)))
+    .attr('stroke-opacity', d => opacity(weight(d)))
+    .attr('d', d => {
+      const s = byName.get(source(d));
+      const t = byName.get(target(d));
+      return `M${s.x},${s.y}Q${(s.x + t.x) / 4},${(s.y + t.y) / 4} ${t.x},${t.y}`;
+    });
+
+link.append('title').text(describe);
+
+const node = svg.append('g')
+  .selectAll('g')
+  .data(nodes)
+  .join('g')
+    .attr('transform', d => `rotate(${d.angle * 180 / Math.PI}) translate(${radius},0)`);
+
+node.append('circle')
+    .attr('r', 3)
+    .attr('fill', '#333');
+
+node.append('text')
+    .attr('dy', '0.31em')
+    .attr('x', d => (Math.cos(d.angle) < 0 ? -6 : 6))
+    .attr('text-anchor', d => (Math.cos(d.angle) < 0 ? 'end' : 'start'))
+    .attr('transform', d => (Math.cos(d.angle) < 0 ? 'rotate(180)' : null))
+    .text(d => d.name);
+
+node.append('title')
+    .text(d => [d.name, ...data
+      .filter(l => source(l) === d.name || target(l) === d.name)
+      .map(l => `  ${source(l) === d.name ? target(l) : source(l)}: ${weight(l)}`)].join('\n'));
+
+node
+    .on('mouseover', (event, d) => {
+      const linked = l => source(l) === d.name || target(l) === d.name;
+      link.attr('stroke', l => (linked(l) ? '#d62728' : '#ccc'))
+        .filter(linked)
+        .raise();
+      node.attr('font-weight', n => (n === d || data.some(l => linked(l)
+        && (source(l) === n.name || target(l) === n.name)) ? 'bold' : null));
+    })
+    .on('mouseout', () => {
+      link.attr('stroke', 'steelblue');
+      node.attr('font-weight', null);
+    })
+    .on('click', (event, d) => selectEntity(d.name));
+
+container.append(svg.node());
@@ -0,0 +1,88 @@
+// Line chart of values over time, one line per series
+//
+// Roles: x (date, ISO 8601), y, series (optional)
+//
+// Records without y value (null) are gaps of the line.
+
+const x = d => new Date(field('x')(d));
+const y = d => +field('y')(d);
+const defined = d => field('y')(d) != null;
+const series = d => String(field('series', '')(d));
+
+const width = 928;
+const height = 500;
+const marginTop = 20;
+const marginRight = 30;
+const marginBottom = 30;
+const marginLeft = 50;
+
+const groups = d3.group(
+  data.slice().sort((a, b) => x(a) - x(b)),
+  series);
+
+const xScale = d3.scaleUtc()
+    .domain(d3.extent(data, x))
+    .range([marginLeft, width - marginRight]);
+
+const yScale = d3.scaleLinear()
+    .domain([0, d3.max(data.filter(defined), y) || 1])
+    .nice()
+    .range([height - marginBottom, marginTop]);
+
+const color = d3.scaleOrdinal(d3.schemeTableau10);
+const line = d3.line(d => xScale(x(d)), d => yScale(y(d))).defined(defined);
+
+const svg = d3.create('svg')
+    .attr('width', width)
+    .attr('height', height)
+    .attr('viewBox', [0, 0, width, height])
+    .attr('style', 'max-width: 100%; height: auto; font: 10px sans-serif;');
+
+svg.append('g')
+    .attr('transform', `translate(0,${height - marginBottom})`)
+    .call(d3.axisBottom(xScale).ticks(width / 80).tickSizeOuter(0));
+
+svg.append('g')
+    .attr('transform', `translate(${marginLeft},0)`)
+    .call(d3.axisLeft(yScale).ticks(height / 40))
+    .call(g => g.select('.domain').remove())
+    .call(g => g.append('text')
+      .attr('x', -marginLeft)
+      .attr('y', 10)
+      .attr('fill', 'currentColor')
+      .attr('text-anchor', 'start')
+      .text(options.fields.y));
+
+const serie = svg.append('g')
+  .selectAll('g')
+  .data(Array.from(groups))
+  .join('g');
+
+serie.append('path')
+    .attr('fill', 'none')
+    .attr('stroke', ([name]) => color(name))
+    .attr('stroke-width', 1.5)
+    .attr('d', ([, values]) => line(values));
+
+serie.append('g')
+    .attr('fill', ([name]) => color(name))
+  .selectAll('circle')
+  .data(([, values]) => values.filter(defined))
+  .join('circle')
+    .attr('cx', d => xScale(x(d)))
+    .attr('cy', d => yScale(y(d)))
+    .attr('r', 2.5)
+  .append('title')
+    .text(describe);
+
+if (hasField('series')) {
+  const legend = svg.append('g')
+    .selectAll('g')
+    .data(Array.from(groups.keys()))
+    .join('g')
+      .attr('transform', (d, i) => `translate(${width - marginRight - 150},${marginTop + i * 14})`);
+  legend.append('rect').attr('width', 10).attr('height', 10).attr('fill', color);
+  legend.append('text').attr('x', 14).attr('y', 9).text(d => d);
+}
+
+container.append(svg.node());
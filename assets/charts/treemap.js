@@ -0,0 +1,61 @@
+// Treemap of a '/' separated hierarchy
+//
+// Roles: path, size, color (optional; colour intensity of the leaves,
+// without it leaves are coloured by top level directory)
+
+const width = 1154;
+const height = 800;
+const size = field('size', 0);
+const heatOf = field('color', 0);
+
+const root = d3.treemap()
+    .size([width, height])
+    .paddingOuter(3)
+    .paddingTop(17)
+    .paddingInner(1)
+    .round(true)(
+  d3.hierarchy(pathHierarchy(data, field('path')))
+    .sum(d => (d.record ? +size(d.record) || 0 : 0))
+    .sort((a, b) => b.value - a.value));
+
+const heat = d3.scaleSequential(
+  [0, d3.max(root.leaves(), d => +heatOf(d.data.record) || 0) || 1],
+  d3.interpolateReds);
+const category = d3.scaleOrdinal(d3.schemeTableau10);
+const topLevel = d => (d.depth ? d.ancestors().reverse()[1].data.name : '');
+const format = d3.format(',');
+
+const svg = d3.create('svg')
+    .attr('viewBox', [0, 0, width, height])
+    .attr('width', width)
+    .attr('height', height)
+    .attr('style', 'max-width: 100%; height: auto; font: 10px sans-serif;');
+
+const node = svg.selectAll('g')
+  .data(root.descendants())
+  .join('g')
+    .attr('transform', d => `translate(${d.x0},${d.y0})`)
+    .on('click', (event, d) => {
+      if (!d.children) selectEntity(field('path')(d.data.record));
+    });
+
+node.append('title')
+    .text(d => (d.children
+      ? `${nodePath(d)}\nentries: ${format(d.leaves().length)}\n${options.fields.size}: ${format(d.value)}`
+      : describe(d.data.record)));
+
+node.append('rect')
+    .attr('width', d => d.x1 - d.x0)
+    .attr('height', d => d.y1 - d.y0)
+    .attr('fill', d => (d.children
+      ? 'hsl(210,20%,92%)'
+      : hasField('color') ? heat(+heatOf(d.data.record) || 0) : category(topLevel(d))))
+    .attr('stroke', d => (d.children ? 'hsl(210,20%,70%)' : null));
+
+node.append('text')
+    .attr('x', 3)
+    .attr('y', 13)
+    .attr('fill-opacity', d => (d.children ? 0.8 : 1))
+    .text(d => ((d.x1 - d.x0) > 6 * String(d.data.name).length ? d.data.name : ''));
+
+container.append(svg.node());
@@ -0,0 +1,1824 @@
+// ocs-d3 - a small, dependency free subset of the D3 v7 API
+//
+// Embedded into every HTML page generated by ocs, so the pages work without
+// network access. Only the parts of D3 used by the ocs templates are
+// provided; the semantics follow D3 (https://d3js.org, ISC license), many
+// algorithms (circle packing, zoom interpolation, colour spaces) are ports of
+// the D3 implementation. Pages needing the full D3 library can be generated
+// with `ocs --d3-cdn`.
+//
+// D3 is distributed under the following license:
+//
+// Copyright 2010-2023 Mike Bostock
+//
+// Permission to use, copy, modify, and/or distribute this software for any purpose
+// with or without fee is hereby granted, provided that the above copyright notice
+// and this permission notice appear in all copies.
+//
+// THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHOR DISCLAIMS ALL WARRANTIES WITH
+// REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF MERCHANTABILITY AND
+// FITNESS. IN NO EVENT SHALL THE AUTHOR BE LIABLE FOR ANY SPECIAL, DIRECT,
+// INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES WHATSOEVER RESULTING FROM LOSS
+// OF USE, DATA OR PROFITS, WHETHER IN AN ACTION OF CONTRACT, NEGLIGENCE OR OTHER
+// TORTIOUS ACTION, ARISING OUT OF OR IN CONNECTION WITH THE USE OR PERFORMANCE OF
+// THIS SOFTWARE.
+(function (global) {
+  'use strict';
+
+  const d3 = {};
+  const SVG_NS = 'http://www.w3.org/2000/svg';
+
+  // ---------------------------------------------------------------------
+  // Selections
+
+  function EnterNode(parent, datum) {
+    this.ownerDocument = parent.ownerDocument;
+    this.namespaceURI = parent.namespaceURI;
+    this._parent = parent;
+    this.__data__ = datum;
+  }
+
+  EnterNode.prototype = {
+    appendChild(child) { return this._parent.appendChild(child); },
+    insertBefore(child, next) { return this._parent.insertBefore(child, next); },
+    querySelector(selector) { return this._parent.querySelector(selector); },
+    querySelectorAll(selector) { return this._parent.querySelectorAll(selector); },
+  };
+
+  function creator(name) {
+    return function () {
+      const document = this.ownerDocument || global.document;
+      if (name === 'svg' || this.namespaceURI === SVG_NS) {
+        return document.createElementNS(SVG_NS, name);
+      }
+      return document.createElement(name);
+    };
+  }
+
+  function selector(value) {
+    if (typeof value === 'function') return value;
+    return function () { return this.querySelector(value); };
+  }
+
+  function selectorAll(value) {
+    if (typeof value === 'function') return value;
+    if (value == null) return () => [];
+    return function () { return this.querySelectorAll(value); };
+  }
+
+  function Selection(groups, parents) {
+    this._groups = groups;
+    this._parents = parents;
+  }
+
+  Selection.prototype = {
+    select(value) {
+      const select = selector(value);
+      const groups = this._groups.map(group => group.map((node, i) => {
+        if (!node) return undefined;
+        const sub = select.call(node, node.__data__, i, group);
+        if (sub && '__data__' in node) sub.__data__ = node.__data__;
+        return sub || undefined;
+      }));
+      return new Selection(groups, this._parents);
+    },
+
+    selectAll(value) {
+      const select = selectorAll(value);
+      const groups = [];
+      const parents = [];
+      this._groups.forEach(group => group.forEach((node, i) => {
+        if (!node) return;
+        groups.push(Array.from(select.call(node, node.__data__, i, group)));
+        parents.push(node);
+      }));
+      return new Selection(groups, parents);
+    },
+
+    filter(match) {
+      const test = typeof match === 'function'
+        ? match
+        : function () { return this.matches(match); };
+      const groups = this._groups.map(group =>
+        group.filter((node, i) => node && test.call(node, node.__data__, i, group)));
+      return new Selection(groups, this._parents);
+    },
+
+    data(value) {
+      const parents = this._parents;
+      const update = [];
+      const enter = [];
+      const exit = [];
+      this._groups.forEach((group, j) => {
+        const parent = parents[j];
+        const data = Array.from(typeof value === 'function'
+          ? value.call(parent, parent && parent.__data__, j, parents)
+          : value);
+        const updateGroup = new Array(data.length);
+        const enterGroup = new Array(data.length);
+        const exitGroup = new Array(group.length);
+        data.forEach((datum, i) => {
+          if (group[i]) {
+            group[i].__data__ = datum;
+            updateGroup[i] = group[i];
+          } else {
+            enterGroup[i] = new EnterNode(parent, datum);
+          }
+        });
+        for (let i = data.length; i < group.length; ++i) exitGroup[i] = group[i];
+        update.push(updateGroup);
+        enter.push(enterGroup);
+        exit.push(exitGroup);
+      });
+      const selection = new Selection(update, parents);
+      selection._enter = enter;
+      selection._exit = exit;
+      return selection;
+    },
+
+    enter() {
+      return new Selection(this._enter || this._groups.map(g => new Array(g.length)), this._parents);
+    },
+
+    exit() {
+      return new Selection(this._exit || this._groups.map(g => new Array(g.length)), this._parents);
+    },
+
+    join(onenter, onupdate, onexit) {
+      let enter = this.enter();
+      let update = this;
+      const exit = this.exit();
+      if (typeof onenter === 'function') {
+        enter = onenter(enter);
+        if (enter && enter.selection) enter = enter.selection();
+      } else {
+        enter = enter.append(String(onenter));
+      }
+      if (onupdate != null) {
+        update = onupdate(update);
+        if (update && update.selection) update = update.selection();
+      }
+      if (onexit == null) exit.remove(); else onexit(exit);
+      return enter && update ? enter.merge(update).order() : update;
+    },
+
+    merge(other) {
+      const others = other.selection ? other.selection()._groups : other._groups;
+      const groups = this._groups.map((group, j) =>
+        group.map((node, i) => node || (others[j] && others[j][i])));
+      return new Selection(groups, this._parents);
+    },
+
+    order() {
+      this._groups.forEach(group => {
+        let next;
+        for (let i = group.length - 1; i >= 0; --i) {
+          const node = group[i];
+          if (!node) continue;
+          if (next && node.compareDocumentPosition(next) ^ 4) {
+            next.parentNode.insertBefore(node, next);
+          }
+          next = node;
+        }
+      });
+      return this;
+    },
+
+    sort(compare) {
+      const groups = this._groups.map(group =>
+        group.filter(Boolean).sort((a, b) => compare(a.__data__, b.__data__)));
+      return new Selection(groups, this._parents).order();
+    },
+
+    append(name) {
+      const create = typeof name === 'function' ? name : creator(name);
+      return this.select(function () {
+        return this.appendChild(create.apply(this, arguments));
+      });
+    },
+
+    remove() {
+      return this.each(function () {
+        if (this.parentNode) this.parentNode.removeChild(this);
+      });
+    },
+
+    raise() {
+      return this.each(function () {
+        if (this.nextSibling) this.parentNode.appendChild(this);
+      });
+    },
+
+    lower() {
+      return this.each(function () {
+        if (this.previousSibling) this.parentNode.insertBefore(this, this.parentNode.firstChild);
+      });
+    },
+
+    each(callback) {
+      this._groups.forEach(group => group.forEach((node, i) => {
+        if (node) callback.call(node, node.__data__, i, group);
+      }));
+      return this;
+    },
+
+    call(callback, ...args) {
+      callback(this, ...args);
+      return this;
+    },
+
+    node() {
+      for (const group of this._groups) {
+        for (const node of group) if (node) return node;
+      }
+      return null;
+    },
+
+    nodes() {
+      const nodes = [];
+      this.each(function () { nodes.push(this); });
+      return nodes;
+    },
+
+    size() { return this.nodes().length; },
+
+    empty() { return !this.node(); },
+
+    datum(value) {
+      if (value === undefined) return this.node() ? this.node().__data__ : undefined;
+      return this.each(function (d, i, group) {
+        this.__data__ = typeof value === 'function' ? value.call(this, d, i, group) : value;
+      });
+    },
+
+    attr(name, value) {
+      if (value === undefined) return this.node() ? this.node().getAttribute(name) : null;
+      return this.each(function (d, i, group) {
+        const v = typeof value === 'function' ? value.call(this, d, i, group) : value;
+        if (v == null) this.removeAttribute(name); else this.setAttribute(name, v);
+      });
+    },
+
+    style(name, value, priority) {
+      if (value === undefined) {
+        const node = this.node();
+        return node ? node.style.getPropertyValue(name) : null;
+      }
+      return this.each(function (d, i, group) {
+        const v = typeof value === 'function' ? value.call(this, d, i, group) : value;
+        if (v == null) this.style.removeProperty(name);
+        else this.style.setProperty(name, v, priority || '');
+      });
+    },
+
+    property(name, value) {
+      if (value === undefined) return this.node() ? this.node()[name] : undefined;
+      return this.each(function (d, i, group) {
+        this[name] = typeof value === 'function' ? value.call(this, d, i, group) : value;
+      });
+    },
+
+    classed(names, value) {
+      const list = String(names).trim().split(/\s+/);
+      if (value === undefined) {
+        const node = this.node();
+        return !!node && list.every(c => node.classList.contains(c));
+      }
+      return this.each(function (d, i, group) {
+        const v = typeof value === 'function' ? value.call(this, d, i, group) : value;
+        list.forEach(c => this.classList.toggle(c, !!v));
+      });
+    },
+
+    text(value) {
+      if (value === undefined) return this.node() ? this.node().textContent : null;
+      return this.each(function (d, i, group) {
+        const v = typeof value === 'function' ? value.call(this, d, i, group) : value;
+        this.textContent = v == null ? '' : v;
+      });
+    },
+
+    html(value) {
+      if (value === undefined) return this.node() ? this.node().innerHTML : null;
+      return this.each(function (d, i, group) {
+        const v = typeof value === 'function' ? value.call(this, d, i, group) : value;
+        this.innerHTML = v == null ? '' : v;
+      });
+    },
+
+    on(typename, listener) {
+      const type = String(typename).split('.')[0];
+      const key = '__on_' + typename;
+      return this.each(function () {
+        if (this[key]) this.removeEventListener(type, this[key]);
+        if (listener == null) {
+          delete this[key];
+          return;
+        }
+        this[key] = function (event) { listener.call(this, event, this.__data__); };
+        this.addEventListener(type, this[key]);
+      });
+    },
+
+    transition(parent) {
+      const timing = parent instanceof Transition ? parent._timing : undefined;
+      return new Transition(this._groups, this._parents, timing);
+    },
+
+    interrupt() {
+      return this.each(function () { delete this.__transition; });
+    },
+  };
+
+  d3.selection = function () {
+    return new Selection([[global.document.documentElement]], [null]);
+  };
+
+  d3.select = function (value) {
+    const node = typeof value === 'string' ? global.document.querySelector(value) : value;
+    return new Selection([[node]], [global.document.documentElement]);
+  };
+
+  d3.selectAll = function (value) {
+    const nodes = typeof value === 'string'
+      ? global.document.querySelectorAll(value)
+      : value || [];
+    return new Selection([Array.from(nodes)], [global.document.documentElement]);
+  };
+
+  d3.create = function (name) {
+    return d3.select(creator(name).call(global.document.documentElement));
+  };
+
+  // ---------------------------------------------------------------------
+  // Transitions
+
+  function easeCubic(t) {
+    return ((t *= 2) <= 1 ? t * t * t : (t -= 2) * t * t + 2) / 2;
+  }
+
+  d3.easeCubic = easeCubic;
+  d3.easeLinear = t => +t;
+
+  let transitionId = 0;
+
+  // A transition shares its timing with the transitions derived from it via
+  // `selection.transition(transition)`, like in D3.
+  function Transition(groups, parents, timing) {
+    this._groups = groups;
+    this._parents = parents;
+    this._id = ++transitionId;
+    this._timing = timing || { delay: 0, duration: 250, ease: easeCubic, time: null };
+    this._tweens = new Map();
+    this._listeners = { start: [], end: [], interrupt: [] };
+    global.requestAnimationFrame(now => this._schedule(now));
+  }
+
+  Transition.prototype = {
+    _schedule(now) {
+      const timing = this._timing;
+      if (timing.time == null) timing.time = now;
+      if (now - timing.time < timing.delay) {
+        global.requestAnimationFrame(later => this._schedule(later));
+        return;
+      }
+      const instances = [];
+      this._groups.forEach(group => group.forEach((node, i) => {
+        if (!node) return;
+        if (node.__transition && node.__transition !== this._id) {
+          this._listeners.interrupt.forEach(fn => fn.call(node, node.__data__, i, group));
+        }
+        node.__transition = this._id;
+        this._listeners.start.forEach(fn => fn.call(node, node.__data__, i, group));
+        const tweens = [];
+        this._tweens.forEach(factory => {
+          const tween = factory.call(node, node.__data__, i, group);
+          if (tween) tweens.push(tween);
+        });
+        instances.push({ node, i, group, tweens });
+      }));
+      const tick = time => {
+        const elapsed = time - timing.time - timing.delay;
+        const t = timing.duration > 0 ? Math.max(0, Math.min(1, elapsed / timing.duration)) : 1;
+        const e = timing.ease(t);
+        const active = instances.filter(inst => inst.node.__transition === this._id);
+        active.forEach(inst => inst.tweens.forEach(tween => tween.call(inst.node, e)));
+        if (t < 1) {
+          if (active.length) global.requestAnimationFrame(tick);
+          return;
+        }
+        active.forEach(inst => {
+          delete inst.node.__transition;
+          this._listeners.end.forEach(fn => fn.call(inst.node, inst.node.__data__, inst.i, inst.group));
+        });
+      };
+      tick(now);
+    },
+
+    duration(value) {
+      if (value === undefined) return this._timing.duration;
+      this._timing.duration = +value;
+      return this;
+    },
+
+    delay(value) {
+      if (value === undefined) return this._timing.delay;
+      this._timing.delay = +value;
+      return this;
+    },
+
+    ease(value) {
+      if (value === undefined) return this._timing.ease;
+      this._timing.ease = value;
+      return this;
+    },
+
+    tween(name, factory) {
+      if (factory === undefined) return this._tweens.get(name);
+      if (factory === null) this._tweens.delete(name); else this._tweens.set(name, factory);
+      return this;
+    },
+
+    attr(name, value) {
+      return this.tween('attr.' + name, function (d, i, group) {
+        const target = typeof value === 'function' ? value.call(this, d, i, group) : value;
+        if (target == null) return t => { if (t >= 1) this.removeAttribute(name); };
+        const interpolator = interpolate(this.getAttribute(name), target);
+        return t => this.setAttribute(name, interpolator(t));
+      });
+    },
+
+    style(name, value, priority) {
+      return this.tween('style.' + name, function (d, i, group) {
+        const target = typeof value === 'function' ? value.call(this, d, i, group) : value;
+        if (target == null) return t => { if (t >= 1) this.style.removeProperty(name); };
+        const start = this.style.getPropertyValue(name)
+          || global.getComputedStyle(this).getPropertyValue(name);
+        const interpolator = interpolate(start, target);
+        return t => this.style.setProperty(name, interpolator(t), priority || '');
+      });
+    },
+
+    text(value) {
+      return this.on('start.text', function (d, i, group) {
+        const v = typeof value === 'function' ? value.call(this, d, i, group) : value;
+        this.textContent = v == null ? '' : v;
+      });
+    },
+
+    remove() {
+      return this.on('end.remove', function () {
+        if (this.parentNode) this.parentNode.removeChild(this);
+      });
+    },
+
+    on(typename, listener) {
+      const type = String(typename).split('.')[0];
+      this._listeners[type].push(listener);
+      return this;
+    },
+
+    selection() {
+      return new Selection(this._groups, this._parents);
+    },
+
+    transition() {
+      return new Transition(this._groups, this._parents, {
+        delay: this._timing.delay + this._timing.duration,
+        duration: this._timing.duration,
+        ease: this._timing.ease,
+        time: null,
+      });
+    },
+  };
+
+  Transition.prototype.filter = function (match) {
+    const selection = this.selection().filter(match);
+    return new Transition(selection._groups, selection._parents, this._timing);
+  };
+
+  // ---------------------------------------------------------------------
+  // Colours and interpolation
+
+  function Rgb(r, g, b, opacity) {
+    this.r = r;
+    this.g = g;
+    this.b = b;
+    this.opacity = opacity == null ? 1 : opacity;
+  }
+
+  Rgb.prototype.toString = function () {
+    const c = v => Math.max(0, Math.min(255, Math.round(v) || 0));
+    return this.opacity === 1
+      ? `rgb(${c(this.r)}, ${c(this.g)}, ${c(this.b)})`
+      : `rgba(${c(this.r)}, ${c(this.g)}, ${c(this.b)}, ${this.opacity})`;
+  };
+
+  Rgb.prototype.darker = function (k) {
+    k = Math.pow(0.7, k == null ? 1 : k);
+    return new Rgb(this.r * k, this.g * k, this.b * k, this.opacity);
+  };
+
+  Rgb.prototype.brighter = function (k) {
+    k = Math.pow(1 / 0.7, k == null ? 1 : k);
+    return new Rgb(this.r * k, this.g * k, this.b * k, this.opacity);
+  };
+
+  Rgb.prototype.formatHex = function () {
+    const h = v => Math.max(0, Math.min(255, Math.round(v) || 0)).toString(16).padStart(2, '0');
+    return '#' + h(this.r) + h(this.g) + h(this.b);
+  };
+
+  const namedColors = {
+    black: '#000000', white: '#ffffff', red: '#ff0000', green: '#008000', blue: '#0000ff',
+    grey: '#808080', gray: '#808080', steelblue: '#4682b4', orange: '#ffa500',
+  };
+
+  function hue2rgb(h, m1, m2) {
+    return 255 * (h < 60 ? m1 + (m2 - m1) * h / 60
+      : h < 180 ? m2
+        : h < 240 ? m1 + (m2 - m1) * (240 - h) / 60
+          : m1);
+  }
+
+  function hslToRgb(h, s, l, opacity) {
+    h = (h % 360) + (h < 0) * 360;
+    const m2 = l + (l < 0.5 ? l : 1 - l) * s;
+    const m1 = 2 * l - m2;
+    return new Rgb(
+      hue2rgb(h >= 240 ? h - 240 : h + 120, m1, m2),
+      hue2rgb(h, m1, m2),
+      hue2rgb(h < 120 ? h + 240 : h - 120, m1, m2),
+      opacity);
+  }
+
+  function color(value) {
+    if (value instanceof Rgb) return value;
+    let s = String(value).trim().toLowerCase();
+    if (namedColors[s]) s = namedColors[s];
+    if (s === 'transparent') return new Rgb(0, 0, 0, 0);
+    let m = /^#([0-9a-f]{3}|[0-9a-f]{6})$/.exec(s);
+    if (m) {
+      let hex = m[1];
+      if (hex.length === 3) hex = hex.replace(/./g, c => c + c);
+      const n = parseInt(hex, 16);
+      return new Rgb((n >> 16) & 0xff, (n >> 8) & 0xff, n & 0xff, 1);
+    }
+    m = /^rgba?\(([^)]*)\)$/.exec(s);
+    if (m) {
+      const p = m[1].split(/[\s,/]+/).filter(Boolean).map(parseFloat);
+      return new Rgb(p[0], p[1], p[2], p.length > 3 ? p[3] : 1);
+    }
+    m = /^hsla?\(([^)]*)\)$/.exec(s);
+    if (m) {
+      const p = m[1].split(/[\s,/]+/).filter(Boolean).map(parseFloat);
+      return hslToRgb(p[0], p[1] / 100, p[2] / 100, p.length > 3 ? p[3] : 1);
+    }
+    return null;
+  }
+
+  d3.color = color;
+  d3.rgb = (r, g, b, opacity) => (g === undefined ? color(r) : new Rgb(r, g, b, opacity));
+
+  // CIELAB / HCL conversions, see d3-color
+  const Xn = 0.96422;
+  const Yn = 1;
+  const Zn = 0.82521;
+  const t0 = 4 / 29;
+  const t1 = 6 / 29;
+  const t2 = 3 * t1 * t1;
+  const t3 = t1 * t1 * t1;
+
+  const rgb2lrgb = x => ((x /= 255) <= 0.04045 ? x / 12.92 : Math.pow((x + 0.055) / 1.055, 2.4));
+  const lrgb2rgb = x => 255 * (x <= 0.0031308 ? 12.92 * x : 1.055 * Math.pow(x, 1 / 2.4) - 0.055);
+  const xyz2lab = t => (t > t3 ? Math.pow(t, 1 / 3) : t / t2 + t0);
+  const lab2xyz = t => (t > t1 ? t * t * t : t2 * (t - t0));
+
+  function hcl(value) {
+    const o = color(value);
+    const r = rgb2lrgb(o.r);
+    const g = rgb2lrgb(o.g);
+    const b = rgb2lrgb(o.b);
+    const y = xyz2lab((0.2225045 * r + 0.7168786 * g + 0.0606169 * b) / Yn);
+    let x = y;
+    let z = y;
+    if (r !== g || g !== b) {
+      x = xyz2lab((0.4360747 * r + 0.3850649 * g + 0.1430804 * b) / Xn);
+      z = xyz2lab((0.0139322 * r + 0.0971045 * g + 0.7141733 * b) / Zn);
+    }
+    const l = 116 * y - 16;
+    const la = 500 * (x - y);
+    const lb = 200 * (y - z);
+    const c = Math.sqrt(la * la + lb * lb);
+    let h = c < 1e-6 ? NaN : Math.atan2(lb, la) * 180 / Math.PI;
+    if (h < 0) h += 360;
+    return { h, c, l, opacity: o.opacity };
+  }
+
+  function hclToRgb(h, c, l, opacity) {
+    if (isNaN(h)) h = 0;
+    const rad = h * Math.PI / 180;
+    const la = Math.cos(rad) * c;
+    const lb = Math.sin(rad) * c;
+    let y = (l + 16) / 116;
+    let x = y + la / 500;
+    let z = y - lb / 200;
+    x = Xn * lab2xyz(x);
+    y = Yn * lab2xyz(y);
+    z = Zn * lab2xyz(z);
+    return new Rgb(
+      lrgb2rgb(3.1338561 * x - 1.6168667 * y - 0.4906146 * z),
+      lrgb2rgb(-0.9787684 * x + 1.9161415 * y + 0.0334540 * z),
+      lrgb2rgb(0.0719453 * x - 0.2289914 * y + 1.4052427 * z),
+      opacity);
+  }
+
+  function interpolateNumber(a, b) {
+    a = +a;
+    b = +b;
+    return t => a * (1 - t) + b * t;
+  }
+
+  function interpolateRgb(a, b) {
+    const ca = color(a) || color(b);
+    const cb = color(b);
+    return t => new Rgb(
+      ca.r + (cb.r - ca.r) * t,
+      ca.g + (cb.g - ca.g) * t,
+      ca.b + (cb.b - ca.b) * t,
+      ca.opacity + (cb.opacity - ca.opacity) * t).toString();
+  }
+
+  function interpolateHcl(a, b) {
+    const ca = hcl(a);
+    const cb = hcl(b);
+    const ha = isNaN(ca.h) ? cb.h : ca.h;
+    const hb = isNaN(cb.h) ? ha : cb.h;
+    let dh = hb - ha;
+    if (dh > 180 || dh < -180) dh -= 360 * Math.round(dh / 360);
+    return t => hclToRgb(
+      ha + dh * t,
+      ca.c + (cb.c - ca.c) * t,
+      ca.l + (cb.l - ca.l) * t,
+      ca.opacity + (cb.opacity - ca.opacity) * t).toString();
+  }
+
+  function interpolateString(a, b) {
+    a = String(a);
+    b = String(b);
+    const numbers = /[-+]?(?:\d+\.?\d*|\.?\d+)(?:[eE][-+]?\d+)?/g;
+    const from = a.match(numbers) || [];
+    const parts = [];
+    let last = 0;
+    let k = 0;
+    let m;
+    while ((m = numbers.exec(b))) {
+      parts.push(b.slice(last, m.index));
+      const to = +m[0];
+      parts.push(interpolateNumber(k < from.length ? +from[k] : to, to));
+      k += 1;
+      last = numbers.lastIndex;
+    }
+    parts.push(b.slice(last));
+    return t => parts.map(p => (typeof p === 'function' ? p(t) : p)).join('');
+  }
+
+  function interpolate(a, b) {
+    if (typeof b === 'number') return interpolateNumber(a, b);
+    if (b != null && b !== '' && !isNaN(+b) && a != null && a !== '' && !isNaN(+a)) {
+      return interpolateNumber(a, b);
+    }
+    if (color(b)) return interpolateRgb(a, b);
+    return interpolateString(a, b);
+  }
+
+  // Smooth zooming and panning, van Wijk and Nuij; see d3-interpolate
+  function interpolateZoom(p0, p1) {
+    const rho = Math.SQRT2;
+    const rho2 = 2;
+    const rho4 = 4;
+    const ux0 = p0[0];
+    const uy0 = p0[1];
+    const w0 = p0[2];
+    const dx = p1[0] - ux0;
+    const dy = p1[1] - uy0;
+    const w1 = p1[2];
+    const d2 = dx * dx + dy * dy;
+    let i;
+    let S;
+    if (d2 < 1e-12) {
+      S = Math.log(w1 / w0) / rho;
+      i = t => [ux0 + t * dx, uy0 + t * dy, w0 * Math.exp(rho * t * S)];
+    } else {
+      const d1 = Math.sqrt(d2);
+      const b0 = (w1 * w1 - w0 * w0 + rho4 * d2) / (2 * w0 * rho2 * d1);
+      const b1 = (w1 * w1 - w0 * w0 - rho4 * d2) / (2 * w1 * rho2 * d1);
+      const r0 = Math.log(Math.sqrt(b0 * b0 + 1) - b0);
+      const r1 = Math.log(Math.sqrt(b1 * b1 + 1) - b1);
+      S = (r1 - r0) / rho;
+      i = t => {
+        const s = t * S;
+        const coshr0 = Math.cosh(r0);
+        const u = w0 / (rho2 * d1) * (coshr0 * Math.tanh(rho * s + r0) - Math.sinh(r0));
+        return [ux0 + u * dx, uy0 + u * dy, w0 * coshr0 / Math.cosh(rho * s + r0)];
+      };
+    }
+    i.duration = S * 1000 * rho / Math.SQRT2;
+    return i;
+  }
+
+  d3.interpolate = interpolate;
+  d3.interpolateNumber = interpolateNumber;
+  d3.interpolateRgb = interpolateRgb;
+  d3.interpolateHcl = interpolateHcl;
+  d3.interpolateString = interpolateString;
+  d3.interpolateZoom = interpolateZoom;
+
+  // Sequential colour schemes, piecewise linear through the ColorBrewer
+  // colours (D3 uses a basis spline, the difference is hardly visible)
+  function ramp(colors) {
+    const n = colors.length - 1;
+    return t => {
+      t = Math.max(0, Math.min(1, t));
+      const i = Math.min(n - 1, Math.floor(t * n));
+      return interpolateRgb(colors[i], colors[i + 1])(t * n - i);
+    };
+  }
+
+  d3.interpolateReds = ramp(['#fff5f0', '#fee0d2', '#fcbba1', '#fc9272', '#fb6a4a',
+    '#ef3b2c', '#cb181d', '#a50f15', '#67000d']);
+  d3.interpolateBlues = ramp(['#f7fbff', '#deebf7', '#c6dbef', '#9ecae1', '#6baed6',
+    '#4292c6', '#2171b5', '#08519c', '#08306b']);
+  d3.interpolateYlOrRd = ramp(['#ffffcc', '#ffeda0', '#fed976', '#feb24c', '#fd8d3c',
+    '#fc4e2a', '#e31a1c', '#bd0026', '#800026']);
+  d3.interpolateRdYlGn = ramp(['#a50026', '#d73027', '#f46d43', '#fdae61', '#fee08b',
+    '#d9ef8b', '#a6d96a', '#66bd63', '#1a9850', '#006837']);
+
+  d3.schemeTableau10 = ['#4e79a7', '#f28e2c', '#e15759', '#76b7b2', '#59a14f',
+    '#edc949', '#af7aa1', '#ff9da7', '#9c755f', '#bab0ab'];
+
+  // ---------------------------------------------------------------------
+  // Scales
+
+  function scaleLinear() {
+    let domain = [0, 1];
+    let range = [0, 1];
+    let interpolator = interpolate;
+    let clamp = false;
+
+    function scale(x) {
+      x = +x;
+      const n = Math.min(domain.length, range.length) - 1;
+      const ascending = domain[n] >= domain[0];
+      let j = 0;
+      while (j < n - 1 && (ascending ? x > domain[j + 1] : x < domain[j + 1])) j += 1;
+      const d0 = domain[j];
+      const d1 = domain[j + 1];
+      let t = d1 === d0 ? 0.5 : (x - d0) / (d1 - d0);
+      if (clamp) t = Math.max(0, Math.min(1, t));
+      return interpolator(range[j], range[j + 1])(t);
+    }
+
+    scale.invert = y => {
+      const t = (y - range[0]) / (range[1] - range[0]);
+      return domain[0] + t * (domain[1] - domain[0]);
+    };
+    scale.domain = d => (d === undefined ? domain.slice() : (domain = Array.from(d, Number), scale));
+    scale.range = r => (r === undefined ? range.slice() : (range = Array.from(r), scale));
+    scale.interpolate = f => (f === undefined ? interpolator : (interpolator = f, scale));
+    scale.clamp = c => (c === undefined ? clamp : (clamp = !!c, scale));
+    scale.ticks = count => ticks(domain[0], domain[domain.length - 1], count == null ? 10 : count);
+    scale.tickFormat = (count, specifier) => format(specifier || ',');
+    scale.nice = count => {
+      const step = tickStep(domain[0], domain[domain.length - 1], count == null ? 10 : count);
+      if (step) {
+        domain[0] = Math.floor(domain[0] / step) * step;
+        domain[domain.length - 1] = Math.ceil(domain[domain.length - 1] / step) * step;
+      }
+      return scale;
+    };
+    scale.copy = () => scaleLinear().domain(domain).range(range).interpolate(interpolator).clamp(clamp);
+    return scale;
+  }
+
+  function scaleSequential(a, b) {
+    let domain = [0, 1];
+    let interpolator = t => t;
+    let clamp = false;
+    if (typeof a === 'function') interpolator = a;
+    else if (a) {
+      domain = Array.from(a, Number);
+      if (b) interpolator = b;
+    }
+
+    function scale(x) {
+      let t = domain[1] === domain[0] ? 0.5 : (x - domain[0]) / (domain[1] - domain[0]);
+      if (clamp) t = Math.max(0, Math.min(1, t));
+      return interpolator(t);
+    }
+
+    scale.domain = d => (d === undefined ? domain.slice() : (domain = Array.from(d, Number), scale));
+    scale.interpolator = f => (f === undefined ? interpolator : (interpolator = f, scale));
+    scale.clamp = c => (c === undefined ? clamp : (clamp = !!c, scale));
+    return scale;
+  }
+
+  function scaleOrdinal(initialRange) {
+    const index = new Map();
+    let domain = [];
+    let range = initialRange ? Array.from(initialRange) : [];
+
+    function scale(d) {
+      const key = String(d);
+      if (!index.has(key)) {
+        domain.push(d);
+        index.set(key, domain.length - 1);
+      }
+      return range[index.get(key) % range.length];
+    }
+
+    scale.domain = d => {
+      if (d === undefined) return domain.slice();
+      domain = [];
+      index.clear();
+      Array.from(d).forEach(scale);
+      return scale;
+    };
+    scale.range = r => (r === undefined ? range.slice() : (range = Array.from(r), scale));
+    return scale;
+  }
+
+  d3.scaleLinear = scaleLinear;
+  d3.scaleSequential = scaleSequential;
+  d3.scaleOrdinal = scaleOrdinal;
+
+  function scaleBand() {
+    let domain = [];
+    const index = new Map();
+    let range = [0, 1];
+    let paddingInner = 0;
+    let paddingOuter = 0;
+    let align = 0.5;
+    let round = false;
+    let step = 1;
+    let bandwidth = 1;
+    let positions = [];
+
+    function rescale() {
+      const n = domain.length;
+      const reverse = range[1] < range[0];
+      let start = reverse ? range[1] : range[0];
+      const stop = reverse ? range[0] : range[1];
+      step = (stop - start) / Math.max(1, n - paddingInner + paddingOuter * 2);
+      if (round) step = Math.floor(step);
+      start += (stop - start - step * (n - paddingInner)) * align;
+      bandwidth = step * (1 - paddingInner);
+      if (round) {
+        start = Math.round(start);
+        bandwidth = Math.round(bandwidth);
+      }
+      positions = Array.from({ length: n }, (_, i) => start + step * i);
+      if (reverse) positions.reverse();
+      return scale;
+    }
+
+    function scale(d) {
+      const i = index.get(String(d));
+      return i === undefined ? undefined : positions[i];
+    }
+
+    scale.domain = d => {
+      if (d === undefined) return domain.slice();
+      domain = [];
+      index.clear();
+      Array.from(d).forEach(value => {
+        const key = String(value);
+        if (!index.has(key)) index.set(key, domain.push(value) - 1);
+      });
+      return rescale();
+    };
+    scale.range = r => (r === undefined ? range.slice() : (range = Array.from(r, Number), rescale()));
+    scale.rangeRound = r => {
+      round = true;
+      return scale.range(r);
+    };
+    scale.round = r => (r === undefined ? round : (round = !!r, rescale()));
+    scale.padding = p => (p === undefined ? paddingInner : (paddingInner = paddingOuter = +p, rescale()));
+    scale.paddingInner = p => (p === undefined ? paddingInner : (paddingInner = +p, rescale()));
+    scale.paddingOuter = p => (p === undefined ? paddingOuter : (paddingOuter = +p, rescale()));
+    scale.align = a => (a === undefined ? align : (align = +a, rescale()));
+    scale.bandwidth = () => bandwidth;
+    scale.step = () => step;
+    return rescale();
+  }
+
+  // Time scale in UTC; ticks snap to seconds, minutes, hours, days, weeks,
+  // months or years
+  const SECOND = 1e3;
+  const MINUTE = 60 * SECOND;
+  const HOUR = 60 * MINUTE;
+  const DAY = 24 * HOUR;
+  const WEEK = 7 * DAY;
+  const MONTH = 30 * DAY;
+  const YEAR = 365 * DAY;
+
+  const timeIntervals = [
+    [SECOND, 1, '%H:%M:%S'], [5 * SECOND, 1, '%H:%M:%S'], [15 * SECOND, 1, '%H:%M:%S'],
+    [30 * SECOND, 1, '%H:%M:%S'], [MINUTE, 1, '%H:%M'], [5 * MINUTE, 1, '%H:%M'],
+    [15 * MINUTE, 1, '%H:%M'], [30 * MINUTE, 1, '%H:%M'], [HOUR, 1, '%H:%M'],
+    [3 * HOUR, 1, '%H:%M'], [6 * HOUR, 1, '%H:%M'], [12 * HOUR, 1, '%H:%M'],
+    [DAY, 1, '%b %d'], [2 * DAY, 1, '%b %d'], [WEEK, 1, '%b %d'],
+    [MONTH, 1, '%b %Y'], [3 * MONTH, 3, '%b %Y'],
+  ];
+
+  function timeInterval(start, stop, count) {
+    const target = Math.abs(stop - start) / Math.max(1, count);
+    if (target > 3 * MONTH * 1.5) {
+      const years = Math.max(1, tickStep(0, Math.abs(stop - start) / YEAR, count));
+      return { duration: years * YEAR, months: 12 * Math.round(years), format: '%Y' };
+    }
+    let best = timeIntervals[0];
+    timeIntervals.forEach(interval => {
+      if (Math.abs(Math.log(interval[0] / target)) < Math.abs(Math.log(best[0] / target))) {
+        best = interval;
+      }
+    });
+    return { duration: best[0], months: best[0] >= MONTH ? best[1] : 0, format: best[2] };
+  }
+
+  function timeTicks(start, stop, count) {
+    const reverse = stop < start;
+    if (reverse) [start, stop] = [stop, start];
+    const interval = timeInterval(start, stop, count);
+    const result = [];
+    if (interval.months) {
+      const first = new Date(start);
+      let year = first.getUTCFullYear();
+      let month = first.getUTCMonth();
+      month -= month % interval.months;
+      if (interval.months >= 12) year -= year % (interval.months / 12);
+      let t;
+      while ((t = Date.UTC(year, month, 1)) <= stop) {
+        if (t >= start) result.push(new Date(t));
+        month += interval.months;
+        year += Math.floor(month / 12);
+        month %= 12;
+      }
+    } else {
+      for (let t = Math.ceil(start / interval.duration) * interval.duration; t <= stop; t += interval.duration) {
+        result.push(new Date(t));
+      }
+    }
+    return reverse ? result.reverse() : result;
+  }
+
+  const toTime = value => +(value instanceof Date ? value : new Date(value));
+
+  function scaleUtc() {
+    const linear = scaleLinear();
+
+    function scale(x) {
+      return linear(toTime(x));
+    }
+
+    scale.invert = y => new Date(linear.invert(y));
+    scale.domain = d => (d === undefined
+      ? linear.domain().map(t => new Date(t))
+      : (linear.domain(Array.from(d, toTime)), scale));
+    scale.range = r => (r === undefined ? linear.range() : (linear.range(r), scale));
+    scale.interpolate = f => (f === undefined ? linear.interpolate() : (linear.interpolate(f), scale));
+    scale.clamp = c => (c === undefined ? linear.clamp() : (linear.clamp(c), scale));
+    scale.ticks = count => {
+      const d = linear.domain();
+      return timeTicks(d[0], d[d.length - 1], count == null ? 10 : count);
+    };
+    scale.tickFormat = (count, specifier) => {
+      if (specifier) return utcFormat(specifier);
+      const d = linear.domain();
+      return utcFormat(timeInterval(d[0], d[d.length - 1], count == null ? 10 : count).format);
+    };
+    scale.nice = () => scale;
+    scale.copy = () => scaleUtc().domain(scale.domain()).range(scale.range());
+    return scale;
+  }
+
+  d3.scaleBand = scaleBand;
+  d3.scaleUtc = scaleUtc;
+  d3.scaleTime = scaleUtc;
+
+  // ---------------------------------------------------------------------
+  // Arrays, ticks and number formatting
+
+  const accessor = f => (f ? (d, i, a) => f(d, i, a) : d => d);
+
+  d3.max = (values, f) => {
+    let max;
+    Array.from(values).forEach((d, i, a) => {
+      const v = accessor(f)(d, i, a);
+      if (v != null && v === v && (max === undefined || v > max)) max = v;
+    });
+    return max;
+  };
+
+  d3.min = (values, f) => {
+    let min;
+    Array.from(values).forEach((d, i, a) => {
+      const v = accessor(f)(d, i, a);
+      if (v != null && v === v && (min === undefined || v < min)) min = v;
+    });
+    return min;
+  };
+
+  d3.extent = (values, f) => [d3.min(values, f), d3.max(values, f)];
+
+  d3.sum = (values, f) => {
+    let sum = 0;
+    Array.from(values).forEach((d, i, a) => {
+      const v = +accessor(f)(d, i, a);
+      if (v) sum += v;
+    });
+    return sum;
+  };
+
+  d3.range = (start, stop, step) => {
+    if (stop === undefined) {
+      stop = start;
+      start = 0;
+    }
+    step = step === undefined ? 1 : step;
+    const n = Math.max(0, Math.ceil((stop - start) / step));
+    return Array.from({ length: n }, (_, i) => start + i * step);
+  };
+
+  function tickStep(start, stop, count) {
+    const step0 = Math.abs(stop - start) / Math.max(0, count);
+    if (!(step0 > 0) || !isFinite(step0)) return 0;
+    const power = Math.pow(10, Math.floor(Math.log10(step0)));
+    const error = step0 / power;
+    const factor = error >= Math.sqrt(50) ? 10 : error >= Math.sqrt(10) ? 5 : error >= Math.sqrt(2) ? 2 : 1;
+    return factor * power;
+  }
+
+  function ticks(start, stop, count) {
+    const reverse = stop < start;
+    if (reverse) [start, stop] = [stop, start];
+    const step = tickStep(start, stop, count);
+    if (!step) return [start];
+    const result = [];
+    const first = Math.ceil(start / step);
+    const last = Math.floor(stop / step);
+    for (let i = first; i <= last; ++i) result.push(+(i * step).toPrecision(12));
+    return reverse ? result.reverse() : result;
+  }
+
+  d3.ticks = ticks;
+  d3.tickStep = tickStep;
+
+  // Supports the specifiers used by ocs: [,][.precision][d|f|%|s]
+  function format(specifier) {
+    const m = /^(,)?(?:\.(\d+))?([dfs%]?)$/.exec(String(specifier)) || [];
+    const grouping = !!m[1];
+    const precision = m[2] === undefined ? undefined : +m[2];
+    const type = m[3] || '';
+    const number = (x, digits) => x.toLocaleString('en-US', {
+      useGrouping: grouping,
+      minimumFractionDigits: digits === undefined ? 0 : digits,
+      maximumFractionDigits: digits === undefined ? 12 : digits,
+    });
+    return value => {
+      const x = +value;
+      if (!isFinite(x)) return String(value);
+      switch (type) {
+        case 'd': return number(Math.round(x), 0);
+        case 'f': return number(x, precision === undefined ? 6 : precision);
+        case '%': return number(x * 100, precision === undefined ? 0 : precision) + '%';
+        case 's': {
+          const prefixes = ['', 'k', 'M', 'G', 'T'];
+          let k = 0;
+          let v = x;
+          while (Math.abs(v) >= 1000 && k < prefixes.length - 1) {
+            v /= 1000;
+            k += 1;
+          }
+          return number(+v.toPrecision(precision || 3)) + prefixes[k];
+        }
+        default: return number(x, precision);
+      }
+    };
+  }
+
+  d3.format = format;
+
+  const monthNames = ['Jan', 'Feb', 'Mar', 'Apr', 'May', 'Jun', 'Jul', 'Aug', 'Sep', 'Oct', 'Nov', 'Dec'];
+
+  // Supports %Y %y %m %b %d %e %H %M %S and %%
+  function utcFormat(specifier) {
+    const pad = v => String(v).padStart(2, '0');
+    return value => {
+      const date = value instanceof Date ? value : new Date(value);
+      return String(specifier).replace(/%([YymbdeHMS%])/g, (_, c) => {
+        switch (c) {
+          case 'Y': return String(date.getUTCFullYear());
+          case 'y': return pad(date.getUTCFullYear() % 100);
+          case 'm': return pad(date.getUTCMonth() + 1);
+          case 'b': return monthNames[date.getUTCMonth()];
+          case 'd': return pad(date.getUTCDate());
+          case 'e': return String(date.getUTCDate()).padStart(2, ' ');
+          case 'H': return pad(date.getUTCHours());
+          case 'M': return pad(date.getUTCMinutes());
+          case 'S': return pad(date.getUTCSeconds());
+          default: return '%';
+        }
+      });
+    };
+  }
+
+  d3.utcFormat = utcFormat;
+
+  d3.ascending = (a, b) => (a == null || b == null ? NaN : a < b ? -1 : a > b ? 1 : a >= b ? 0 : NaN);
+  d3.descending = (a, b) => (a == null || b == null ? NaN : b < a ? -1 : b > a ? 1 : b >= a ? 0 : NaN);
+
+  d3.group = (values, key) => {
+    const groups = new Map();
+    Array.from(values).forEach(d => {
+      const k = key(d);
+      if (!groups.has(k)) groups.set(k, []);
+      groups.get(k).push(d);
+    });
+    return groups;
+  };
+
+  // ---------------------------------------------------------------------
+  // Shapes and axes
+
+  d3.line = (x, y) => {
+    let fx = x || (d => d[0]);
+    let fy = y || (d => d[1]);
+    let defined = () => true;
+
+    function line(data) {
+      let path = '';
+      let move = true;
+      Array.from(data).forEach((d, i, a) => {
+        if (!defined(d, i, a)) {
+          move = true;
+          return;
+        }
+        path += (move ? 'M' : 'L') + fx(d, i, a) + ',' + fy(d, i, a);
+        move = false;
+      });
+      return path || null;
+    }
+
+    line.x = f => (f === undefined ? fx : (fx = typeof f === 'function' ? f : () => f, line));
+    line.y = f => (f === undefined ? fy : (fy = typeof f === 'function' ? f : () => f, line));
+    line.defined = f => (f === undefined ? defined : (defined = typeof f === 'function' ? f : () => f, line));
+    return line;
+  };
+
+  function axis(orient, scale) {
+    let tickArguments = [];
+    let tickValues = null;
+    let tickFormat = null;
+    let tickSizeInner = 6;
+    let tickSizeOuter = 6;
+    let tickPadding = 3;
+    const k = orient === 'top' || orient === 'left' ? -1 : 1;
+    const horizontal = orient === 'top' || orient === 'bottom';
+
+    function render(context) {
+      const selection = context.selection ? context.selection() : context;
+      const values = tickValues || (scale.ticks ? scale.ticks(...tickArguments) : scale.domain());
+      const label = tickFormat || (scale.tickFormat ? scale.tickFormat(...tickArguments) : d => d);
+      const offset = scale.bandwidth ? scale.bandwidth() / 2 : 0;
+      const range = scale.range();
+      const r0 = +range[0];
+      const r1 = +range[range.length - 1];
+      const spacing = Math.max(tickSizeInner, 0) + tickPadding;
+
+      selection.each(function () {
+        const g = d3.select(this);
+        g.selectAll('*').remove();
+        g.attr('fill', 'none')
+          .attr('font-size', 10)
+          .attr('font-family', 'sans-serif')
+          .attr('text-anchor', orient === 'right' ? 'start' : orient === 'left' ? 'end' : 'middle');
+        g.append('path')
+          .attr('class', 'domain')
+          .attr('stroke', 'currentColor')
+          .attr('d', horizontal
+            ? `M${r0},${k * tickSizeOuter}V0H${r1}V${k * tickSizeOuter}`
+            : `M${k * tickSizeOuter},${r0}H0V${r1}H${k * tickSizeOuter}`);
+        const tick = g.selectAll('.tick')
+          .data(values)
+          .join('g')
+          .attr('class', 'tick')
+          .attr('transform', d => (horizontal
+            ? `translate(${scale(d) + offset},0)`
+            : `translate(0,${scale(d) + offset})`));
+        tick.append('line')
+          .attr('stroke', 'currentColor')
+          .attr(horizontal ? 'y2' : 'x2', k * tickSizeInner);
+        tick.append('text')
+          .attr('fill', 'currentColor')
+          .attr(horizontal ? 'y' : 'x', k * spacing)
+          .attr('dy', orient === 'top' ? '0em' : orient === 'bottom' ? '0.71em' : '0.32em')
+          .text(label);
+      });
+    }
+
+    render.scale = s => (s === undefined ? scale : (scale = s, render));
+    render.ticks = (...args) => {
+      tickArguments = args;
+      return render;
+    };
+    render.tickArguments = args => (args === undefined ? tickArguments.slice() : (tickArguments = Array.from(args), render));
+    render.tickValues = values => (values === undefined ? tickValues : (tickValues = values && Array.from(values), render));
+    render.tickFormat = f => (f === undefined ? tickFormat : (tickFormat = f, render));
+    render.tickSize = size => (size === undefined ? tickSizeInner : (tickSizeInner = tickSizeOuter = +size, render));
+    render.tickSizeInner = size => (size === undefined ? tickSizeInner : (tickSizeInner = +size, render));
+    render.tickSizeOuter = size => (size === undefined ? tickSizeOuter : (tickSizeOuter = +size, render));
+    render.tickPadding = p => (p === undefined ? tickPadding : (tickPadding = +p, render));
+    return render;
+  }
+
+  d3.axisTop = scale => axis('top', scale);
+  d3.axisRight = scale => axis('right', scale);
+  d3.axisBottom = scale => axis('bottom', scale);
+  d3.axisLeft = scale => axis('left', scale);
+
+  // ---------------------------------------------------------------------
+  // Hierarchies
+
+  function Node(data) {
+    this.data = data;
+    this.depth = 0;
+    this.height = 0;
+    this.parent = null;
+  }
+
+  function computeHeight(node) {
+    let height = 0;
+    do node.height = height;
+    while ((node = node.parent) && node.height < ++height);
+  }
+
+  function hierarchy(data, children) {
+    const childrenOf = children || (d => d.children);
+    const root = new Node(data);
+    const nodes = [root];
+    let node;
+    while ((node = nodes.pop())) {
+      const childs = childrenOf(node.data);
+      const n = childs ? Array.from(childs).length : 0;
+      if (n) {
+        node.children = Array.from(childs, d => new Node(d));
+        for (let i = n - 1; i >= 0; --i) {
+          const child = node.children[i];
+          child.parent = node;
+          child.depth = node.depth + 1;
+          nodes.push(child);
+        }
+      }
+    }
+    return root.eachBefore(computeHeight);
+  }
+
+  Node.prototype = {
+    constructor: Node,
+
+    each(callback, that) {
+      let index = -1;
+      const queue = [this];
+      let node;
+      while ((node = queue.shift())) {
+        callback.call(that, node, ++index, this);
+        if (node.children) queue.push(...node.children);
+      }
+      return this;
+    },
+
+    eachBefore(callback, that) {
+      let index = -1;
+      const stack = [this];
+      let node;
+      while ((node = stack.pop())) {
+        callback.call(that, node, ++index, this);
+        if (node.children) {
+          for (let i = node.children.length - 1; i >= 0; --i) stack.push(node.children[i]);
+        }
+      }
+      return this;
+    },
+
+    eachAfter(callback, that) {
+      const stack = [this];
+      const order = [];
+      let node;
+      while ((node = stack.pop())) {
+        order.push(node);
+        if (node.children) stack.push(...node.children);
+      }
+      let index = -1;
+      while ((node = order.pop())) callback.call(that, node, ++index, this);
+      return this;
+    },
+
+    sum(value) {
+      return this.eachAfter(node => {
+        let sum = +value(node.data) || 0;
+        if (node.children) node.children.forEach(child => { sum += child.value; });
+        node.value = sum;
+      });
+    },
+
+    count() {
+      return this.eachAfter(node => {
+        node.value = node.children ? d3.sum(node.children, c => c.value) : 1;
+      });
+    },
+
+    sort(compare) {
+      return this.eachBefore(node => {
+        if (node.children) node.children.sort(compare);
+      });
+    },
+
+    ancestors() {
+      const nodes = [];
+      let node = this;
+      while (node) {
+        nodes.push(node);
+        node = node.parent;
+      }
+      return nodes;
+    },
+
+    descendants() {
+      const nodes = [];
+      this.each(node => nodes.push(node));
+      return nodes;
+    },
+
+    leaves() {
+      const leaves = [];
+      this.eachBefore(node => {
+        if (!node.children) leaves.push(node);
+      });
+      return leaves;
+    },
+
+    links() {
+      const links = [];
+      this.each(node => {
+        if (node !== this) links.push({ source: node.parent, target: node });
+      });
+      return links;
+    },
+
+    find(callback, that) {
+      let found;
+      this.each((node, index) => {
+        if (found === undefined && callback.call(that, node, index, this)) found = node;
+      });
+      return found;
+    },
+  };
+
+  d3.hierarchy = hierarchy;
+
+  // Circle packing, see d3-hierarchy pack, siblings and enclose
+
+  function lcg() {
+    let s = 1;
+    return () => (s = (1664525 * s + 1013904223) % 4294967296) / 4294967296;
+  }
+
+  function shuffle(array, random) {
+    let m = array.length;
+    while (m) {
+      const i = random() * m-- | 0;
+      const t = array[m];
+      array[m] = array[i];
+      array[i] = t;
+    }
+    return array;
+  }
+
+  function enclosesNot(a, b) {
+    const dr = a.r - b.r;
+    const dx = b.x - a.x;
+    const dy = b.y - a.y;
+    return dr < 0 || dr * dr < dx * dx + dy * dy;
+  }
+
+  function enclosesWeak(a, b) {
+    const dr = a.r - b.r + Math.max(a.r, b.r, 1) * 1e-9;
+    const dx = b.x - a.x;
+    const dy = b.y - a.y;
+    return dr > 0 && dr * dr > dx * dx + dy * dy;
+  }
+
+  function enclosesWeakAll(a, B) {
+    return B.every(b => enclosesWeak(a, b));
+  }
+
+  function encloseBasis2(a, b) {
+    const x21 = b.x - a.x;
+    const y21 = b.y - a.y;
+    const r21 = b.r - a.r;
+    const l = Math.sqrt(x21 * x21 + y21 * y21);
+    return {
+      x: (a.x + b.x + x21 / l * r21) / 2,
+      y: (a.y + b.y + y21 / l * r21) / 2,
+      r: (l + a.r + b.r) / 2,
+    };
+  }
+
+  function encloseBasis3(a, b, c) {
+    const x1 = a.x;
+    const y1 = a.y;
+    const r1 = a.r;
+    const a2 = x1 - b.x;
+    const a3 = x1 - c.x;
+    const b2 = y1 - b.y;
+    const b3 = y1 - c.y;
+    const c2 = b.r - r1;
+    const c3 = c.r - r1;
+    const d1 = x1 * x1 + y1 * y1 - r1 * r1;
+    const d2 = d1 - b.x * b.x - b.y * b.y + b.r * b.r;
+    const d3_ = d1 - c.x * c.x - c.y * c.y + c.r * c.r;
+    const ab = a3 * b2 - a2 * b3;
+    const xa = (b2 * d3_ - b3 * d2) / (ab * 2) - x1;
+    const xb = (b3 * c2 - b2 * c3) / ab;
+    const ya = (a3 * d2 - a2 * d3_) / (ab * 2) - y1;
+    const yb = (a2 * c3 - a3 * c2) / ab;
+    const A = xb * xb + yb * yb - 1;
+    const B = 2 * (r1 + xa * xb + ya * yb);
+    const C = xa * xa + ya * ya - r1 * r1;
+    const r = -(Math.abs(A) > 1e-6 ? (B + Math.sqrt(B * B - 4 * A * C)) / (2 * A) : C / B);
+    return { x: x1 + xa + xb * r, y: y1 + ya + yb * r, r };
+  }
+
+  function encloseBasis(B) {
+    switch (B.length) {
+      case 1: return { x: B[0].x, y: B[0].y, r: B[0].r };
+      case 2: return encloseBasis2(B[0], B[1]);
+      default: return encloseBasis3(B[0], B[1], B[2]);
+    }
+  }
+
+  function extendBasis(B, p) {
+    if (enclosesWeakAll(p, B)) return [p];
+    for (let i = 0; i < B.length; ++i) {
+      if (enclosesNot(p, B[i]) && enclosesWeakAll(encloseBasis2(B[i], p), B)) return [B[i], p];
+    }
+    for (let i = 0; i < B.length - 1; ++i) {
+      for (let j = i + 1; j < B.length; ++j) {
+        if (enclosesNot(encloseBasis2(B[i], B[j]), p)
+          && enclosesNot(encloseBasis2(B[i], p), B[j])
+          && enclosesNot(encloseBasis2(B[j], p), B[i])
+          && enclosesWeakAll(encloseBasis3(B[i], B[j], p), B)) {
+          return [B[i], B[j], p];
+        }
+      }
+    }
+    throw new Error('Unable to compute enclosing circle');
+  }
+
+  function packEnclose(circles, random) {
+    circles = shuffle(Array.from(circles), random);
+    const n = circles.length;
+    let B = [];
+    let e;
+    let i = 0;
+    while (i < n) {
+      const p = circles[i];
+      if (e && enclosesWeak(e, p)) {
+        ++i;
+      } else {
+        B = extendBasis(B, p);
+        e = encloseBasis(B);
+        i = 0;
+      }
+    }
+    return e;
+  }
+
+  function place(b, a, c) {
+    const dx = b.x - a.x;
+    const dy = b.y - a.y;
+    const d2 = dx * dx + dy * dy;
+    if (d2) {
+      let a2 = a.r + c.r;
+      a2 *= a2;
+      let b2 = b.r + c.r;
+      b2 *= b2;
+      if (a2 > b2) {
+        const x = (d2 + b2 - a2) / (2 * d2);
+        const y = Math.sqrt(Math.max(0, b2 / d2 - x * x));
+        c.x = b.x - x * dx - y * dy;
+        c.y = b.y - x * dy + y * dx;
+      } else {
+        const x = (d2 + a2 - b2) / (2 * d2);
+        const y = Math.sqrt(Math.max(0, a2 / d2 - x * x));
+        c.x = a.x + x * dx - y * dy;
+        c.y = a.y + x * dy + y * dx;
+      }
+    } else {
+      c.x = a.x + c.r;
+      c.y = a.y;
+    }
+  }
+
+  function intersects(a, b) {
+    const dr = a.r + b.r - 1e-6;
+    const dx = b.x - a.x;
+    const dy = b.y - a.y;
+    return dr > 0 && dr * dr > dx * dx + dy * dy;
+  }
+
+  function score(node) {
+    const a = node._;
+    const b = node.next._;
+    const ab = a.r + b.r;
+    const dx = (a.x * b.r + b.x * a.r) / ab;
+    const dy = (a.y * b.r + b.y * a.r) / ab;
+    return dx * dx + dy * dy;
+  }
+
+  function ChainNode(circle) {
+    this._ = circle;
+    this.next = null;
+    this.previous = null;
+  }
+
+  function packSiblings(circles, random) {
+    const n = circles.length;
+    if (!n) return 0;
+    let a = circles[0];
+    a.x = 0;
+    a.y = 0;
+    if (n === 1) return a.r;
+    let b = circles[1];
+    a.x = -b.r;
+    b.x = a.r;
+    b.y = 0;
+    if (n === 2) return a.r + b.r;
+    let c = circles[2];
+    place(b, a, c);
+
+    a = new ChainNode(a);
+    b = new ChainNode(b);
+    c = new ChainNode(c);
+    a.next = c.previous = b;
+    b.next = a.previous = c;
+    c.next = b.previous = a;
+
+    pack: for (let i = 3; i < n; ++i) {
+      place(a._, b._, circles[i]);
+      c = new ChainNode(circles[i]);
+      let j = b.next;
+      let k = a.previous;
+      let sj = b._.r;
+      let sk = a._.r;
+      do {
+        if (sj <= sk) {
+          if (intersects(j._, c._)) {
+            b = j;
+            a.next = b;
+            b.previous = a;
+            --i;
+            continue pack;
+          }
+          sj += j._.r;
+          j = j.next;
+        } else {
+          if (intersects(k._, c._)) {
+            a = k;
+            a.next = b;
+            b.previous = a;
+            --i;
+            continue pack;
+          }
+          sk += k._.r;
+          k = k.previous;
+        }
+      } while (j !== k.next);
+
+      c.previous = a;
+      c.next = b;
+      a.next = b.previous = b = c;
+
+      let aa = score(a);
+      while ((c = c.next) !== b) {
+        const ca = score(c);
+        if (ca < aa) {
+          a = c;
+          aa = ca;
+        }
+      }
+      b = a.next;
+    }
+
+    const chain = [b._];
+    c = b;
+    while ((c = c.next) !== b) chain.push(c._);
+    const e = packEnclose(chain, random);
+    circles.forEach(circle => {
+      circle.x -= e.x;
+      circle.y -= e.y;
+    });
+    return e.r;
+  }
+
+  function pack() {
+    let dx = 1;
+    let dy = 1;
+    let padding = () => 0;
+    let radius = null;
+
+    function layout(root) {
+      const random = lcg();
+      root.x = dx / 2;
+      root.y = dy / 2;
+      const leafRadius = radius || (d => Math.sqrt(d.value));
+      root.eachBefore(node => {
+        if (!node.children) node.r = Math.max(0, +leafRadius(node) || 0);
+      });
+      const packChildren = (pad, k) => node => {
+        if (!node.children) return;
+        const r = pad(node) * k || 0;
+        if (r) node.children.forEach(child => { child.r += r; });
+        const e = packSiblings(node.children, random);
+        if (r) node.children.forEach(child => { child.r -= r; });
+        node.r = e + r;
+      };
+      if (radius) {
+        root.eachAfter(packChildren(padding, 0.5)).eachBefore(translate(1));
+      } else {
+        root.eachAfter(packChildren(() => 0, 1))
+          .eachAfter(packChildren(padding, root.r / Math.min(dx, dy)))
+          .eachBefore(translate(Math.min(dx, dy) / (2 * root.r)));
+      }
+      return root;
+    }
+
+    function translate(k) {
+      return node => {
+        const parent = node.parent;
+        node.r *= k;
+        if (parent) {
+          node.x = parent.x + k * node.x;
+          node.y = parent.y + k * node.y;
+        }
+      };
+    }
+
+    layout.size = size => (size === undefined ? [dx, dy] : (dx = +size[0], dy = +size[1], layout));
+    layout.padding = p => (p === undefined ? padding : (padding = typeof p === 'function' ? p : () => +p, layout));
+    layout.radius = r => (r === undefined ? radius : (radius = r, layout));
+    return layout;
+  }
+
+  d3.pack = pack;
+  d3.packSiblings = circles => packSiblings(circles, lcg());
+  d3.packEnclose = circles => packEnclose(circles, lcg());
+
+  // Treemap, see d3-hierarchy treemap, dice, slice and squarify
+
+  function treemapDice(parent, x0, y0, x1, y1) {
+    const k = parent.value && (x1 - x0) / parent.value;
+    parent.children.forEach(node => {
+      node.y0 = y0;
+      node.y1 = y1;
+      node.x0 = x0;
+      node.x1 = x0 += node.value * k;
+    });
+  }
+
+  function treemapSlice(parent, x0, y0, x1, y1) {
+    const k = parent.value && (y1 - y0) / parent.value;
+    parent.children.forEach(node => {
+      node.x0 = x0;
+      node.x1 = x1;
+      node.y0 = y0;
+      node.y1 = y0 += node.value * k;
+    });
+  }
+
+  const phi = (1 + Math.sqrt(5)) / 2;
+
+  function treemapSquarify(parent, x0, y0, x1, y1) {
+    const nodes = parent.children;
+    const n = nodes.length;
+    let value = parent.value;
+    let i0 = 0;
+    let i1 = 0;
+    while (i0 < n) {
+      const dx = x1 - x0;
+      const dy = y1 - y0;
+      let sumValue;
+      do sumValue = nodes[i1++].value; while (!sumValue && i1 < n);
+      let minValue = sumValue;
+      let maxValue = sumValue;
+      const alpha = Math.max(dy / dx, dx / dy) / (value * phi);
+      let beta = sumValue * sumValue * alpha;
+      let minRatio = Math.max(maxValue / beta, beta / minValue);
+      for (; i1 < n; ++i1) {
+        const nodeValue = nodes[i1].value;
+        sumValue += nodeValue;
+        if (nodeValue < minValue) minValue = nodeValue;
+        if (nodeValue > maxValue) maxValue = nodeValue;
+        beta = sumValue * sumValue * alpha;
+        const newRatio = Math.max(maxValue / beta, beta / minValue);
+        if (newRatio > minRatio) {
+          sumValue -= nodeValue;
+          break;
+        }
+        minRatio = newRatio;
+      }
+      const row = { value: sumValue, children: nodes.slice(i0, i1) };
+      if (dx < dy) treemapDice(row, x0, y0, x1, dy ? (y0 += dy * sumValue / value) : y1);
+      else treemapSlice(row, x0, y0, dx ? (x0 += dx * sumValue / value) : x1, y1);
+      value -= sumValue;
+      i0 = i1;
+    }
+  }
+
+  function treemap() {
+    let dx = 1;
+    let dy = 1;
+    let round = false;
+    let tile = treemapSquarify;
+    const constant = x => () => x;
+    let paddingInner = constant(0);
+    let paddingTop = constant(0);
+    let paddingRight = constant(0);
+    let paddingBottom = constant(0);
+    let paddingLeft = constant(0);
+    let paddingStack = [0];
+
+    function positionNode(node) {
+      let p = paddingStack[node.depth];
+      let x0 = node.x0 + p;
+      let y0 = node.y0 + p;
+      let x1 = node.x1 - p;
+      let y1 = node.y1 - p;
+      if (x1 < x0) x0 = x1 = (x0 + x1) / 2;
+      if (y1 < y0) y0 = y1 = (y0 + y1) / 2;
+      node.x0 = x0;
+      node.y0 = y0;
+      node.x1 = x1;
+      node.y1 = y1;
+      if (node.children) {
+        p = paddingStack[node.depth + 1] = paddingInner(node) / 2;
+        x0 += paddingLeft(node) - p;
+        y0 += paddingTop(node) - p;
+        x1 -= paddingRight(node) - p;
+        y1 -= paddingBottom(node) - p;
+        if (x1 < x0) x0 = x1 = (x0 + x1) / 2;
+        if (y1 < y0) y0 = y1 = (y0 + y1) / 2;
+        tile(node, x0, y0, x1, y1);
+      }
+    }
+
+    function layout(root) {
+      paddingStack = [0];
+      root.x0 = root.y0 = 0;
+      root.x1 = dx;
+      root.y1 = dy;
+      root.eachBefore(positionNode);
+      if (round) {
+        root.eachBefore(node => {
+          node.x0 = Math.round(node.x0);
+          node.y0 = Math.round(node.y0);
+          node.x1 = Math.round(node.x1);
+          node.y1 = Math.round(node.y1);
+        });
+      }
+      return root;
+    }
+
+    const accessor = p => (typeof p === 'function' ? p : constant(+p));
+    layout.size = size => (size === undefined ? [dx, dy] : (dx = +size[0], dy = +size[1], layout));
+    layout.round = r => (r === undefined ? round : (round = !!r, layout));
+    layout.tile = t => (t === undefined ? tile : (tile = t, layout));
+    layout.padding = p => layout.paddingInner(p).paddingOuter(p);
+    layout.paddingInner = p => (p === undefined ? paddingInner : (paddingInner = accessor(p), layout));
+    layout.paddingOuter = p => layout.paddingTop(p).paddingRight(p).paddingBottom(p).paddingLeft(p);
+    layout.paddingTop = p => (p === undefined ? paddingTop : (paddingTop = accessor(p), layout));
+    layout.paddingRight = p => (p === undefined ? paddingRight : (paddingRight = accessor(p), layout));
+    layout.paddingBottom = p => (p === undefined ? paddingBottom : (paddingBottom = accessor(p), layout));
+    layout.paddingLeft = p => (p === undefined ? paddingLeft : (paddingLeft = accessor(p), layout));
+    return layout;
+  }
+
+  d3.treemap = treemap;
+  d3.treemapSquarify = treemapSquarify;
+  d3.treemapDice = treemapDice;
+  d3.treemapSlice = treemapSlice;
+
+  d3.version = '7-ocs';
+  global.d3 = d3;
+})(typeof window !== 'undefined' ? window : globalThis);
@@ -0,0 +1,158 @@
+// Tables and entity details of the report
+//
+// In scope: `d3`, `report` (the results of all analyses plus `authors` and
+// `trend` by entity) and `pages` (analysis id -> link of its section/page).
+
+const entityColumns = ['entity', 'coupled'];
+
+function renderTable(element, records) {
+  if (!records || records.length === 0) {
+    d3.select(element).append('p').attr('class', 'hint').text('No results');
+    return;
+  }
+  const columns = Object.keys(records[0]);
+  const table = d3.select(element).append('table');
+  table.append('thead').append('tr')
+    .selectAll('th')
+    .data(columns)
+    .join('th')
+      .text(d => d);
+  table.append('tbody')
+    .selectAll('tr')
+    .data(records)
+    .join('tr')
+      .attr('class', d => (d.entity !== undefined ? 'entity' : null))
+      .on('click', (event, d) => {
+        // a click on the coupled entity selects that one
+        const cell = event.target.__data__;
+        if (d.entity !== undefined) {
+          select(cell && entityColumns.includes(cell.column) ? cell.value : d.entity);
+        }
+      })
+    .selectAll('td')
+    .data(d => columns.map(column => ({ column, value: d[column] })))
+    .join('td')
+      .attr('class', d => (typeof d.value === 'number' ? 'number' : null))
+      .text(d => d.value);
+}
+
+document.querySelectorAll('[data-table]').forEach(element => {
+  renderTable(element, report[element.dataset.table]);
+});
+
+const byEntity = records => new Map((records || []).map(r => [r.entity, r]));
+const hotspots = byEntity(report.hotspots);
+const ages = byEntity(report.age);
+const owners = byEntity(report.ownership);
+const churns = byEntity(report.churn);
+
+function facts(entity) {
+  const hotspot = hotspots.get(entity);
+  const age = ages.get(entity);
+  const owner = owners.get(entity);
+  const churn = churns.get(entity);
+  return [
+    hotspot && ['Revisions', hotspot.revisions],
+    hotspot && ['Lines of code', hotspot.code],
+    age && ['Last change', `${age['last-change']} (${age['age-months']} months ago)`],
+    owner && ['Main developer', `${owner['main-dev']} (${Math.round(owner.ownership * 100)}%)`],
+    churn && ['Added / deleted', `${churn.added} / ${churn.deleted}`],
+  ].filter(Boolean);
+}
+
+function partners(entity) {
+  return (report.coupling || [])
+    .filter(c => c.entity === entity || c.coupled === entity)
+    .map(c => ({ entity: c.entity === entity ? c.coupled : c.entity, degree: c.degree }));
+}
+
+// Indentation complexity at the end of every month
+function trendChart(parent, periods) {
+  const width = 330;
+  const height = 140;
+  const margin = { top: 10, right: 10, bottom: 20, left: 40 };
+  const parse = period => new Date(`${period}T00:00:00Z`);
+  const x = d3.scaleUtc()
+      .domain(d3.extent(periods, d => parse(d.period)))
+      .range([margin.left, width - margin.right]);
+  const y = d3.scaleLinear()
+      .domain([0, d3.max(periods, d => d.complexity) || 1])
+      .nice()
+      .range([height - margin.bottom, margin.top]);
+  const svg = parent.append('svg')
+      .attr('width', width)
+      .attr('height', height)
+      .attr('viewBox', [0, 0, width, height])
+      .attr('style', 'font: 10px sans-serif;');
+  svg.append('g')
+      .attr('transform', `translate(0,${height - margin.bottom})`)
+      .call(d3.axisBottom(x).ticks(4));
+  svg.append('g')
+      .attr('transform', `translate(${margin.left},0)`)
+      .call(d3.axisLeft(y).ticks(4));
+  svg.append('path')
+      .attr('fill', 'none')
+      .attr('stroke', 'steelblue')
+      .attr('stroke-width', 1.5)
+      .attr('d', d3.line().x(d => x(parse(d.period))).y(d => y(d.complexity))(periods));
+}
+
+function showDetails(entity) {
+  const details = d3.select('#details');
+  details.selectAll('*').remove();
+  details.append('h2').text(entity);
+
+  const known = facts(entity);
+  if (known.length > 0) {
+    details.append('table')
+      .selectAll('tr')
+      .data(known)
+      .join('tr')
+      .selectAll('td')
+      .data(d => d)
+      .join('td')
+        .text(d => d);
+  }
+
+  details.append('h3').text('Coupling partners');
+  const coupled = partners(entity);
+  if (report.coupling === undefined) {
+    details.append('p').attr('class', 'hint').text('Coupling is not part of the report');
+  } else if (coupled.length === 0) {
+    details.append('p').attr('class', 'hint').text('No coupled entities');
+  } else {
+    renderTable(details.append('div').node(), coupled);
+  }
+
+  details.append('h3').text('Authors');
+  renderTable(details.append('div').node(), report.authors[entity]);
+
+  details.append('h3').text('Complexity trend');
+  const periods = report.trend[entity] || [];
+  if (periods.length < 2) {
+    details.append('p').attr('class', 'hint').text('Not a hotspot or known for a single month only');
+  } else {
+    trendChart(details, periods);
+  }
+}
+
+function select(entity) {
+  d3.selectAll('tr.entity').classed('selected', d => d.entity === entity);
+  showDetails(entity);
+  const hash = `#entity=${encodeURIComponent(entity)}`;
+  history.replaceState(null, '', hash);
+  // keep the selection when moving to another page of a site
+  document.querySelectorAll('nav a').forEach(a => {
+    const page = a.getAttribute('href').split('#')[0];
+    if (page !== '') {
+      a.setAttribute('href', page + hash);
+    }
+  });
+}
+
+document.addEventListener('ocs-select', event => select(event.detail));
+
+const selected = /^#entity=(.*)$/.exec(location.hash);
+if (selected) {
+  select(decodeURIComponent(selected[1]));
+}
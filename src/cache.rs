@@ -0,0 +1,325 @@
+//! Persistent cache of change records
+//!
+//! ## Requirements
+//!
+//! - Re-running an analysis shall only process commits that were not
+//!   analysed before.
+//! - The cache lives inside the git directory (`.git/ocs/`) so it is neither
+//!   committed nor shows up as untracked file.
+//! - History rewrites (rebase, amend, force push) shall never lead to wrong
+//!   results.
+//!
+//! ## Design Decisions
+//!
+//! - Records are keyed by commit id. A record is derived from the commit only,
+//!   so a cached record is either valid or unused - it can never be stale.
+//! - The cache remembers the HEAD it was written for (the tip). If the tip is
+//!   no longer part of the history of HEAD, the history was rewritten and all
+//!   records not reachable from HEAD anymore are dropped to keep the cache
+//!   small.
+//! - Storage format is JSON lines: a header line followed by one record per
+//!   line. An unreadable or incompatible cache file is discarded and rebuilt.
+//! - The file is written to a temporary file first and renamed afterwards,
+//!   so an interrupted run never leaves a truncated cache behind.
+
+use git2::{Oid, Repository};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use crate::changes::CommitChanges;
+
+/// Increment whenever the layout or the semantics of change records change
+const CACHE_VERSION: u32 = 3;
+const CACHE_DIR: &str = "ocs";
+const CACHE_FILE: &str = "changes.jsonl";
+
+#[derive(Serialize, Deserialize)]
+struct CacheHeader {
+    version: u32,
+    tip: Option<String>,
+}
+
+/// Relation between the cached tip and the current HEAD
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TipState {
+    /// Nothing cached yet
+    Empty,
+    UpToDate,
+    /// HEAD has the given number of commits on top of the cached tip
+    Behind(usize),
+    /// The cached tip is not part of the history of HEAD anymore
+    Rewritten,
+}
+
+pub struct ChangeCache {
+    enabled: bool,
+    tip: Option<String>,
+    records: HashMap<String, CommitChanges>,
+    modified: bool,
+}
+
+impl ChangeCache {
+    /// Directory holding all ocs cache files of the repository
+    pub fn directory(repo: &Repository) -> PathBuf {
+        repo.path().join(CACHE_DIR)
+    }
+
+    fn file(repo: &Repository) -> PathBuf {
+        Self::directory(repo).join(CACHE_FILE)
+    }
+
+    fn empty(enabled: bool) -> Self {
+        ChangeCache {
+            enabled,
+            tip: None,
+            records: HashMap::new(),
+            modified: false,
+        }
+    }
+
+    /// A cache that never stores anything (e.g. `--no-cache`)
+    pub fn disabled() -> Self {
+        Self::empty(false)
+    }
+
+    pub fn load(repo: &Repository) -> Self {
+        match Self::read(&Self::file(repo)) {
+            Ok(Some(cache)) => cache,
+            Ok(None) => Self::empty(true),
+            Err(e) => {
+                warn!("Discard unreadable change record cache: {}", e);
+                Self::empty(true)
+            }
+        }
+    }
+
+    fn read(path: &Path) -> Result<Option<Self>, Box<dyn Error>> {
+        let file = match File::open(path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        let mut lines = BufReader::new(file).lines();
+        let header: CacheHeader = match lines.next() {
+            Some(line) => serde_json::from_str(&line?)?,
+            None => return Ok(None),
+        };
+        if header.version != CACHE_VERSION {
+            info!(
+                "Change record cache has version {}, expected {}; rebuild it",
+                header.version, CACHE_VERSION
+            );
+            return Ok(None);
+        }
+        let mut records = HashMap::new();
+        for line in lines {
+            let record: CommitChanges = serde_json::from_str(&line?)?;
+            records.insert(record.id.clone(), record);
+        }
+        Ok(Some(ChangeCache {
+            enabled: true,
+            tip: header.tip,
+            records,
+            modified: false,
+        }))
+    }
+
+    pub fn get(&self, id: &str) -> Option<&CommitChanges> {
+        self.records.get(id)
+    }
+
+    pub fn insert(&mut self, record: CommitChanges) {
+        if self.enabled {
+            self.records.insert(record.id.clone(), record);
+            self.modified = true;
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.records.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+
+    pub fn tip(&self) -> Option<&str> {
+        self.tip.as_deref()
+    }
+
+    /// Write the cache back to disk, taking the current HEAD as new tip
+    pub fn store(&mut self, repo: &Repository) -> Result<(), Box<dyn Error>> {
+        if !self.enabled {
+            return Ok(());
+        }
+        let head = head_id(repo);
+        let head_str = head.map(|h| h.to_string());
+        if !self.modified && head_str == self.tip {
+            return Ok(());
+        }
+        if let Some(head) = head {
+            if tip_state(repo, self.tip.as_deref()) == TipState::Rewritten {
+                info!("History was rewritten; drop unreachable change records");
+                self.prune(repo, head)?;
+            }
+        }
+        self.tip = head_str;
+
+        fs::create_dir_all(Self::directory(repo))?;
+        let path = Self::file(repo);
+        let tmp_path = path.with_extension("tmp");
+        {
+            let mut writer = BufWriter::new(File::create(&tmp_path)?);
+            let header = CacheHeader {
+                version: CACHE_VERSION,
+                tip: self.tip.clone(),
+            };
+            serde_json::to_writer(&mut writer, &header)?;
+            writeln!(writer)?;
+            for record in self.records.values() {
+                serde_json::to_writer(&mut writer, record)?;
+                writeln!(writer)?;
+            }
+            writer.flush()?;
+        }
+        fs::rename(tmp_path, path)?;
+        self.modified = false;
+        Ok(())
+    }
+
+    fn prune(&mut self, repo: &Repository, head: Oid) -> Result<(), git2::Error> {
+        let mut revwalk = repo.revwalk()?;
+        revwalk.push(head)?;
+        let reachable = revwalk
+            .map(|id| id.map(|id| id.to_string()))
+            .collect::<Result<HashSet<String>, git2::Error>>()?;
+        self.records.retain(|id, _| reachable.contains(id));
+        Ok(())
+    }
+
+    /// Remove all cache files of the repository
+    pub fn clear(repo: &Repository) -> io::Result<()> {
+        let directory = Self::directory(repo);
+        if directory.exists() {
+            fs::remove_dir_all(directory)?;
+        }
+        Ok(())
+    }
+}
+
+fn head_id(repo: &Repository) -> Option<Oid> {
+    repo.head()
+        .ok()
+        .and_then(|head| head.peel_to_commit().ok())
+        .map(|commit| commit.id())
+}
+
+/// Determine how the given cached tip relates to the current HEAD
+pub fn tip_state(repo: &Repository, tip: Option<&str>) -> TipState {
+    let tip = match tip {
+        Some(tip) => tip,
+        None => return TipState::Empty,
+    };
+    let head = match head_id(repo) {
+        Some(head) => head,
+        None => return TipState::Rewritten,
+    };
+    let tip = match Oid::from_str(tip) {
+        Ok(tip) if repo.find_commit(tip).is_ok() => tip,
+        _ => return TipState::Rewritten,
+    };
+    if tip == head {
+        return TipState::UpToDate;
+    }
+    if !repo.graph_descendant_of(head, tip).unwrap_or(false) {
+        return TipState::Rewritten;
+    }
+    let new_commits = repo.revwalk().and_then(|mut revwalk| {
+        revwalk.push(head)?;
+        revwalk.hide(tip)?;
+        Ok(revwalk.count())
+    });
+    match new_commits {
+        Ok(n) => TipState::Behind(n),
+        Err(_) => TipState::Rewritten,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::changes::TestRepository;
+
+    fn tip(id: Oid) -> String {
+        id.to_string()
+    }
+
+    #[test]
+    fn verify_tip_state() {
+        let test_repo = TestRepository::default();
+        let repo = &test_repo.repo;
+        assert_eq!(tip_state(repo, None), TipState::Empty);
+        let first = test_repo.commit("first", &[("a.rs", b"1\n")]);
+        assert_eq!(tip_state(repo, Some(&tip(first))), TipState::UpToDate);
+        test_repo.commit("second", &[("a.rs", b"2\n")]);
+        let third = test_repo.commit("third", &[("a.rs", b"3\n")]);
+        assert_eq!(tip_state(repo, Some(&tip(first))), TipState::Behind(2));
+
+        // amend the third commit
+        test_repo.reset(first);
+        test_repo.commit("second and third", &[("a.rs", b"3\n")]);
+        assert_eq!(tip_state(repo, Some(&tip(third))), TipState::Rewritten);
+        assert_eq!(tip_state(repo, Some("unknown")), TipState::Rewritten);
+    }
+
+    #[test]
+    fn verify_prune_after_rewrite() {
+        let test_repo = TestRepository::default();
+        let repo = &test_repo.repo;
+        let record = |id: Oid| CommitChanges::from_commit(repo, &repo.find_commit(id).unwrap());
+        let first = test_repo.commit("first", &[("a.rs", b"1\n")]);
+        let second = test_repo.commit("second", &[("a.rs", b"2\n")]);
+        let mut cache = ChangeCache::load(repo);
+        cache.insert(record(first).unwrap());
+        cache.insert(record(second).unwrap());
+        cache.store(repo).unwrap();
+
+        test_repo.reset(first);
+        let amended = test_repo.commit("amended", &[("a.rs", b"3\n")]);
+        let mut cache = ChangeCache::load(repo);
+        assert_eq!((cache.len(), cache.tip()), (2, Some(tip(second).as_str())));
+        cache.insert(record(amended).unwrap());
+        cache.store(repo).unwrap();
+
+        let cache = ChangeCache::load(repo);
+        assert_eq!(cache.tip(), Some(tip(amended).as_str()));
+        assert!(cache.get(&tip(first)).is_some() && cache.get(&tip(amended)).is_some());
+        assert!(cache.get(&tip(second)).is_none());
+    }
+
+    #[test]
+    fn verify_stale_cache_version_is_discarded() {
+        let test_repo = TestRepository::default();
+        let repo = &test_repo.repo;
+        let first = test_repo.commit("first", &[("a.rs", b"1\n")]);
+        let mut cache = ChangeCache::load(repo);
+        cache.insert(CommitChanges::from_commit(repo, &repo.find_commit(first).unwrap()).unwrap());
+        cache.store(repo).unwrap();
+        assert_eq!(ChangeCache::load(repo).len(), 1);
+
+        let file = ChangeCache::file(repo);
+        let content = fs::read_to_string(&file).unwrap().replacen(
+            &format!("{{\"version\":{}", CACHE_VERSION),
+            &format!("{{\"version\":{}", CACHE_VERSION - 1),
+            1,
+        );
+        fs::write(&file, content).unwrap();
+        let cache = ChangeCache::load(repo);
+        assert!(cache.is_empty() && cache.tip().is_none());
+    }
+}
@@ -0,0 +1,582 @@
+//! Per-commit change records
+//!
+//! All history based analyses are computed from change records: for every
+//! commit the list of files it touched together with the number of lines
+//! added and deleted. This is the same information Code Maat reads from
+//! `git log --numstat`.
+//!
+//! ## Design Decisions
+//!
+//! - A change record only depends on the commit it is derived from. Since a
+//!   commit id is a hash over the commit content (tree, parents, metadata),
+//!   records can be cached by commit id and never become wrong.
+//! - Changes are computed against the first parent. Merge commits are kept
+//!   (they count as commits of their author) but do not carry changes, like
+//!   `git log --numstat` does.
+//! - Binary files are reported with zero added and deleted lines.
+//! - Submodule pointer bumps are always recorded, flagged as such, and
+//!   reported like `git log --numstat` does (one line added and/or deleted).
+//!   Whether they are analysed is decided when collecting, so cached records
+//!   serve both cases.
+//! - With submodule recursion the history of every (initialized) submodule
+//!   is collected as well, its paths prefixed by the submodule location.
+
+use git2::{Commit, Delta, DiffOptions, FileMode, Patch, Repository};
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::error::Error;
+use std::path::Path;
+use time::{OffsetDateTime, UtcOffset};
+
+use crate::cache::ChangeCache;
+use crate::cli::{CommonArgs, GitArgs};
+use crate::git::determine_commits_to_analyse;
+use crate::progress;
+
+/// How a single file was changed by a commit
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileChange {
+    pub path: String,
+    pub added: u64,
+    pub deleted: u64,
+    /// The change is a submodule pointer bump
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub submodule: bool,
+    /// The file is binary, so it has neither added nor deleted lines
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub binary: bool,
+}
+
+/// All changes of one commit plus the commit metadata analyses need
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CommitChanges {
+    pub id: String,
+    /// Commit time in seconds since epoch
+    pub time: i64,
+    /// Timezone offset of the commit time in minutes
+    pub offset: i32,
+    pub author: String,
+    pub email: String,
+    pub message: String,
+    pub changes: Vec<FileChange>,
+}
+
+impl CommitChanges {
+    /// Commit time in the timezone of the commit
+    pub fn date(&self) -> OffsetDateTime {
+        let offset = UtcOffset::from_whole_seconds(self.offset * 60).unwrap_or(UtcOffset::UTC);
+        OffsetDateTime::from_unix_timestamp(self.time)
+            .unwrap_or(OffsetDateTime::UNIX_EPOCH)
+            .to_offset(offset)
+    }
+
+    pub fn from_commit(repo: &Repository, commit: &Commit) -> Result<Self, git2::Error> {
+        let author = commit.author();
+        let mut record = CommitChanges {
+            id: commit.id().to_string(),
+            time: commit.time().seconds(),
+            offset: commit.time().offset_minutes(),
+            author: author.name().unwrap_or_default().to_owned(),
+            email: author.email().unwrap_or_default().to_owned(),
+            message: commit.message().unwrap_or_default().to_owned(),
+            changes: Vec::new(),
+        };
+        if commit.parent_count() > 1 {
+            return Ok(record);
+        }
+
+        let new_tree = commit.tree()?;
+        let old_tree = match commit.parent(0) {
+            Ok(parent) => Some(parent.tree()?),
+            Err(_) => None,
+        };
+        let diff = repo.diff_tree_to_tree(
+            old_tree.as_ref(),
+            Some(&new_tree),
+            Some(&mut DiffOptions::new()),
+        )?;
+
+        for (idx, delta) in diff.deltas().enumerate() {
+            let path = match delta.new_file().path().or(delta.old_file().path()) {
+                Some(p) => p.to_string_lossy().into_owned(),
+                None => continue,
+            };
+            let submodule = delta.new_file().mode() == FileMode::Commit
+                || delta.old_file().mode() == FileMode::Commit;
+            if submodule {
+                let (added, deleted) = match delta.status() {
+                    Delta::Added => (1, 0),
+                    Delta::Deleted => (0, 1),
+                    _ => (1, 1),
+                };
+                record.changes.push(FileChange {
+                    path,
+                    added,
+                    deleted,
+                    submodule,
+                    binary: false,
+                });
+                continue;
+            }
+            let (added, deleted, binary) = match Patch::from_diff(&diff, idx)? {
+                Some(patch) if patch.delta().flags().is_binary() => (0, 0, true),
+                Some(patch) => {
+                    let (_, added, deleted) = patch.line_stats()?;
+                    (added as u64, deleted as u64, false)
+                }
+                None => (0, 0, true),
+            };
+            record.changes.push(FileChange {
+                path,
+                added,
+                deleted,
+                submodule,
+                binary,
+            });
+        }
+        Ok(record)
+    }
+}
+
+/// Builder of change records for tests
+#[cfg(test)]
+impl CommitChanges {
+    /// A commit of the author at the time (seconds since epoch) without
+    /// message and changes; the id is made up of author and time
+    pub fn fixture(author: &str, time: i64) -> Self {
+        CommitChanges {
+            id: format!("{}-{}", author, time),
+            time,
+            offset: 0,
+            author: author.to_owned(),
+            email: String::new(),
+            message: String::new(),
+            changes: Vec::new(),
+        }
+    }
+
+    pub fn with_message(mut self, message: &str) -> Self {
+        self.message = message.to_owned();
+        self
+    }
+
+    pub fn with_change(mut self, path: &str, added: u64, deleted: u64) -> Self {
+        self.changes.push(FileChange {
+            path: path.to_owned(),
+            added,
+            deleted,
+            submodule: false,
+            binary: false,
+        });
+        self
+    }
+
+    /// A change of a binary file
+    pub fn with_binary_change(mut self, path: &str) -> Self {
+        self.changes.push(FileChange {
+            path: path.to_owned(),
+            added: 0,
+            deleted: 0,
+            submodule: false,
+            binary: true,
+        });
+        self
+    }
+
+    /// A change of one added line for every path
+    pub fn with_changes(self, paths: &[&str]) -> Self {
+        paths
+            .iter()
+            .fold(self, |commit, path| commit.with_change(path, 1, 0))
+    }
+}
+
+/// A git repository in a temporary directory for tests; commits are made by
+/// one author, a second apart
+#[cfg(test)]
+pub struct TestRepository {
+    pub repo: Repository,
+    time: std::cell::Cell<i64>,
+    _dir: tempfile::TempDir,
+}
+
+#[cfg(test)]
+impl Default for TestRepository {
+    fn default() -> Self {
+        let dir = tempfile::tempdir().unwrap();
+        TestRepository {
+            repo: Repository::init(dir.path()).unwrap(),
+            time: std::cell::Cell::new(1_700_000_000),
+            _dir: dir,
+        }
+    }
+}
+
+#[cfg(test)]
+impl TestRepository {
+    /// Commit the files with the given content on top of HEAD
+    pub fn commit(&self, message: &str, files: &[(&str, &[u8])]) -> git2::Oid {
+        let parents = self.head().into_iter().collect::<Vec<_>>();
+        self.commit_with_parents(Some("HEAD"), message, files, &parents)
+    }
+
+    /// Commit the submodule pointer and the submodule's `.gitmodules` entry
+    /// on top of HEAD
+    pub fn commit_submodule(&self, message: &str, path: &str, commit: git2::Oid) -> git2::Oid {
+        let gitmodules = format!("[submodule \"{0}\"]\n\tpath = {0}\n\turl = ./{0}\n", path);
+        let parents = self.head().into_iter().collect::<Vec<_>>();
+        let entries = [
+            (
+                ".gitmodules",
+                0o100644,
+                self.repo.blob(gitmodules.as_bytes()).unwrap(),
+            ),
+            (path, 0o160000, commit),
+        ];
+        self.commit_entries(Some("HEAD"), message, &entries, &parents)
+    }
+
+    /// The working directory, e.g. to check out a submodule into
+    pub fn path(&self) -> &Path {
+        self.repo.workdir().unwrap()
+    }
+
+    /// Commit the files on top of HEAD without moving HEAD, e.g. for a
+    /// branch to merge
+    pub fn commit_aside(&self, message: &str, files: &[(&str, &[u8])]) -> git2::Oid {
+        let parents = self.head().into_iter().collect::<Vec<_>>();
+        self.commit_with_parents(None, message, files, &parents)
+    }
+
+    /// Commit merging the other commit into HEAD
+    pub fn merge(&self, message: &str, other: git2::Oid) -> git2::Oid {
+        let parents = [self.head().unwrap(), other];
+        self.commit_with_parents(Some("HEAD"), message, &[], &parents)
+    }
+
+    /// Move HEAD to the commit, e.g. to rewrite history
+    pub fn reset(&self, id: git2::Oid) {
+        let commit = self.repo.find_object(id, None).unwrap();
+        self.repo
+            .reset(&commit, git2::ResetType::Soft, None)
+            .unwrap();
+    }
+
+    fn head(&self) -> Option<git2::Oid> {
+        self.repo.head().ok().and_then(|head| head.target())
+    }
+
+    fn commit_with_parents(
+        &self,
+        update_ref: Option<&str>,
+        message: &str,
+        files: &[(&str, &[u8])],
+        parents: &[git2::Oid],
+    ) -> git2::Oid {
+        let entries = files
+            .iter()
+            .map(|(path, content)| (*path, 0o100644, self.repo.blob(content).unwrap()))
+            .collect::<Vec<_>>();
+        self.commit_entries(update_ref, message, &entries, parents)
+    }
+
+    /// Commit the tree entries, each a path, mode and object id
+    fn commit_entries(
+        &self,
+        update_ref: Option<&str>,
+        message: &str,
+        entries: &[(&str, u32, git2::Oid)],
+        parents: &[git2::Oid],
+    ) -> git2::Oid {
+        let mut index = git2::Index::new().unwrap();
+        if let Some(parent) = parents.first() {
+            let parent = self.repo.find_commit(*parent).unwrap();
+            index.read_tree(&parent.tree().unwrap()).unwrap();
+        }
+        for (path, mode, id) in entries {
+            let entry = git2::IndexEntry {
+                ctime: git2::IndexTime::new(0, 0),
+                mtime: git2::IndexTime::new(0, 0),
+                dev: 0,
+                ino: 0,
+                mode: *mode,
+                uid: 0,
+                gid: 0,
+                file_size: 0,
+                id: *id,
+                flags: 0,
+                flags_extended: 0,
+                path: path.as_bytes().to_vec(),
+            };
+            index.add(&entry).unwrap();
+        }
+        let tree_id = index.write_tree_to(&self.repo).unwrap();
+        let tree = self.repo.find_tree(tree_id).unwrap();
+        self.time.set(self.time.get() + 1);
+        let signature = git2::Signature::new(
+            "ann",
+            "ann@example.com",
+            &git2::Time::new(self.time.get(), 0),
+        )
+        .unwrap();
+        let parents = parents
+            .iter()
+            .map(|id| self.repo.find_commit(*id).unwrap())
+            .collect::<Vec<_>>();
+        self.repo
+            .commit(
+                update_ref,
+                &signature,
+                &signature,
+                message,
+                &tree,
+                &parents.iter().collect::<Vec<_>>(),
+            )
+            .unwrap()
+    }
+}
+
+/// Collect the change records of all commits selected by the git arguments
+///
+/// Records already present in the on-disk cache are reused; only commits not
+/// seen before are diffed. A cache that cannot be written is reported but
+/// never fails the analysis.
+pub fn collect_changes(
+    repo: &Repository,
+    git_args: GitArgs,
+    use_cache: bool,
+) -> Result<Vec<CommitChanges>, Box<dyn Error>> {
+    let mut cache = if use_cache {
+        ChangeCache::load(repo)
+    } else {
+        ChangeCache::disabled()
+    };
+
+    let revwalk = determine_commits_to_analyse(repo, git_args)?;
+    let mut log = Vec::new();
+
+    progress::start_commit_analysing();
+    for commit in revwalk {
+        progress::increment_commit_analysing();
+        let commit = commit?;
+        let record = match cache.get(&commit.id().to_string()) {
+            Some(record) => record.clone(),
+            None => {
+                let record = CommitChanges::from_commit(repo, &commit)?;
+                cache.insert(record.clone());
+                record
+            }
+        };
+        log.push(record);
+    }
+    progress::finish_commit_analysing();
+
+    if let Err(e) = cache.store(repo) {
+        warn!("Unable to update the change record cache: {}", e);
+    }
+    Ok(log)
+}
+
+/// Collect the change records of all repositories of the project
+///
+/// The histories are combined into one log ordered by commit time, newest
+/// first. When several repositories are analysed, every path is prefixed with
+/// the name of its repository, e.g. `api/src/main.rs`.
+pub fn collect_project_changes(
+    common_args: &CommonArgs,
+    git_args: GitArgs,
+) -> Result<Vec<CommitChanges>, Box<dyn Error>> {
+    let mut log = Vec::new();
+    for repository in &common_args.repositories {
+        let repo = Repository::open(&repository.path)?;
+        let prefix = match common_args.is_multi_repository() {
+            true => format!("{}/", repository.name),
+            false => String::new(),
+        };
+        collect_repository_changes(&repo, &prefix, &git_args, common_args, &mut log)?;
+    }
+    if common_args.is_multi_repository() || common_args.recurse_submodules {
+        log.sort_by_key(|commit| Reverse(commit.time));
+    }
+    Ok(log)
+}
+
+fn collect_repository_changes(
+    repo: &Repository,
+    prefix: &str,
+    git_args: &GitArgs,
+    common_args: &CommonArgs,
+    log: &mut Vec<CommitChanges>,
+) -> Result<(), Box<dyn Error>> {
+    let mut repository_log = collect_changes(repo, git_args.clone(), common_args.use_cache)?;
+    for commit in repository_log.iter_mut() {
+        if !common_args.recurse_submodules {
+            commit.changes.retain(|change| !change.submodule);
+        }
+        if !prefix.is_empty() {
+            for change in commit.changes.iter_mut() {
+                change.path = format!("{}{}", prefix, change.path);
+            }
+        }
+    }
+    log.append(&mut repository_log);
+
+    if common_args.recurse_submodules {
+        for submodule in repo.submodules()? {
+            let location = format!("{}{}/", prefix, submodule.path().display());
+            let sub_repo = match submodule.open() {
+                Ok(sub_repo) => sub_repo,
+                Err(e) => {
+                    warn!("Skip submodule {} (not initialized?): {}", location, e);
+                    continue;
+                }
+            };
+            // without --commit the submodule is walked from its own HEAD
+            let sub_args = match &git_args.commit {
+                Some(revision) => match submodule_revision(repo, submodule.path(), revision)? {
+                    Some(revision) => GitArgs {
+                        commit: Some(revision),
+                        ..git_args.clone()
+                    },
+                    None => continue,
+                },
+                None => git_args.clone(),
+            };
+            collect_repository_changes(&sub_repo, &location, &sub_args, common_args, log)?
+        }
+    }
+    Ok(())
+}
+
+/// The submodule revision matching a `--commit` revision of the
+/// superproject, by the submodule commits the superproject records, e.g.
+/// `v1..v2` becomes the range between the submodule commits of `v1` and
+/// `v2`; `None` if the submodule is not in the selected commits
+fn submodule_revision(
+    repo: &Repository,
+    path: &Path,
+    revision: &str,
+) -> Result<Option<String>, git2::Error> {
+    let pointer = |object: &git2::Object| -> Result<Option<git2::Oid>, git2::Error> {
+        match object.peel_to_commit()?.tree()?.get_path(path) {
+            Ok(entry) if entry.filemode() == i32::from(FileMode::Commit) => Ok(Some(entry.id())),
+            Ok(_) => Ok(None),
+            Err(e) if e.code() == git2::ErrorCode::NotFound => Ok(None),
+            Err(e) => Err(e),
+        }
+    };
+    if let Some(hidden) = revision.strip_prefix('^') {
+        let id = pointer(&repo.revparse_single(hidden)?)?;
+        return Ok(id.map(|id| format!("^{}", id)));
+    }
+    let revspec = repo.revparse(revision)?;
+    let from = revspec.from().expect("a revision is never empty");
+    let Some(to) = revspec.to() else {
+        return Ok(pointer(from)?.map(|id| id.to_string()));
+    };
+    let Some(to) = pointer(to)? else {
+        return Ok(None);
+    };
+    let separator = match revspec.mode().contains(git2::RevparseMode::MERGE_BASE) {
+        true => "...",
+        false => "..",
+    };
+    // a submodule added within the range is walked from its beginning
+    Ok(Some(match pointer(from)? {
+        Some(from) => format!("{}{}{}", from, separator, to),
+        None => to.to_string(),
+    }))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn verify_changes_of_commits() {
+        let test_repo = TestRepository::default();
+        test_repo.commit(
+            "Add code and logo",
+            &[
+                ("src/main.rs", b"fn main() {\n}\n"),
+                ("logo.png", b"\x89PNG\0\x01"),
+            ],
+        );
+        let aside = test_repo.commit_aside("Add readme", &[("README.md", b"# ocs\n")]);
+        test_repo.commit(
+            "Print",
+            &[("src/main.rs", b"fn main() {\n    print();\n}\n")],
+        );
+        test_repo.merge("Merge readme", aside);
+
+        let log = collect_changes(&test_repo.repo, GitArgs::default(), false).unwrap();
+        let mut changes: Vec<String> = log
+            .iter()
+            .map(|commit| {
+                let files: Vec<String> = commit
+                    .changes
+                    .iter()
+                    .map(|c| match c.binary {
+                        true => format!("{} binary", c.path),
+                        false => format!("{} +{} -{}", c.path, c.added, c.deleted),
+                    })
+                    .collect();
+                format!("{}: {}", commit.message, files.join(", "))
+            })
+            .collect();
+        changes.sort();
+        assert_eq!(
+            changes,
+            vec![
+                "Add code and logo: logo.png binary, src/main.rs +2 -0",
+                "Add readme: README.md +1 -0",
+                // merges have no changes of their own
+                "Merge readme: ",
+                "Print: src/main.rs +1 -0",
+            ]
+        );
+
+        // the first run fills the cache, the second reads it
+        for _ in 0..2 {
+            let mut cached = collect_changes(&test_repo.repo, GitArgs::default(), true).unwrap();
+            cached.sort_by(|a, b| a.id.cmp(&b.id));
+            let mut log = log.clone();
+            log.sort_by(|a, b| a.id.cmp(&b.id));
+            assert_eq!(cached, log);
+        }
+    }
+
+    #[test]
+    fn verify_submodule_changes_of_commit_range() {
+        let sub_repo = TestRepository::default();
+        let s1 = sub_repo.commit("Sub 1", &[("a.rs", b"1\n")]);
+        let s2 = sub_repo.commit("Sub 2", &[("a.rs", b"2\n")]);
+        let s3 = sub_repo.commit("Sub 3", &[("a.rs", b"3\n")]);
+        let super_repo = TestRepository::default();
+        let v1 = super_repo.commit_submodule("Add lib", "lib", s1);
+        let v2 = super_repo.commit_submodule("Bump lib", "lib", s2);
+        super_repo.commit_submodule("Bump lib again", "lib", s3);
+        let url = sub_repo.path().to_str().unwrap();
+        Repository::clone(url, super_repo.path().join("lib")).unwrap();
+
+        let mut common_args = CommonArgs::new(Some(&super_repo.path().to_path_buf()));
+        common_args.use_cache = false;
+        common_args.recurse_submodules = true;
+        let git_args = GitArgs {
+            commit: Some(format!("{}..{}", v1, v2)),
+            ..GitArgs::default()
+        };
+        let log = collect_project_changes(&common_args, git_args).unwrap();
+        let mut changes: Vec<String> = log
+            .iter()
+            .map(|commit| {
+                let files: Vec<&str> = commit.changes.iter().map(|c| c.path.as_str()).collect();
+                format!("{}: {}", commit.message, files.join(", "))
+            })
+            .collect();
+        changes.sort();
+        // the submodule commits between the pointers of v1 and v2
+        assert_eq!(changes, vec!["Bump lib: lib", "Sub 2: lib/a.rs"]);
+    }
+}
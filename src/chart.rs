@@ -0,0 +1,267 @@
+//! Chart templates of the D3 html output
+//!
+//! Analyses do not write JavaScript. They choose a chart type and map the
+//! roles of the chart (e.g. the `size` of a circle) to fields of their
+//! records; the records are the ones of the JSON output.
+//!
+//! The templates ship with ocs (`assets/charts/`). A user template directory
+//! (`--template-dir`) may replace any of them by a file `<chart-type>.js`.
+//! A template is the body of a JavaScript module; in scope are
+//!
+//! - `d3`
+//! - `data`: the array of records
+//! - `options`: `{id, title, fields}`, `fields` maps roles to field names
+//! - the helpers of `assets/charts/common.js`, among them `container`, the
+//!   element to render into, and `field(role)`, the accessor of a role
+
+use log::info;
+use serde::Serialize;
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+const COMMON: &str = include_str!("../assets/charts/common.js");
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum ChartType {
+    CirclePacking,
+    Treemap,
+    BarChart,
+    Network,
+    TimeSeries,
+    Heatmap,
+}
+
+/// A role of a chart, to be mapped to a field of the records
+struct Role {
+    name: &'static str,
+    required: bool,
+}
+
+const fn required(name: &'static str) -> Role {
+    Role {
+        name,
+        required: true,
+    }
+}
+
+const fn optional(name: &'static str) -> Role {
+    Role {
+        name,
+        required: false,
+    }
+}
+
+const HIERARCHY_ROLES: &[Role] = &[required("path"), required("size"), optional("color")];
+const BAR_CHART_ROLES: &[Role] = &[required("label"), required("value"), optional("color")];
+const NETWORK_ROLES: &[Role] = &[required("source"), required("target"), optional("weight")];
+const TIME_SERIES_ROLES: &[Role] = &[required("x"), required("y"), optional("series")];
+const HEATMAP_ROLES: &[Role] = &[required("x"), required("y"), required("value")];
+
+impl ChartType {
+    /// Name of the chart type and of its template file
+    pub fn name(&self) -> &'static str {
+        match self {
+            ChartType::CirclePacking => "circle-packing",
+            ChartType::Treemap => "treemap",
+            ChartType::BarChart => "bar-chart",
+            ChartType::Network => "network",
+            ChartType::TimeSeries => "time-series",
+            ChartType::Heatmap => "heatmap",
+        }
+    }
+
+    fn roles(&self) -> &'static [Role] {
+        match self {
+            ChartType::CirclePacking | ChartType::Treemap => HIERARCHY_ROLES,
+            ChartType::BarChart => BAR_CHART_ROLES,
+            ChartType::Network => NETWORK_ROLES,
+            ChartType::TimeSeries => TIME_SERIES_ROLES,
+            ChartType::Heatmap => HEATMAP_ROLES,
+        }
+    }
+
+    fn builtin_template(&self) -> &'static str {
+        match self {
+            ChartType::CirclePacking => include_str!("../assets/charts/circle-packing.js"),
+            ChartType::Treemap => include_str!("../assets/charts/treemap.js"),
+            ChartType::BarChart => include_str!("../assets/charts/bar-chart.js"),
+            ChartType::Network => include_str!("../assets/charts/network.js"),
+            ChartType::TimeSeries => include_str!("../assets/charts/time-series.js"),
+            ChartType::Heatmap => include_str!("../assets/charts/heatmap.js"),
+        }
+    }
+}
+
+/// A chart of the records of an analysis
+#[derive(Debug, Clone)]
+pub struct Chart<'a> {
+    chart_type: ChartType,
+    id: &'a str,
+    title: &'a str,
+    fields: BTreeMap<&'static str, &'a str>,
+}
+
+#[derive(Serialize)]
+struct ChartOptions<'a> {
+    id: &'a str,
+    title: &'a str,
+    fields: &'a BTreeMap<&'static str, &'a str>,
+}
+
+impl<'a> Chart<'a> {
+    pub fn new(chart_type: ChartType, title: &'a str) -> Self {
+        Chart {
+            chart_type,
+            id: "chart",
+            title,
+            fields: BTreeMap::new(),
+        }
+    }
+
+    /// Map a role of the chart to a field of the records
+    pub fn field(mut self, role: &'static str, field: &'a str) -> Self {
+        self.fields.insert(role, field);
+        self
+    }
+
+    /// Id of the html element the chart is rendered into; must be unique
+    /// if a page holds several charts
+    pub fn id(mut self, id: &'a str) -> Self {
+        self.id = id;
+        self
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        let roles = self.chart_type.roles();
+        if let Some(role) = roles
+            .iter()
+            .find(|r| r.required && !self.fields.contains_key(r.name))
+        {
+            return Err(format!(
+                "The {} chart needs a field for the role '{}'",
+                self.chart_type.name(),
+                role.name
+            ));
+        }
+        if let Some(role) = self
+            .fields
+            .keys()
+            .find(|f| !roles.iter().any(|r| r.name == **f))
+        {
+            return Err(format!(
+                "The {} chart has no role '{}'",
+                self.chart_type.name(),
+                role
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// The templates to render charts with, built-in or user supplied
+#[derive(Debug, Clone, Default)]
+pub struct ChartTemplates {
+    dir: Option<PathBuf>,
+}
+
+impl ChartTemplates {
+    pub fn new(dir: Option<&Path>) -> Self {
+        ChartTemplates {
+            dir: dir.map(Path::to_path_buf),
+        }
+    }
+
+    fn template(&self, chart_type: ChartType) -> Result<Cow<'static, str>, Box<dyn Error>> {
+        if let Some(dir) = &self.dir {
+            let path = dir.join(format!("{}.js", chart_type.name()));
+            if path.is_file() {
+                info!("Use chart template {}", path.display());
+                return Ok(Cow::Owned(fs::read_to_string(path)?));
+            }
+        }
+        Ok(Cow::Borrowed(chart_type.builtin_template()))
+    }
+
+    /// Write the html of a chart: its container and the script rendering
+    /// the records into it
+    pub fn render<T: Serialize + ?Sized>(
+        &self,
+        writer: &mut dyn Write,
+        chart: &Chart,
+        data: &T,
+    ) -> Result<(), Box<dyn Error>> {
+        writeln!(writer, "<title>{}</title>", escape_html(chart.title))?;
+        self.write_chart(writer, chart, data)
+    }
+
+    /// Write a chart as section of a page holding several charts
+    pub fn render_section<T: Serialize + ?Sized>(
+        &self,
+        writer: &mut dyn Write,
+        chart: &Chart,
+        data: &T,
+    ) -> Result<(), Box<dyn Error>> {
+        writeln!(writer, "<h3>{}</h3>", escape_html(chart.title))?;
+        self.write_chart(writer, chart, data)
+    }
+
+    fn write_chart<T: Serialize + ?Sized>(
+        &self,
+        writer: &mut dyn Write,
+        chart: &Chart,
+        data: &T,
+    ) -> Result<(), Box<dyn Error>> {
+        chart.validate()?;
+        let options = ChartOptions {
+            id: chart.id,
+            title: chart.title,
+            fields: &chart.fields,
+        };
+        writeln!(writer, "<div id=\"{}\"></div>", escape_html(chart.id))?;
+        writeln!(writer, "<script type=\"module\">")?;
+        writeln!(writer, "const data = {};", script_json(data)?)?;
+        writeln!(writer, "const options = {};", script_json(&options)?)?;
+        writer.write_all(COMMON.as_bytes())?;
+        writer.write_all(self.template(chart.chart_type)?.as_bytes())?;
+        writeln!(writer, "</script>")?;
+        Ok(())
+    }
+}
+
+/// JSON to embed into a script element; `</` would end the element early
+pub fn script_json<T: Serialize + ?Sized>(value: &T) -> serde_json::Result<String> {
+    Ok(serde_json::to_string(value)?.replace("</", "<\\/"))
+}
+
+pub fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn verify_chart_roles() {
+        let chart = Chart::new(ChartType::Network, "").field("source", "entity");
+        assert!(chart.validate().is_err());
+        let chart = chart.field("target", "coupled");
+        assert!(chart.validate().is_ok());
+        assert!(chart.field("size", "degree").validate().is_err());
+    }
+
+    #[test]
+    fn verify_script_json_cannot_end_script() {
+        assert_eq!(
+            script_json(&["</script>"]).unwrap(),
+            r#"["<\/script>"]"#.to_owned()
+        );
+    }
+}
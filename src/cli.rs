@@ -2,8 +2,9 @@ use clap::{
     builder::PossibleValue, crate_authors, crate_description, crate_name, crate_version,
     value_parser, Arg, ArgAction, ArgMatches, Command, ValueEnum,
 };
+use std::collections::BTreeSet;
 use std::env;
-use std::fs::File;
+use std::fs::{self, File};
 use std::path::{Path, PathBuf};
 use std::{error::Error, io, io::Write};
 
@@ -11,10 +12,20 @@ use git2::Time;
 
 use time::{error, macros::format_description, Date, OffsetDateTime, UtcOffset};
 
+use crate::chart::ChartTemplates;
+use crate::config::Config;
+use crate::html::{self, D3Source};
+use crate::ranking::{parse_threshold, Ranking, Threshold};
+use crate::table::TableOptions;
+
 #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
 pub enum OutputFormat {
     Csv,
     Json,
+    Markdown,
+    Table,
+    Parquet,
+    Sqlite,
     D3Graphics,
 }
 
@@ -23,6 +34,10 @@ impl ValueEnum for OutputFormat {
         &[
             OutputFormat::Csv,
             OutputFormat::Json,
+            OutputFormat::Markdown,
+            OutputFormat::Table,
+            OutputFormat::Parquet,
+            OutputFormat::Sqlite,
             OutputFormat::D3Graphics,
         ]
     }
@@ -32,6 +47,16 @@ impl ValueEnum for OutputFormat {
             OutputFormat::Csv => PossibleValue::new("csv")
                 .help("Character separated value, ',' is delimiter, 1st line is item name"),
             OutputFormat::Json => PossibleValue::new("json").help("JSON prettry printed output"),
+            OutputFormat::Markdown => PossibleValue::new("markdown")
+                .help("Markdown table, e.g. for pull request comments and job summaries"),
+            OutputFormat::Table => PossibleValue::new("table")
+                .help("Aligned table for the terminal, coloured if the terminal supports it"),
+            OutputFormat::Parquet => PossibleValue::new("parquet")
+                .help("Apache Parquet file, e.g. for DuckDB or Polars; write it with --output"),
+            OutputFormat::Sqlite => PossibleValue::new("sqlite").help(
+                "SQLite database with the result, the commits and their changes; \
+                 requires --output, an existing database is added to",
+            ),
             OutputFormat::D3Graphics => {
                 PossibleValue::new("D3html").help("Render to D3 graphics as single html page")
             }
@@ -39,11 +64,74 @@ impl ValueEnum for OutputFormat {
     }
 }
 
+/// A git repository to analyse
+///
+/// If more than one repository is analysed, all paths of a repository are
+/// prefixed with its name to keep them distinguishable.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProjectRepository {
+    pub name: String,
+    pub path: String,
+}
+
+impl ProjectRepository {
+    /// Repository named after the last component of its (canonical) path
+    pub fn from_path(path: &Path) -> Self {
+        let canonical = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        let name = canonical
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_else(|| canonical.display().to_string());
+        ProjectRepository {
+            name,
+            path: path.display().to_string(),
+        }
+    }
+}
+
+/// Read a manifest file listing repositories to analyse
+///
+/// One repository per line, either `<path>` or `<name>=<path>`. Relative
+/// paths are relative to the directory of the manifest. Empty lines and
+/// lines starting with `#` are ignored.
+pub fn read_manifest(manifest: &Path) -> io::Result<Vec<ProjectRepository>> {
+    let base = manifest.parent().unwrap_or(Path::new(""));
+    let mut repositories = Vec::new();
+    for line in fs::read_to_string(manifest)?.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let repository = match line.split_once('=') {
+            Some((name, path)) => ProjectRepository {
+                name: name.trim().to_owned(),
+                path: base.join(path.trim()).display().to_string(),
+            },
+            None => ProjectRepository::from_path(&base.join(line)),
+        };
+        repositories.push(repository);
+    }
+    Ok(repositories)
+}
+
 #[derive(Debug, Clone)]
 pub struct CommonArgs<'a> {
+    /// The first repository; used by commands working on a single checkout
     pub project_dir: String,
+    /// All repositories whose combined history is analysed
+    pub repositories: Vec<ProjectRepository>,
     pub format: OutputFormat,
     pub output: Option<&'a PathBuf>,
+    pub use_cache: bool,
+    pub recurse_submodules: bool,
+    pub d3_source: D3Source,
+    /// Directory with chart templates replacing the built-in ones
+    pub template_dir: Option<&'a PathBuf>,
+    /// Sorting and filtering of the results
+    pub ranking: Ranking,
+    /// Settings of the configuration file; only read for the commands using
+    /// them, defaults otherwise
+    pub config: Config,
 }
 
 impl CommonArgs<'_> {
@@ -53,11 +141,69 @@ impl CommonArgs<'_> {
             None => env::current_dir().unwrap(),
         };
         CommonArgs {
-            project_dir: project_dir.into_os_string().into_string().unwrap().clone(),
+            project_dir: project_dir.clone().into_os_string().into_string().unwrap(),
+            repositories: vec![ProjectRepository::from_path(&project_dir)],
             format: OutputFormat::Csv,
             output: None,
+            use_cache: true,
+            recurse_submodules: false,
+            d3_source: D3Source::Inline,
+            template_dir: None,
+            ranking: Ranking::default(),
+            config: Config::default(),
         }
     }
+
+    /// Analyse the combined history of several repositories
+    ///
+    /// Fails if two repositories share the same name, since their paths
+    /// could not be told apart.
+    pub fn with_repositories(repositories: Vec<ProjectRepository>) -> Result<Self, String> {
+        let mut common_args = Self::new(
+            repositories
+                .first()
+                .map(|r| PathBuf::from(&r.path))
+                .as_ref(),
+        );
+        if repositories.is_empty() {
+            return Ok(common_args);
+        }
+        let mut names = BTreeSet::new();
+        for repository in &repositories {
+            if !names.insert(repository.name.as_str()) {
+                return Err(format!(
+                    "Repository name '{}' is used more than once; name repositories explicitly in a manifest",
+                    repository.name
+                ));
+            }
+        }
+        common_args.repositories = repositories;
+        Ok(common_args)
+    }
+
+    pub fn ranking_from_cli_args(&mut self, matches: &ArgMatches) {
+        self.ranking = Ranking {
+            sort_by: matches.get_one::<String>("SORT_BY").cloned(),
+            descending: matches.get_flag("desc"),
+            top: matches.get_one::<usize>("top").copied(),
+            min: matches
+                .get_many::<Threshold>("min")
+                .into_iter()
+                .flatten()
+                .cloned()
+                .collect(),
+        };
+    }
+
+    /// Colours only if written to a terminal supporting them
+    pub fn table_options(&self) -> TableOptions {
+        TableOptions::new(self.output.is_none() && console::colors_enabled())
+    }
+
+    /// Are paths to be prefixed by the repository name
+    pub fn is_multi_repository(&self) -> bool {
+        self.repositories.len() > 1
+    }
 }
 
 #[derive(Default, Debug, Clone)]
@@ -117,10 +263,31 @@ pub fn common_builder() -> Command {
         .long("project_dir")
         .short('C')
         .required(false)
+        .action(ArgAction::Append)
         .value_parser(value_parser!(PathBuf))
         .help(
             "Project directory where the git repository exists and the sources are checked out.
-            Default is the current directory"
+            Default is the current directory.
+            Repeat the option to analyse the combined history of several repositories"
+        )
+    )
+    .arg (
+        Arg::new("MANIFEST")
+        .long("manifest")
+        .short('m')
+        .required(false)
+        .value_parser(value_parser!(PathBuf))
+        .help(
+            "File listing repositories to analyse together, one per line as <path> or <name>=<path>"
+        )
+    )
+    .arg (
+        Arg::new("CONFIG")
+        .long("config")
+        .required(false)
+        .value_parser(value_parser!(PathBuf))
+        .help(
+            "Configuration file. Default is .ocs.toml in the project directory if it exists"
         )
     )
     .arg (
@@ -133,6 +300,26 @@ pub fn common_builder() -> Command {
             "Show progress"
         )
     )
+    .arg (
+        Arg::new("no-cache")
+        .long("no-cache")
+        .required(false)
+        .action(ArgAction::SetTrue)
+        .help(
+            "Neither read nor update the cache of analysed commits in .git/ocs/"
+        )
+    )
+    .arg (
+        Arg::new("recurse-submodules")
+        .long("recurse-submodules")
+        .required(false)
+        .action(ArgAction::SetTrue)
+        .help(
+            "Analyse the history of submodules as well (paths prefixed by the submodule location)
+            and count submodule pointer bumps as changes. With --commit, submodules are analysed
+            between the commits the superproject records, otherwise from their checked out commit"
+        )
+    )
     .arg (
         Arg::new("format")
         .long("format")
@@ -143,6 +330,64 @@ pub fn common_builder() -> Command {
             "Set the output format"
         )
     )
+    .arg (
+        Arg::new("SORT_BY")
+        .long("sort-by")
+        .required(false)
+        .value_parser(value_parser!(String))
+        .help(
+            "Sort the results by a column (as named in the csv header), ascending unless --desc"
+        )
+    )
+    .arg (
+        Arg::new("desc")
+        .long("desc")
+        .required(false)
+        .action(ArgAction::SetTrue)
+        .help(
+            "Sort descending, e.g. to rank the most changed entities first"
+        )
+    )
+    .arg (
+        Arg::new("top")
+        .long("top")
+        .required(false)
+        .value_parser(value_parser!(usize))
+        .help(
+            "Keep the first N results, after filtering and sorting"
+        )
+    )
+    .arg (
+        Arg::new("min")
+        .long("min")
+        .required(false)
+        .action(ArgAction::Append)
+        .value_parser(parse_threshold)
+        .help(
+            "Keep results whose column is at least the value, e.g. --min revisions=10.
+            Repeat the option for several columns"
+        )
+    )
+    .arg (
+        Arg::new("d3-cdn")
+        .long("d3-cdn")
+        .required(false)
+        .action(ArgAction::SetTrue)
+        .help(
+            "Load D3 from a CDN in D3html output instead of embedding the bundled renderer.
+            Pages get smaller but need network access to be viewed"
+        )
+    )
+    .arg (
+        Arg::new("TEMPLATE_DIR")
+        .long("template-dir")
+        .required(false)
+        .value_parser(value_parser!(PathBuf))
+        .help(
+            "Directory with chart templates <chart-type>.js replacing the built-in ones of D3html output.
+            Chart types are circle-packing, treemap, bar-chart, network, time-series and heatmap"
+        )
+    )
     .arg (
         Arg::new("FILE")
         .long("output")
@@ -187,22 +432,125 @@ fn parse_iso_date_and_convert_to_git_time(arg: &str) -> Result<Time, error::Pars
 pub trait OutputFormatter {
     fn csv_output(&self, writer: &mut dyn Write) -> Result<(), Box<dyn Error>>;
     fn json_output(&self, writer: &mut dyn Write) -> Result<(), Box<dyn Error>>;
-    fn d3_html_output(&self, writer: &mut dyn Write) -> Result<(), Box<dyn Error>>;
+    fn markdown_output(&self, writer: &mut dyn Write) -> Result<(), Box<dyn Error>>;
+    fn table_output(
+        &self,
+        writer: &mut dyn Write,
+        options: &TableOptions,
+    ) -> Result<(), Box<dyn Error>>;
+    fn parquet_output(&self, writer: &mut dyn Write) -> Result<(), Box<dyn Error>>;
+    fn sqlite_output(&self, database: &Path) -> Result<(), Box<dyn Error>>;
+    fn d3_html_output(
+        &self,
+        writer: &mut dyn Write,
+        templates: &ChartTemplates,
+    ) -> Result<(), Box<dyn Error>>;
 
-    fn output(&self, format: OutputFormat, target: Option<&PathBuf>) {
-        let mut writer = match target {
-            Some(path) => {
-                let path = Path::new(&path);
-                Box::new(File::create(&path).expect("Unable to create file")) as Box<dyn Write>
-            }
+    fn output(&self, common_args: &CommonArgs) -> Result<(), Box<dyn Error>> {
+        // a database is opened by path and written in place
+        if common_args.format == OutputFormat::Sqlite {
+            let path = common_args
+                .output
+                .ok_or("sqlite output requires --output")?;
+            return self.sqlite_output(path);
+        }
+        // binary data is never written to the terminal
+        if common_args.format == OutputFormat::Parquet && common_args.output.is_none() {
+            return Err("parquet output requires --output".into());
+        }
+        let mut writer = match common_args.output {
+            Some(path) => Box::new(
+                File::create(path)
+                    .map_err(|e| format!("Unable to create {}: {}", path.display(), e))?,
+            ) as Box<dyn Write>,
             None => Box::new(io::stdout()) as Box<dyn Write>,
         };
-        match format {
-            OutputFormat::Csv => self.csv_output(&mut writer).unwrap(),
-            OutputFormat::Json => self.json_output(&mut writer).unwrap(),
+        match common_args.format {
+            OutputFormat::Csv => self.csv_output(&mut writer)?,
+            OutputFormat::Json => self.json_output(&mut writer)?,
+            OutputFormat::Markdown => self.markdown_output(&mut writer)?,
+            OutputFormat::Table => self.table_output(&mut writer, &common_args.table_options())?,
+            OutputFormat::Parquet => self.parquet_output(&mut writer)?,
+            OutputFormat::Sqlite => unreachable!("written above"),
             OutputFormat::D3Graphics => {
-                self.d3_html_output(&mut writer).unwrap();
+                html::write_head(&mut writer, common_args.d3_source)?;
+                let templates = ChartTemplates::new(common_args.template_dir.map(|d| d.as_path()));
+                self.d3_html_output(&mut writer, &templates)?;
             }
         }
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+/// Whether writing failed because the reader went away, e.g. `head` in
+/// `ocs export-log | head`; not an error of the analysis
+pub fn is_broken_pipe(e: &(dyn Error + 'static)) -> bool {
+    let kind = if let Some(e) = e.downcast_ref::<io::Error>() {
+        Some(e.kind())
+    } else if let Some(e) = e.downcast_ref::<csv::Error>() {
+        match e.kind() {
+            csv::ErrorKind::Io(e) => Some(e.kind()),
+            _ => None,
+        }
+    } else if let Some(e) = e.downcast_ref::<serde_json::Error>() {
+        e.io_error_kind()
+    } else {
+        None
+    };
+    kind == Some(io::ErrorKind::BrokenPipe)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn verify_manifest() {
+        let dir = tempfile::tempdir().unwrap();
+        let manifest = dir.path().join("repositories.txt");
+        fs::write(
+            &manifest,
+            "# services\napi = services/api\n\n  web=/srv/web  \n# shared code\nlib\n",
+        )
+        .unwrap();
+        let repositories = read_manifest(&manifest).unwrap();
+        assert_eq!(
+            repositories,
+            vec![
+                ProjectRepository {
+                    name: "api".to_owned(),
+                    path: dir.path().join("services/api").display().to_string(),
+                },
+                ProjectRepository {
+                    name: "web".to_owned(),
+                    path: "/srv/web".to_owned(),
+                },
+                ProjectRepository {
+                    name: "lib".to_owned(),
+                    path: dir.path().join("lib").display().to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn verify_unique_repository_names() {
+        let repository = |name: &str, path: &str| ProjectRepository {
+            name: name.to_owned(),
+            path: path.to_owned(),
+        };
+        let common_args = CommonArgs::with_repositories(vec![
+            repository("api", "/srv/api"),
+            repository("web", "/srv/web"),
+        ])
+        .unwrap();
+        assert!(common_args.is_multi_repository());
+        assert_eq!(common_args.project_dir, "/srv/api");
+        assert!(CommonArgs::with_repositories(vec![
+            repository("api", "/srv/api"),
+            repository("api", "/opt/api"),
+        ])
+        .is_err());
     }
 }
@@ -0,0 +1,172 @@
+//! Apache Parquet output of analysis results
+//!
+//! Large results, e.g. coupling of a monorepo, load faster and smaller into
+//! DuckDB, Polars or pandas as Parquet than as CSV. Columns keep their type;
+//! title and metadata of the result are stored as key value metadata of the
+//! file (`ocs.title`, `ocs.metadata` as JSON).
+
+use arrow_array::builder::{BooleanBuilder, Float64Builder, Int64Builder, StringBuilder};
+use arrow_array::{ArrayRef, RecordBatch};
+use arrow_schema::{DataType, Field, Schema};
+use parquet::arrow::ArrowWriter;
+use parquet::basic::Compression;
+use parquet::file::properties::WriterProperties;
+use parquet::format::KeyValue;
+use std::error::Error;
+use std::io::Write;
+use std::sync::Arc;
+
+use crate::result::{AnalysisResult, ColumnType, Value};
+
+/// Rows per record batch, bounds the memory of the arrow arrays
+const BATCH_SIZE: usize = 64 * 1024;
+
+fn data_type(column_type: ColumnType) -> DataType {
+    match column_type {
+        ColumnType::Text => DataType::Utf8,
+        ColumnType::Integer => DataType::Int64,
+        ColumnType::Float => DataType::Float64,
+        ColumnType::Bool => DataType::Boolean,
+    }
+}
+
+fn column_array(rows: &[Vec<Value>], column: usize, column_type: ColumnType) -> ArrayRef {
+    let values = rows.iter().map(|row| &row[column]);
+    match column_type {
+        ColumnType::Integer => {
+            let mut builder = Int64Builder::with_capacity(rows.len());
+            for value in values {
+                match value {
+                    Value::Integer(i) => builder.append_value(*i),
+                    _ => builder.append_null(),
+                }
+            }
+            Arc::new(builder.finish())
+        }
+        ColumnType::Float => {
+            let mut builder = Float64Builder::with_capacity(rows.len());
+            for value in values {
+                builder.append_option(value.as_f64());
+            }
+            Arc::new(builder.finish())
+        }
+        ColumnType::Bool => {
+            let mut builder = BooleanBuilder::with_capacity(rows.len());
+            for value in values {
+                match value {
+                    Value::Bool(b) => builder.append_value(*b),
+                    _ => builder.append_null(),
+                }
+            }
+            Arc::new(builder.finish())
+        }
+        ColumnType::Text => {
+            let mut builder = StringBuilder::new();
+            for value in values {
+                match value {
+                    Value::Null => builder.append_null(),
+                    value => builder.append_value(value.to_string()),
+                }
+            }
+            Arc::new(builder.finish())
+        }
+    }
+}
+
+/// Write the result as a Parquet file, Snappy compressed
+pub fn write_parquet(
+    writer: &mut dyn Write,
+    result: &AnalysisResult,
+) -> Result<(), Box<dyn Error>> {
+    let schema = Arc::new(Schema::new(
+        result
+            .columns
+            .iter()
+            .map(|c| Field::new(&c.name, data_type(c.column_type), true))
+            .collect::<Vec<_>>(),
+    ));
+    let properties = WriterProperties::builder()
+        .set_compression(Compression::SNAPPY)
+        .set_key_value_metadata(Some(vec![
+            KeyValue::new("ocs.title".to_owned(), result.title.clone()),
+            KeyValue::new(
+                "ocs.metadata".to_owned(),
+                serde_json::to_string(&result.metadata)?,
+            ),
+        ]))
+        .build();
+
+    // The arrow writer needs an owned, sendable sink; the compressed file
+    // is much smaller than the rows, so buffer it
+    let mut parquet = ArrowWriter::try_new(Vec::new(), schema.clone(), Some(properties))?;
+    for rows in result.rows.chunks(BATCH_SIZE) {
+        let arrays = result
+            .columns
+            .iter()
+            .enumerate()
+            .map(|(c, column)| column_array(rows, c, column.column_type))
+            .collect();
+        parquet.write(&RecordBatch::try_new(schema.clone(), arrays)?)?;
+    }
+    writer.write_all(&parquet.into_inner()?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::result::Metadata;
+    use arrow_array::{Array, Int64Array, StringArray};
+    use bytes::Bytes;
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+    use serde::Serialize;
+
+    #[derive(Default, Serialize)]
+    struct Record {
+        entity: &'static str,
+        revisions: u64,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        author: Option<&'static str>,
+    }
+
+    #[test]
+    fn verify_parquet_round_trip() {
+        let records = [
+            Record {
+                entity: "a.rs",
+                revisions: 2,
+                author: Some("ann"),
+            },
+            Record {
+                entity: "b.rs",
+                revisions: 12,
+                author: None,
+            },
+        ];
+        let result = AnalysisResult::from_records("Test", Metadata::default(), &records).unwrap();
+        let mut out = Vec::new();
+        write_parquet(&mut out, &result).unwrap();
+
+        let builder = ParquetRecordBatchReaderBuilder::try_new(Bytes::from(out)).unwrap();
+        let key_values = builder.metadata().file_metadata().key_value_metadata();
+        assert!(key_values
+            .unwrap()
+            .iter()
+            .any(|kv| kv.key == "ocs.title" && kv.value.as_deref() == Some("Test")));
+        let batch = builder.build().unwrap().next().unwrap().unwrap();
+        assert_eq!(batch.num_rows(), 2);
+        let revisions = batch
+            .column(1)
+            .as_any()
+            .downcast_ref::<Int64Array>()
+            .unwrap();
+        assert_eq!(revisions.values(), &[2, 12]);
+        let authors = batch
+            .column(2)
+            .as_any()
+            .downcast_ref::<StringArray>()
+            .unwrap();
+        assert_eq!(authors.value(0), "ann");
+        assert!(authors.is_null(1));
+    }
+}
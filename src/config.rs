@@ -0,0 +1,91 @@
+//! Configuration file
+//!
+//! Settings that are project policy rather than options of a single run are
+//! read from a TOML file: the one given by `--config`, else `.ocs.toml` in
+//! the (first) project directory if it exists. All settings are optional and
+//! unknown keys are rejected to catch typos, e.g.
+//!
+//! ```toml
+//! # weights of the factors of the risk score
+//! [risk]
+//! revisions = 3.0
+//! coupling = 0.0
+//! ```
+
+use serde::Deserialize;
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::messages::MessageConfig;
+use crate::risk::RiskWeights;
+
+/// Name of the configuration file looked up in the project directory
+pub const CONFIG_FILE: &str = ".ocs.toml";
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub risk: RiskWeights,
+    pub messages: MessageConfig,
+}
+
+impl Config {
+    pub fn read(path: &Path) -> Result<Self, Box<dyn Error>> {
+        let config: Config = toml::from_str(&fs::read_to_string(path)?)?;
+        config.risk.validate()?;
+        Ok(config)
+    }
+
+    /// The given configuration file, else the one of the project directory;
+    /// defaults if there is none
+    pub fn load(file: Option<&PathBuf>, project_dir: &str) -> Result<Self, Box<dyn Error>> {
+        match file {
+            Some(file) => Self::read(file),
+            None => {
+                let file = Path::new(project_dir).join(CONFIG_FILE);
+                match file.is_file() {
+                    true => Self::read(&file),
+                    false => Ok(Config::default()),
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn verify_partial_configuration() {
+        let config: Config = toml::from_str("[risk]\nrevisions = 5\nage = 0.5\n").unwrap();
+        assert_eq!(config.risk.revisions, 5.0);
+        assert_eq!(config.risk.age, 0.5);
+        assert_eq!(config.risk.coupling, RiskWeights::default().coupling);
+        assert!(toml::from_str::<Config>("[risk]\nrevision = 5\n").is_err());
+    }
+
+    #[test]
+    fn verify_risk_weights_validation() {
+        let weights = |toml: &str| toml::from_str::<Config>(toml).unwrap().risk.validate();
+        assert!(weights("[risk]\ncoupling = 0\n").is_ok());
+        assert_eq!(
+            weights("[risk]\nage = -1\n"),
+            Err("Risk weight age = -1 is negative".to_owned())
+        );
+        assert_eq!(
+            weights("[risk]\nage = nan\n"),
+            Err("Risk weight age = NaN is not a finite number".to_owned())
+        );
+        assert_eq!(
+            weights("[risk]\nsize = inf\n"),
+            Err("Risk weight size = inf is not a finite number".to_owned())
+        );
+        assert!(weights(
+            "[risk]\nrevisions = 0\nsize = 0\ncomplexity = 0\ncomplexity-trend = 0\n\
+            fragmentation = 0\nage = 0\ncoupling = 0\n"
+        )
+        .is_err());
+    }
+}
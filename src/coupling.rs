@@ -0,0 +1,303 @@
+//! Temporal coupling between entities
+//!
+//! Two entities are coupled if they tend to change together. Changing
+//! together means being part of the same logical changeset. By default a
+//! changeset is a commit, which does not work across repositories (commits of
+//! different repositories never share an id) nor for teams splitting work
+//! into many small commits. Therefore changesets can alternatively be formed
+//!
+//! - by a sliding time window per author: consecutive commits of an author
+//!   not further apart than the window belong to the same changeset
+//!   (Code Maat's `--temporal-period`, but sliding and per author)
+//! - by ticket reference: all commits mentioning the same ticket id in the
+//!   commit message belong to the same changeset
+//!
+//! The coupling degree follows Code Maat: the number of shared changesets in
+//! percent of the average number of changesets of both entities.
+
+use regex::Regex;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::changes::CommitChanges;
+
+/// Default pattern of ticket ids in commit messages, e.g. `PROJ-123`
+pub const TICKET_PATTERN: &str = r"[A-Z][A-Z0-9]+-\d+";
+
+/// A set of entities that changed together
+pub type Changeset = BTreeSet<String>;
+
+/// How commits are grouped into logical changesets
+#[derive(Debug, Clone)]
+pub enum ChangesetGrouping {
+    /// Every commit is a changeset
+    Commit,
+    /// Consecutive commits of an author within the given seconds
+    TimeWindow(i64),
+    /// Commits referencing the same ticket id; commits without reference
+    /// are ignored
+    Ticket(Regex),
+}
+
+/// Group the commits of a change log into changesets
+pub fn changesets(log: &[CommitChanges], grouping: &ChangesetGrouping) -> Vec<Changeset> {
+    let files = |commit: &CommitChanges| -> Changeset {
+        commit.changes.iter().map(|c| c.path.clone()).collect()
+    };
+    let mut changesets: Vec<Changeset> = match grouping {
+        ChangesetGrouping::Commit => log.iter().map(files).collect(),
+        ChangesetGrouping::TimeWindow(window) => {
+            let mut by_author: BTreeMap<&str, Vec<&CommitChanges>> = BTreeMap::new();
+            for commit in log {
+                by_author.entry(&commit.author).or_default().push(commit);
+            }
+            let mut changesets = Vec::new();
+            for commits in by_author.values_mut() {
+                commits.sort_by_key(|c| c.time);
+                let mut current = Changeset::new();
+                let mut last_time: Option<i64> = None;
+                for commit in commits.iter() {
+                    if let Some(last_time) = last_time {
+                        if commit.time - last_time > *window {
+                            changesets.push(std::mem::take(&mut current));
+                        }
+                    }
+                    current.extend(files(commit));
+                    last_time = Some(commit.time);
+                }
+                changesets.push(current);
+            }
+            changesets
+        }
+        ChangesetGrouping::Ticket(pattern) => {
+            let mut by_ticket: BTreeMap<&str, Changeset> = BTreeMap::new();
+            for commit in log {
+                for ticket in pattern.find_iter(&commit.message) {
+                    by_ticket
+                        .entry(ticket.as_str())
+                        .or_default()
+                        .extend(files(commit));
+                }
+            }
+            by_ticket.into_values().collect()
+        }
+    };
+    changesets.retain(|c| !c.is_empty());
+    changesets
+}
+
+/// Thresholds to suppress coupling noise; defaults are the ones of Code Maat
+#[derive(Debug, Clone, Copy)]
+pub struct CouplingOptions {
+    /// Minimum number of changesets of each entity
+    pub min_revs: u64,
+    /// Minimum number of changesets both entities are part of
+    pub min_shared_revs: u64,
+    /// Minimum coupling degree in percent
+    pub min_coupling: u64,
+    /// Maximum coupling degree in percent
+    pub max_coupling: u64,
+    /// Changesets with more entities are ignored (e.g. mass reformatting)
+    pub max_changeset_size: usize,
+}
+
+impl Default for CouplingOptions {
+    fn default() -> Self {
+        CouplingOptions {
+            min_revs: 5,
+            min_shared_revs: 5,
+            min_coupling: 30,
+            max_coupling: 100,
+            max_changeset_size: 30,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct Coupling {
+    pub entity: String,
+    pub coupled: String,
+    pub degree: u64,
+    #[serde(rename = "average-revs")]
+    pub average_revs: u64,
+}
+
+/// Compute the coupling of all entity pairs, strongest coupling first
+pub fn coupling(changesets: &[Changeset], options: &CouplingOptions) -> Vec<Coupling> {
+    let mut revs: BTreeMap<&str, u64> = BTreeMap::new();
+    let mut shared: BTreeMap<(&str, &str), u64> = BTreeMap::new();
+
+    for changeset in changesets
+        .iter()
+        .filter(|c| c.len() <= options.max_changeset_size)
+    {
+        for entity in changeset {
+            *revs.entry(entity).or_default() += 1;
+        }
+        for (i, entity) in changeset.iter().enumerate() {
+            for coupled in changeset.iter().skip(i + 1) {
+                *shared.entry((entity, coupled)).or_default() += 1;
+            }
+        }
+    }
+
+    let mut result = Vec::new();
+    for ((entity, coupled), shared_revs) in shared {
+        let entity_revs = revs[entity];
+        let coupled_revs = revs[coupled];
+        let average_revs = (entity_revs + coupled_revs) as f64 / 2.0;
+        let degree = (100.0 * shared_revs as f64 / average_revs).round() as u64;
+        if entity_revs < options.min_revs
+            || coupled_revs < options.min_revs
+            || shared_revs < options.min_shared_revs
+            || degree < options.min_coupling
+            || degree > options.max_coupling
+        {
+            continue;
+        }
+        result.push(Coupling {
+            entity: entity.to_owned(),
+            coupled: coupled.to_owned(),
+            degree,
+            average_revs: average_revs.ceil() as u64,
+        });
+    }
+    result.sort_by(|a, b| {
+        b.degree
+            .cmp(&a.degree)
+            .then(b.average_revs.cmp(&a.average_revs))
+    });
+    result
+}
+
+/// Whether the path is the entity or, for a component, within it
+fn is_part_of(path: &str, entity: &str) -> bool {
+    let entity = entity.trim_end_matches('/');
+    path == entity
+        || path
+            .strip_prefix(entity)
+            .is_some_and(|rest| rest.starts_with('/'))
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PairCoupling {
+    pub entity_revs: u64,
+    pub coupled_revs: u64,
+    pub shared_revs: u64,
+    /// None if neither entity changed
+    pub degree: Option<u64>,
+}
+
+/// Coupling of two given entities; an entity may be a component, i.e. a
+/// directory, changing if any of its files changes
+pub fn pair_coupling(
+    changesets: &[Changeset],
+    entity: &str,
+    coupled: &str,
+    max_changeset_size: usize,
+) -> PairCoupling {
+    let mut pair = PairCoupling {
+        entity_revs: 0,
+        coupled_revs: 0,
+        shared_revs: 0,
+        degree: None,
+    };
+    for changeset in changesets.iter().filter(|c| c.len() <= max_changeset_size) {
+        let has = |entity| changeset.iter().any(|path| is_part_of(path, entity));
+        let (has_entity, has_coupled) = (has(entity), has(coupled));
+        pair.entity_revs += has_entity as u64;
+        pair.coupled_revs += has_coupled as u64;
+        pair.shared_revs += (has_entity && has_coupled) as u64;
+    }
+    let average_revs = (pair.entity_revs + pair.coupled_revs) as f64 / 2.0;
+    if average_revs > 0.0 {
+        pair.degree = Some((100.0 * pair.shared_revs as f64 / average_revs).round() as u64);
+    }
+    pair
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn set(paths: &[&str]) -> Changeset {
+        paths.iter().map(|p| p.to_string()).collect()
+    }
+
+    #[test]
+    fn verify_changesets_by_time_window() {
+        let log = vec![
+            CommitChanges::fixture("a", 100).with_changes(&["api/x"]),
+            CommitChanges::fixture("b", 110).with_changes(&["api/y"]),
+            CommitChanges::fixture("a", 150).with_changes(&["client/x"]),
+            CommitChanges::fixture("a", 300).with_changes(&["client/z"]),
+        ];
+        let changesets = changesets(&log, &ChangesetGrouping::TimeWindow(60));
+        assert_eq!(
+            changesets,
+            vec![
+                set(&["api/x", "client/x"]),
+                set(&["client/z"]),
+                set(&["api/y"])
+            ]
+        );
+    }
+
+    #[test]
+    fn verify_changesets_by_ticket() {
+        let log = vec![
+            CommitChanges::fixture("a", 100)
+                .with_message("PROJ-1: api")
+                .with_changes(&["api/x"]),
+            CommitChanges::fixture("b", 200)
+                .with_message("fix PROJ-1 and PROJ-2")
+                .with_changes(&["client/x"]),
+            CommitChanges::fixture("b", 300)
+                .with_message("no ticket")
+                .with_changes(&["client/y"]),
+        ];
+        let pattern = Regex::new(r"PROJ-\d+").unwrap();
+        let changesets = changesets(&log, &ChangesetGrouping::Ticket(pattern));
+        assert_eq!(
+            changesets,
+            vec![set(&["api/x", "client/x"]), set(&["client/x"])]
+        );
+    }
+
+    #[test]
+    fn verify_coupling_degree() {
+        let changesets = vec![set(&["a", "b"]), set(&["a", "b"]), set(&["a"]), set(&["c"])];
+        let options = CouplingOptions {
+            min_revs: 1,
+            min_shared_revs: 1,
+            ..Default::default()
+        };
+        assert_eq!(
+            coupling(&changesets, &options),
+            vec![Coupling {
+                entity: "a".to_owned(),
+                coupled: "b".to_owned(),
+                degree: 80,
+                average_revs: 3,
+            }]
+        );
+    }
+
+    #[test]
+    fn verify_component_pair_coupling() {
+        let changesets = vec![
+            set(&["api/x", "client/x"]),
+            set(&["api/y", "apidoc/z"]),
+            set(&["client/z"]),
+        ];
+        assert_eq!(
+            pair_coupling(&changesets, "api", "client/", 30),
+            PairCoupling {
+                entity_revs: 2,
+                coupled_revs: 2,
+                shared_revs: 1,
+                degree: Some(50),
+            }
+        );
+    }
+}
@@ -0,0 +1,173 @@
+//! Defect hotspots
+//!
+//! Files that needed many bug fixes are likely to need more, so they deserve
+//! the most testing. Bug fixes are the commits
+//!
+//! - whose message matches a pattern, by default the `fix` class of the
+//!   commit message classes: the usual bug fix words and the conventional
+//!   commit type `fix:`
+//! - or that reference a bug ticket; the ticket keys are read from a CSV
+//!   export of the issue tracker and looked up by the ticket ids found in
+//!   the commit messages
+//!
+//! Files are ranked by the number of changes by bug fixes. The defect
+//! density normalizes them by size: fixes per 1000 lines of code.
+
+use regex::Regex;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::error::Error;
+use std::path::Path;
+
+use crate::changes::CommitChanges;
+
+/// How bug fix commits are told apart
+#[derive(Debug, Clone)]
+pub enum FixDetection {
+    /// The message matches the pattern
+    Pattern(Regex),
+    /// The message references one of the ticket keys, found by the pattern
+    Tickets {
+        pattern: Regex,
+        keys: BTreeSet<String>,
+    },
+}
+
+impl FixDetection {
+    pub fn is_fix(&self, message: &str) -> bool {
+        match self {
+            FixDetection::Pattern(pattern) => pattern.is_match(message),
+            FixDetection::Tickets { pattern, keys } => pattern
+                .find_iter(message)
+                .any(|ticket| keys.contains(ticket.as_str())),
+        }
+    }
+}
+
+/// Ticket keys of a CSV file with header; the keys are in the column named
+/// `key`, `id` or `issue key` (ignoring case), else in the first column
+pub fn read_ticket_keys(path: &Path) -> Result<BTreeSet<String>, Box<dyn Error>> {
+    let mut reader = csv::Reader::from_path(path)?;
+    let column = reader
+        .headers()?
+        .iter()
+        .position(|name| ["key", "id", "issue key"].contains(&name.trim().to_lowercase().as_str()))
+        .unwrap_or(0);
+    let mut keys = BTreeSet::new();
+    for record in reader.records() {
+        if let Some(key) = record?.get(column) {
+            keys.insert(key.trim().to_owned());
+        }
+    }
+    Ok(keys)
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct DefectHotspot {
+    pub entity: String,
+    /// Changes by bug fix commits
+    pub fixes: u64,
+    pub revisions: u64,
+    pub code: usize,
+    /// Fixes per 1000 lines of code
+    pub density: f64,
+    /// Position among all files by revisions
+    #[serde(rename = "hotspot-rank")]
+    pub hotspot_rank: u64,
+}
+
+/// Files with lines of code fixed at least once, most fixes first
+pub fn defect_hotspots(
+    log: &[CommitChanges],
+    lines_of_code: BTreeMap<String, usize>,
+    detection: &FixDetection,
+) -> Vec<DefectHotspot> {
+    let mut revisions: BTreeMap<&str, (u64, u64)> = BTreeMap::new();
+    for commit in log {
+        let is_fix = detection.is_fix(&commit.message);
+        for change in &commit.changes {
+            let (revisions, fixes) = revisions.entry(&change.path).or_default();
+            *revisions += 1;
+            *fixes += u64::from(is_fix);
+        }
+    }
+    let files: Vec<(String, usize, u64, u64)> = lines_of_code
+        .into_iter()
+        .filter_map(|(entity, code)| {
+            let (revisions, fixes) = *revisions.get(entity.as_str())?;
+            Some((entity, code, revisions, fixes))
+        })
+        .collect();
+
+    let mut hotspots: Vec<DefectHotspot> = files
+        .iter()
+        .filter(|(_, _, _, fixes)| *fixes > 0)
+        .map(|(entity, code, revisions, fixes)| DefectHotspot {
+            entity: entity.clone(),
+            fixes: *fixes,
+            revisions: *revisions,
+            code: *code,
+            density: match code {
+                0 => 0.0,
+                _ => (*fixes as f64 * 1000.0 / *code as f64 * 100.0).round() / 100.0,
+            },
+            hotspot_rank: 1 + files.iter().filter(|(_, _, r, _)| r > revisions).count() as u64,
+        })
+        .collect();
+    hotspots.sort_by(|a, b| b.fixes.cmp(&a.fixes).then(b.density.total_cmp(&a.density)));
+    hotspots
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::coupling::TICKET_PATTERN;
+
+    #[test]
+    fn verify_defect_hotspots_by_tickets() {
+        let log = vec![
+            CommitChanges::fixture("", 1)
+                .with_message("ABC-1: crash")
+                .with_changes(&["a.rs", "b.rs"]),
+            CommitChanges::fixture("", 2)
+                .with_message("ABC-2 new feature")
+                .with_changes(&["a.rs"]),
+            CommitChanges::fixture("", 3)
+                .with_message("refactor, see ABC-3")
+                .with_changes(&["b.rs"]),
+            CommitChanges::fixture("", 4)
+                .with_message("cleanup")
+                .with_changes(&["a.rs", "c.rs"]),
+        ];
+        let detection = FixDetection::Tickets {
+            pattern: Regex::new(TICKET_PATTERN).unwrap(),
+            keys: BTreeSet::from(["ABC-1".to_owned(), "ABC-3".to_owned()]),
+        };
+        let code = BTreeMap::from([
+            ("a.rs".to_owned(), 2000),
+            ("b.rs".to_owned(), 400),
+            ("c.rs".to_owned(), 10),
+        ]);
+        assert_eq!(
+            defect_hotspots(&log, code, &detection),
+            vec![
+                DefectHotspot {
+                    entity: "b.rs".to_owned(),
+                    fixes: 2,
+                    revisions: 2,
+                    code: 400,
+                    density: 5.0,
+                    hotspot_rank: 2,
+                },
+                DefectHotspot {
+                    entity: "a.rs".to_owned(),
+                    fixes: 1,
+                    revisions: 3,
+                    code: 2000,
+                    density: 0.5,
+                    hotspot_rank: 1,
+                },
+            ]
+        );
+    }
+}
@@ -62,6 +62,18 @@ pub fn determine_commits_to_analyse(
     Ok(revwalk)
 }
 
+/// The newest commit selected by `--commit`, `HEAD` without it, e.g. to
+/// measure the sources the analysed history ends with
+pub fn tip_commit<'r>(repo: &'r Repository, args: &GitArgs) -> Result<Commit<'r>, Error> {
+    if let Some(commit) = args.commit.as_ref().filter(|c| !c.starts_with('^')) {
+        let revspec = repo.revparse(commit)?;
+        if let Some(tip) = revspec.to().or(revspec.from()) {
+            return tip.peel_to_commit();
+        }
+    }
+    repo.head()?.peel_to_commit()
+}
+
 fn commit_message_matches(msg: Option<&str>, grep: &Option<String>) -> bool {
     match (grep, msg) {
         (&None, _) => true,
@@ -88,6 +100,7 @@ fn commit_timestamp_is_in_range(
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::changes::TestRepository;
 
     #[test]
     fn verify_commit_timestamp_is_in_range() {
@@ -131,4 +144,23 @@ mod test {
             Some(Time::new(-1, 0))
         ));
     }
+
+    #[test]
+    fn verify_tip_commit() {
+        let test_repo = TestRepository::default();
+        let first = test_repo.commit("First", &[("a.rs", b"1\n")]);
+        let second = test_repo.commit("Second", &[("a.rs", b"2\n")]);
+        let head = test_repo.commit("Third", &[("a.rs", b"3\n")]);
+        let tip = |commit: Option<String>| {
+            let args = GitArgs {
+                commit,
+                ..GitArgs::default()
+            };
+            tip_commit(&test_repo.repo, &args).unwrap().id()
+        };
+        assert_eq!(tip(None), head);
+        assert_eq!(tip(Some(second.to_string())), second);
+        assert_eq!(tip(Some(format!("{}..{}", first, second))), second);
+        assert_eq!(tip(Some(format!("^{}", first))), head);
+    }
 }
@@ -0,0 +1,39 @@
+//! Framing of the generated HTML pages
+//!
+//! Every page is a single self-contained file: by default the D3 subset
+//! bundled with ocs (`assets/ocs-d3.js`) is embedded into the page, so
+//! reports can be archived, mailed or opened in air-gapped environments.
+//! Loading the full D3 library from a CDN is available on request.
+
+use std::io::{self, Write};
+
+/// Bundled implementation of the D3 API subset used by the ocs pages
+const OCS_D3: &str = include_str!("../assets/ocs-d3.js");
+
+const D3_CDN_URL: &str = "https://cdn.jsdelivr.net/npm/d3@7";
+
+/// Where the D3 library of generated HTML pages comes from
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum D3Source {
+    /// Embed the bundled D3 subset; the page works without network access
+    Inline,
+    /// Load the full D3 library from the jsDelivr CDN
+    Cdn,
+}
+
+/// Write the document head with the D3 library
+///
+/// The page content (container and chart script) is written afterwards by
+/// the analysis.
+pub fn write_head(writer: &mut dyn Write, d3_source: D3Source) -> io::Result<()> {
+    writer.write_all(b"<!DOCTYPE html>\n<meta charset=\"utf-8\">\n")?;
+    match d3_source {
+        D3Source::Inline => {
+            writer.write_all(b"<script>\n")?;
+            writer.write_all(OCS_D3.as_bytes())?;
+            writer.write_all(b"</script>\n")?;
+        }
+        D3Source::Cdn => writeln!(writer, "<script src=\"{}\"></script>", D3_CDN_URL)?,
+    }
+    Ok(())
+}
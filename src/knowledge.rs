@@ -0,0 +1,297 @@
+//! Distribution of knowledge among the authors
+//!
+//! Knowledge of code is modelled by line contributions: the more lines of an
+//! entity an author added, the better the author knows it. Code mostly
+//! written by developers who left the project is a knowledge island: nobody
+//! is left who knows it well. This is harmful in hotspots, code that keeps
+//! being changed (see Tornhill, "Your Code as a Crime Scene").
+//!
+//! Entities are files or, aggregated by directory depth, components.
+//!
+//! The truck factor is the number of authors who would have to leave until
+//! more than half of the files have no owner left, i.e. no author who knows
+//! them. It is computed greedily like by Avelino et al., "A novel approach
+//! for estimating truck factors": the author owning most of the remaining
+//! files leaves first.
+
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use time::OffsetDateTime;
+
+use crate::changes::{CommitChanges, FileChange};
+use crate::metrics::{contributions, months_between, Contribution};
+
+/// Component of a path: its directory `depth` levels deep, or the directory
+/// of files further up, `.` for files at the top level. Depth 0 is the path
+/// itself.
+pub fn component(path: &str, depth: usize) -> String {
+    if depth == 0 {
+        return path.to_owned();
+    }
+    let directories: Vec<&str> = match path.rsplit_once('/') {
+        Some((directory, _)) => directory.split('/').take(depth).collect(),
+        None => return ".".to_owned(),
+    };
+    directories.join("/")
+}
+
+/// The change log with the paths replaced by their components; changes of a
+/// commit to the same component are merged
+pub fn component_log(log: &[CommitChanges], depth: usize) -> Vec<CommitChanges> {
+    if depth == 0 {
+        return log.to_vec();
+    }
+    log.iter()
+        .map(|commit| {
+            let mut changes: BTreeMap<String, FileChange> = BTreeMap::new();
+            for change in &commit.changes {
+                let path = component(&change.path, depth);
+                let merged = changes.entry(path.clone()).or_insert_with(|| FileChange {
+                    path,
+                    added: 0,
+                    deleted: 0,
+                    submodule: change.submodule,
+                    binary: change.binary,
+                });
+                merged.added += change.added;
+                merged.deleted += change.deleted;
+                merged.submodule &= change.submodule;
+                merged.binary &= change.binary;
+            }
+            CommitChanges {
+                changes: changes.into_values().collect(),
+                ..commit.clone()
+            }
+        })
+        .collect()
+}
+
+/// Lines of code per component
+pub fn component_sizes(
+    lines_of_code: BTreeMap<String, usize>,
+    depth: usize,
+) -> BTreeMap<String, usize> {
+    let mut sizes = BTreeMap::new();
+    for (path, code) in lines_of_code {
+        *sizes.entry(component(&path, depth)).or_default() += code;
+    }
+    sizes
+}
+
+/// Authors without commits in the last `months` whole months
+pub fn inactive_authors(
+    log: &[CommitChanges],
+    months: u32,
+    now: OffsetDateTime,
+) -> BTreeSet<String> {
+    let mut last_commit: BTreeMap<&str, OffsetDateTime> = BTreeMap::new();
+    for commit in log {
+        let last = last_commit.entry(&commit.author).or_insert(commit.date());
+        *last = (*last).max(commit.date());
+    }
+    last_commit
+        .into_iter()
+        .filter(|(_, last)| months_between(*last, now) >= months)
+        .map(|(author, _)| author.to_owned())
+        .collect()
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct KnowledgeLoss {
+    pub entity: String,
+    pub revisions: u64,
+    pub code: usize,
+    /// The author who added most lines
+    #[serde(rename = "main-dev")]
+    pub main_dev: String,
+    #[serde(rename = "main-dev-left")]
+    pub main_dev_left: bool,
+    /// Share of the added lines whose authors left, 0 to 1
+    pub loss: f64,
+    /// The loss weighted by the revisions relative to the most revised
+    /// entity, 0 to 1
+    pub risk: f64,
+}
+
+/// Knowledge lost by the departure of authors for the entities with lines
+/// of code, highest risk first
+pub fn knowledge_loss(
+    log: &[CommitChanges],
+    lines_of_code: BTreeMap<String, usize>,
+    departed: &BTreeSet<String>,
+) -> Vec<KnowledgeLoss> {
+    let authors = contributions(log);
+    let max_revisions = authors
+        .values()
+        .map(|a| a.iter().map(|c| c.revisions).sum())
+        .max()
+        .unwrap_or(1);
+    let round = |value: f64| (value * 100.0).round() / 100.0;
+
+    let mut losses: Vec<KnowledgeLoss> = lines_of_code
+        .into_iter()
+        .filter_map(|(entity, code)| {
+            let authors = authors.get(&entity)?;
+            let main = authors.first()?;
+            let revisions: u64 = authors.iter().map(|a| a.revisions).sum();
+            let total_added: u64 = authors.iter().map(|a| a.added).sum();
+            let lost_added: u64 = authors
+                .iter()
+                .filter(|a| departed.contains(&a.author))
+                .map(|a| a.added)
+                .sum();
+            let loss = match total_added {
+                0 => 0.0,
+                _ => lost_added as f64 / total_added as f64,
+            };
+            Some(KnowledgeLoss {
+                revisions,
+                code,
+                main_dev: main.author.clone(),
+                main_dev_left: departed.contains(&main.author),
+                loss: round(loss),
+                risk: round(loss * revisions as f64 / max_revisions as f64),
+                entity,
+            })
+        })
+        .collect();
+    losses.sort_by(|a, b| {
+        b.risk
+            .total_cmp(&a.risk)
+            .then(b.loss.total_cmp(&a.loss))
+            .then(b.revisions.cmp(&a.revisions))
+    });
+    losses
+}
+
+/// Minimum share of the added lines of a file that makes an author an owner
+pub const OWNER_SHARE: f64 = 0.25;
+
+/// The authors knowing an entity: the main developer and everybody who added
+/// at least `OWNER_SHARE` of the lines, biggest contributor first
+pub fn owners(authors: &[Contribution]) -> Vec<String> {
+    let total_added: u64 = authors.iter().map(|a| a.added).sum();
+    authors
+        .iter()
+        .enumerate()
+        .filter(|(i, a)| *i == 0 || a.added as f64 >= OWNER_SHARE * total_added as f64)
+        .map(|(_, a)| a.author.clone())
+        .collect()
+}
+
+/// The critical authors in order of departure and the files they leave
+/// without owner, given the owners of every file
+pub fn truck_factor(owners: &BTreeMap<String, Vec<String>>) -> (Vec<String>, BTreeSet<String>) {
+    let mut critical: Vec<String> = Vec::new();
+    let mut orphaned: BTreeSet<String> = BTreeSet::new();
+    while orphaned.len() * 2 <= owners.len() {
+        let mut owned: BTreeMap<&str, usize> = BTreeMap::new();
+        for (_, file_owners) in owners.iter().filter(|(f, _)| !orphaned.contains(*f)) {
+            for owner in file_owners.iter().filter(|o| !critical.contains(o)) {
+                *owned.entry(owner).or_default() += 1;
+            }
+        }
+        // The first of the authors owning most files leaves
+        let Some((author, _)) = owned.into_iter().fold(
+            None,
+            |top: Option<(&str, usize)>, (author, count)| match top {
+                Some((_, max)) if max >= count => top,
+                _ => Some((author, count)),
+            },
+        ) else {
+            break;
+        };
+        critical.push(author.to_owned());
+        orphaned.extend(
+            owners
+                .iter()
+                .filter(|(_, file_owners)| file_owners.iter().all(|o| critical.contains(o)))
+                .map(|(file, _)| file.clone()),
+        );
+    }
+    (critical, orphaned)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use time::macros::datetime;
+
+    #[test]
+    fn verify_knowledge_loss_of_components() {
+        assert_eq!(component("src/ui/view.rs", 1), "src");
+        assert_eq!(component("src/main.rs", 2), "src");
+        assert_eq!(component("main.rs", 1), ".");
+
+        let log = vec![
+            CommitChanges::fixture("bob", datetime!(2024-06-01 12:00 UTC).unix_timestamp())
+                .with_change("src/a.rs", 10, 0)
+                .with_change("src/b.rs", 10, 0),
+            CommitChanges::fixture("alice", datetime!(2023-01-01 12:00 UTC).unix_timestamp())
+                .with_change("src/a.rs", 60, 0),
+            CommitChanges::fixture("alice", datetime!(2023-01-02 12:00 UTC).unix_timestamp())
+                .with_change("doc/x.md", 5, 0),
+        ];
+        let departed = inactive_authors(&log, 6, datetime!(2024-07-01 00:00 UTC));
+        assert_eq!(departed, BTreeSet::from(["alice".to_owned()]));
+
+        let log = component_log(&log, 1);
+        assert_eq!(log[0].changes.len(), 1);
+        let sizes = component_sizes(
+            BTreeMap::from([
+                ("src/a.rs".to_owned(), 50),
+                ("src/b.rs".to_owned(), 20),
+                ("doc/x.md".to_owned(), 3),
+            ]),
+            1,
+        );
+        let losses = knowledge_loss(&log, sizes, &departed);
+        assert_eq!(losses[0].entity, "src");
+        assert_eq!(losses[0].code, 70);
+        assert_eq!(losses[0].main_dev, "alice");
+        assert!(losses[0].main_dev_left);
+        assert_eq!((losses[0].loss, losses[0].risk), (0.75, 0.75));
+        assert_eq!(losses[1].entity, "doc");
+        assert_eq!((losses[1].loss, losses[1].risk), (1.0, 0.5));
+    }
+
+    #[test]
+    fn verify_truck_factor() {
+        let contribution = |author: &str, added: u64| Contribution {
+            author: author.to_owned(),
+            revisions: 1,
+            added,
+            deleted: 0,
+        };
+        assert_eq!(
+            owners(&[
+                contribution("a", 60),
+                contribution("b", 30),
+                contribution("c", 10)
+            ]),
+            vec!["a", "b"]
+        );
+
+        let owners: BTreeMap<String, Vec<String>> = [
+            ("1", vec!["a"]),
+            ("2", vec!["a", "b"]),
+            ("3", vec!["b"]),
+            ("4", vec!["c"]),
+        ]
+        .into_iter()
+        .map(|(file, owners)| {
+            (
+                file.to_owned(),
+                owners.into_iter().map(str::to_owned).collect(),
+            )
+        })
+        .collect();
+        let (critical, orphaned) = truck_factor(&owners);
+        assert_eq!(critical, vec!["a", "b"]);
+        assert_eq!(orphaned, BTreeSet::from(["1", "2", "3"].map(str::to_owned)));
+        assert_eq!(
+            truck_factor(&BTreeMap::new()),
+            (Vec::new(), BTreeSet::new())
+        );
+    }
+}
@@ -1,3 +1,22 @@
+pub mod cache;
+pub mod changes;
+pub mod chart;
 pub mod cli;
+pub mod columnar;
+pub mod config;
+pub mod coupling;
+pub mod defects;
 pub mod git;
+pub mod html;
+pub mod knowledge;
+pub mod markdown;
+pub mod messages;
+pub mod metrics;
 pub mod progress;
+pub mod ranking;
+pub mod result;
+pub mod risk;
+pub mod sqlite;
+pub mod table;
+pub mod trend;
+pub mod xray;
@@ -1,29 +1,81 @@
+pub mod cache;
+pub mod changes;
+pub mod chart;
 pub mod cli;
+pub mod columnar;
+pub mod config;
+pub mod coupling;
+pub mod defects;
 pub mod git;
+pub mod html;
+pub mod knowledge;
+pub mod markdown;
+pub mod messages;
+pub mod metrics;
 pub mod progress;
+pub mod ranking;
+pub mod result;
+pub mod risk;
+pub mod sqlite;
+pub mod table;
+pub mod trend;
+pub mod xray;
 
 use crate::cli::git_common_args_extension;
-use crate::git::determine_commits_to_analyse;
 use crate::progress::configure_progress_visualization;
 
-use clap::Arg;
-use cli::{CommonArgs, GitArgs, OutputFormat};
+use clap::{value_parser, Arg, ArgAction};
+use cli::{read_manifest, CommonArgs, GitArgs, OutputFormat, ProjectRepository};
+use config::Config;
+use html::D3Source;
+use log::error;
 
 pub mod subcommands {
+    #[macro_use]
+    pub mod cache;
     #[macro_use]
     pub mod cloc;
     #[macro_use]
+    pub mod coupling;
+    #[macro_use]
+    pub mod coupling_trend;
+    #[macro_use]
+    pub mod defects;
+    #[macro_use]
+    pub mod developer_timeline;
+    #[macro_use]
+    pub mod entity_effort;
+    #[macro_use]
+    pub mod export_log;
+    #[macro_use]
+    pub mod function_coupling;
+    #[macro_use]
     pub mod hotspot;
     #[macro_use]
+    pub mod hotspot_trend;
+    #[macro_use]
+    pub mod knowledge_loss;
+    #[macro_use]
+    pub mod messages;
+    #[macro_use]
+    pub mod report;
+    #[macro_use]
     pub mod revisions;
     #[macro_use]
+    pub mod risk;
+    #[macro_use]
     pub mod summary;
+    #[macro_use]
+    pub mod truck_factor;
+    #[macro_use]
+    pub mod xray;
 }
 
 use crate::cli::{common_builder, setup_logger};
 use clap::Command;
 use std::ffi::OsString;
 use std::path::PathBuf;
+use std::process;
 
 fn main() {
     let builder = common_builder()
@@ -36,44 +88,233 @@ fn main() {
                 .arg_required_else_help(true)
                 .arg(Arg::new("config-key").help("config item to set")),
         );
+    let builder = cache_command!(builder);
     let builder = cloc_command!(builder);
+    let builder = coupling_command!(builder);
+    let builder = coupling_trend_command!(builder);
+    let builder = defects_command!(builder);
+    let builder = developer_timeline_command!(builder);
+    let builder = entity_effort_command!(builder);
+    let builder = export_log_command!(builder);
+    let builder = function_coupling_command!(builder);
     let builder = hotspot_command!(builder);
+    let builder = hotspot_trend_command!(builder);
+    let builder = knowledge_loss_command!(builder);
+    let builder = messages_command!(builder);
+    let builder = report_command!(builder);
     let builder = revisions_command!(builder);
+    let builder = risk_command!(builder);
     let builder = summary_command!(builder);
+    let builder = truck_factor_command!(builder);
+    let builder = xray_command!(builder);
 
     let matches = builder.get_matches();
 
     configure_progress_visualization(matches.get_flag("progress"));
     let verbose = matches.get_count("verbose") as u64;
     setup_logger(verbose);
-    let mut common_args = CommonArgs::new(matches.get_one::<PathBuf>("DIRECTORY"));
+    let mut repositories: Vec<ProjectRepository> = matches
+        .get_many::<PathBuf>("DIRECTORY")
+        .into_iter()
+        .flatten()
+        .map(|path| ProjectRepository::from_path(path))
+        .collect();
+    if let Some(manifest) = matches.get_one::<PathBuf>("MANIFEST") {
+        repositories.extend(read_manifest(manifest).unwrap_or_else(|e| {
+            error!("Unable to read manifest {}: {}", manifest.display(), e);
+            process::exit(2);
+        }));
+    }
+    let mut common_args = CommonArgs::with_repositories(repositories).unwrap_or_else(|e| {
+        error!("{}", e);
+        process::exit(2);
+    });
+    // Only the commands using the configuration file read it, so a broken
+    // file does not fail the others
+    let config_file = matches.get_one::<PathBuf>("CONFIG");
+    let load_config = |project_dir: &str| {
+        Config::load(config_file, project_dir).unwrap_or_else(|e| {
+            error!("Unable to read configuration: {}", e);
+            process::exit(2);
+        })
+    };
     common_args.format = *matches
         .get_one::<OutputFormat>("format")
         .expect("Option with default is never None");
     common_args.output = matches.get_one::<PathBuf>("FILE");
+    common_args.use_cache = !matches.get_flag("no-cache");
+    common_args.recurse_submodules = matches.get_flag("recurse-submodules");
+    common_args.template_dir = matches.get_one::<PathBuf>("TEMPLATE_DIR");
+    common_args.ranking_from_cli_args(&matches);
+    if matches.get_flag("d3-cdn") {
+        common_args.d3_source = D3Source::Cdn;
+    }
 
     // process the respective subcommand
-    match matches.subcommand() {
+    let result = match matches.subcommand() {
         Some(("config", sub_matches)) => {
             println!(
                 "Pushing to {}",
                 sub_matches.get_one::<String>("REMOTE").expect("required")
             );
+            Ok(())
+        }
+        Some((subcommands::cache::COMMAND, sub_matches)) => {
+            subcommands::cache::run(common_args, sub_matches)
+        }
+        Some((subcommands::cloc::COMMAND, _sub_matches)) => subcommands::cloc::run(common_args),
+        Some((subcommands::coupling::COMMAND, sub_matches)) => {
+            let git_args = GitArgs::from_cli_args(sub_matches);
+            let coupling_args = subcommands::coupling::CouplingArgs::from_cli_args(sub_matches);
+            subcommands::coupling::run(common_args, git_args, coupling_args)
+        }
+        Some((subcommands::coupling_trend::COMMAND, sub_matches)) => {
+            let git_args = GitArgs::from_cli_args(sub_matches);
+            let trend_args = subcommands::hotspot_trend::TrendArgs::from_cli_args(sub_matches);
+            let entity = |id: &str| {
+                sub_matches
+                    .get_one::<String>(id)
+                    .expect("Required argument is never None")
+                    .clone()
+            };
+            let pair_args = subcommands::coupling_trend::PairArgs {
+                entity: entity("ENTITY"),
+                coupled: entity("COUPLED"),
+                grouping: subcommands::coupling::grouping_from_cli_args(sub_matches),
+                max_changeset_size: *sub_matches
+                    .get_one::<usize>("max-changeset-size")
+                    .expect("Option with default is never None"),
+            };
+            subcommands::coupling_trend::run(common_args, git_args, trend_args, pair_args)
+        }
+        Some((subcommands::defects::COMMAND, sub_matches)) => {
+            common_args.config = load_config(&common_args.project_dir);
+            let git_args = GitArgs::from_cli_args(sub_matches);
+            let defect_args = subcommands::defects::DefectArgs {
+                fix_pattern: sub_matches.get_one::<regex::Regex>("fix-pattern").cloned(),
+                bug_tickets: sub_matches.get_one::<PathBuf>("bug-tickets").cloned(),
+                ticket_pattern: sub_matches
+                    .get_one::<regex::Regex>("ticket-pattern")
+                    .expect("Option with default is never None")
+                    .clone(),
+            };
+            subcommands::defects::run(common_args, git_args, defect_args)
         }
-        Some((subcommands::cloc::COMMAND, _sub_matches)) => {
-            subcommands::cloc::run(common_args);
+        Some((subcommands::developer_timeline::COMMAND, sub_matches)) => {
+            let git_args = GitArgs::from_cli_args(sub_matches);
+            subcommands::developer_timeline::run(common_args, git_args)
+        }
+        Some((subcommands::entity_effort::COMMAND, sub_matches)) => {
+            let git_args = GitArgs::from_cli_args(sub_matches);
+            let depth = *sub_matches
+                .get_one::<usize>("depth")
+                .expect("Option with default is never None");
+            subcommands::entity_effort::run(common_args, git_args, depth)
+        }
+        Some((subcommands::export_log::COMMAND, sub_matches)) => {
+            let git_args = GitArgs::from_cli_args(sub_matches);
+            let log_format = *sub_matches
+                .get_one::<subcommands::export_log::LogFormat>("log-format")
+                .expect("Option with default is never None");
+            subcommands::export_log::check_global_args(&matches)
+                .and_then(|_| subcommands::export_log::run(common_args, git_args, log_format))
+        }
+        Some((subcommands::function_coupling::COMMAND, sub_matches)) => {
+            let git_args = GitArgs::from_cli_args(sub_matches);
+            let coupling_args = subcommands::coupling::CouplingArgs::from_cli_args(sub_matches);
+            let entities: Vec<&str> = sub_matches
+                .get_many::<String>("ENTITY")
+                .expect("Required argument is never None")
+                .map(|e| e.as_str())
+                .collect();
+            subcommands::function_coupling::run(common_args, git_args, coupling_args, &entities)
         }
         Some((subcommands::hotspot::COMMAND, sub_matches)) => {
             let git_args = GitArgs::from_cli_args(sub_matches);
-            subcommands::hotspot::run(common_args, git_args).unwrap();
+            subcommands::hotspot::run(common_args, git_args)
+        }
+        Some((subcommands::hotspot_trend::COMMAND, sub_matches)) => {
+            let git_args = GitArgs::from_cli_args(sub_matches);
+            let trend_args = subcommands::hotspot_trend::TrendArgs::from_cli_args(sub_matches);
+            let entities: Vec<String> = sub_matches
+                .get_many::<String>("ENTITY")
+                .into_iter()
+                .flatten()
+                .cloned()
+                .collect();
+            let max_entities = *sub_matches
+                .get_one::<usize>("entities")
+                .expect("Option with default is never None");
+            subcommands::hotspot_trend::run(
+                common_args,
+                git_args,
+                trend_args,
+                entities,
+                max_entities,
+            )
+        }
+        Some((subcommands::knowledge_loss::COMMAND, sub_matches)) => {
+            let git_args = GitArgs::from_cli_args(sub_matches);
+            let departed = match sub_matches.get_many::<String>("author") {
+                Some(authors) => {
+                    subcommands::knowledge_loss::Departed::Authors(authors.cloned().collect())
+                }
+                None => subcommands::knowledge_loss::Departed::InactiveMonths(
+                    *sub_matches
+                        .get_one::<u32>("inactive-months")
+                        .expect("Option with default is never None"),
+                ),
+            };
+            let depth = *sub_matches
+                .get_one::<usize>("depth")
+                .expect("Option with default is never None");
+            subcommands::knowledge_loss::run(common_args, git_args, departed, depth)
+        }
+        Some((subcommands::messages::COMMAND, sub_matches)) => {
+            common_args.config = load_config(&common_args.project_dir);
+            let git_args = GitArgs::from_cli_args(sub_matches);
+            let analysis = match sub_matches.get_flag("classify") {
+                true => subcommands::messages::MessageAnalysis::Classes,
+                false => subcommands::messages::MessageAnalysis::Terms {
+                    min_length: *sub_matches
+                        .get_one::<usize>("min-length")
+                        .expect("Option with default is never None"),
+                },
+            };
+            subcommands::messages::run(common_args, git_args, analysis)
+        }
+        Some((subcommands::report::COMMAND, sub_matches)) => {
+            let git_args = GitArgs::from_cli_args(sub_matches);
+            let report_args = subcommands::report::ReportArgs::from_cli_args(sub_matches);
+            subcommands::report::run(common_args, git_args, report_args)
         }
         Some((subcommands::revisions::COMMAND, sub_matches)) => {
             let git_args = GitArgs::from_cli_args(sub_matches);
-            subcommands::revisions::run(common_args, git_args).unwrap();
+            subcommands::revisions::run(common_args, git_args)
+        }
+        Some((subcommands::risk::COMMAND, sub_matches)) => {
+            common_args.config = load_config(&common_args.project_dir);
+            let git_args = GitArgs::from_cli_args(sub_matches);
+            subcommands::risk::run(common_args, git_args)
         }
         Some((subcommands::summary::COMMAND, sub_matches)) => {
             let git_args = GitArgs::from_cli_args(sub_matches);
-            subcommands::summary::run(common_args, git_args).unwrap();
+            subcommands::summary::run(common_args, git_args)
+        }
+        Some((subcommands::truck_factor::COMMAND, sub_matches)) => {
+            let git_args = GitArgs::from_cli_args(sub_matches);
+            let depth = *sub_matches
+                .get_one::<usize>("depth")
+                .expect("Option with default is never None");
+            let orphaned = sub_matches.get_flag("orphaned");
+            subcommands::truck_factor::run(common_args, git_args, depth, orphaned)
+        }
+        Some((subcommands::xray::COMMAND, sub_matches)) => {
+            let git_args = GitArgs::from_cli_args(sub_matches);
+            let entity = sub_matches
+                .get_one::<String>("ENTITY")
+                .expect("Required argument is never None");
+            subcommands::xray::run(common_args, git_args, entity)
         }
         // Further commands can be called as sub processes
         // Since they are not known at this point they will be not listed when calling help
@@ -86,7 +327,16 @@ fn main() {
                 .flatten()
                 .collect::<Vec<_>>();
             println!("Calling out to {ext:?} with {args:?}");
+            Ok(())
         }
         _ => unreachable!(), // If all subcommands are defined above, anything else is unreachable!()
+    };
+    match result {
+        Err(e) if cli::is_broken_pipe(e.as_ref()) => {}
+        Err(e) => {
+            error!("{}", e);
+            process::exit(2);
+        }
+        Ok(()) => {}
     }
 }
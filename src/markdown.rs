@@ -0,0 +1,108 @@
+//! Markdown tables, e.g. for pull request comments, wikis and job summaries
+//!
+//! The columns and their names are the ones of the CSV output; numeric
+//! columns are right aligned.
+
+use std::error::Error;
+use std::io::Write;
+
+use crate::result::AnalysisResult;
+
+/// Write the result as table below a `##` heading and a line describing
+/// what was analysed
+pub fn write_table(writer: &mut dyn Write, result: &AnalysisResult) -> Result<(), Box<dyn Error>> {
+    writeln!(writer, "## {}", result.title)?;
+    writeln!(writer)?;
+    if !result.metadata.analysis.is_empty() {
+        writeln!(writer, "_{}_", result.metadata.describe())?;
+        writeln!(writer)?;
+    }
+    write_row(writer, result.columns.iter().map(|c| c.name.clone()))?;
+    write_row(
+        writer,
+        result.columns.iter().map(|c| {
+            if c.column_type.is_numeric() {
+                "---:".to_owned()
+            } else {
+                "---".to_owned()
+            }
+        }),
+    )?;
+    for row in &result.rows {
+        write_row(writer, row.iter().map(|v| v.to_string()))?;
+    }
+    Ok(())
+}
+
+fn write_row(
+    writer: &mut dyn Write,
+    cells: impl Iterator<Item = String>,
+) -> Result<(), Box<dyn Error>> {
+    write!(writer, "|")?;
+    for cell in cells {
+        write!(writer, " {} |", escape_cell(&cell))?;
+    }
+    writeln!(writer)?;
+    Ok(())
+}
+
+/// Cells must not break the table: escape pipes, join lines
+fn escape_cell(cell: &str) -> String {
+    cell.replace('|', "\\|")
+        .replace("\r\n", "<br>")
+        .replace('\n', "<br>")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::result::Metadata;
+    use serde::Serialize;
+
+    #[derive(Default, Serialize)]
+    struct Record {
+        entity: &'static str,
+        revisions: u64,
+    }
+
+    #[test]
+    fn verify_markdown_table() {
+        let records = [
+            Record {
+                entity: "src/a|b.rs",
+                revisions: 12,
+            },
+            Record {
+                entity: "README.md",
+                revisions: 3,
+            },
+        ];
+        let mut out = Vec::new();
+        let result =
+            AnalysisResult::from_records("Hotspots", Metadata::default(), &records).unwrap();
+        write_table(&mut out, &result).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "## Hotspots\n\n\
+             | entity | revisions |\n\
+             | --- | ---: |\n\
+             | src/a\\|b.rs | 12 |\n\
+             | README.md | 3 |\n"
+        );
+    }
+
+    #[test]
+    fn verify_header_of_empty_table() {
+        let records: [Record; 0] = [];
+        let mut out = Vec::new();
+        let result =
+            AnalysisResult::from_records("Hotspots", Metadata::default(), &records).unwrap();
+        write_table(&mut out, &result).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "## Hotspots\n\n\
+             | entity | revisions |\n\
+             | --- | ---: |\n"
+        );
+    }
+}
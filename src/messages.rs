@@ -0,0 +1,199 @@
+//! Analysis of commit messages
+//!
+//! Commit messages tell why code changed. The frequency of terms gives an
+//! overview of what a project or a period was about; classifying commits by
+//! message patterns, e.g. bug fixes, tells which files attract which kind of
+//! work. Classes are regular expressions searched in the whole message; a
+//! commit may be in several classes. The default classes follow the
+//! conventional commit types, besides `fix` matching the usual bug fix words.
+//! They are replaced by the `[messages.classes]` table of the configuration
+//! file, e.g.
+//!
+//! ```toml
+//! [messages.classes]
+//! fix = '(?i)\b(fix|bug|hotfix|defect)'
+//! security = '(?i)\b(cve|vulnerability)'
+//! ```
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::changes::CommitChanges;
+
+/// Words too common to tell anything
+const STOP_WORDS: &[&str] = &[
+    "about", "after", "all", "also", "and", "are", "but", "can", "for", "from", "has", "have",
+    "into", "its", "not", "now", "off", "one", "only", "out", "than", "that", "the", "then",
+    "there", "this", "use", "was", "were", "when", "which", "will", "with", "without", "you",
+];
+
+/// Settings of the commit message analysis
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MessageConfig {
+    /// Regular expressions by class name
+    pub classes: BTreeMap<String, String>,
+}
+
+impl Default for MessageConfig {
+    fn default() -> Self {
+        let conventional = |types: &str| format!(r"^({})(\([^)]*\))?!?:", types);
+        MessageConfig {
+            classes: BTreeMap::from([
+                (
+                    "fix".to_owned(),
+                    format!(
+                        r"(?i)\b(fix(e[sd])?|bugs?|hotfix(es)?)\b|{}",
+                        conventional("fix")
+                    ),
+                ),
+                ("feat".to_owned(), conventional("feat")),
+                ("refactor".to_owned(), conventional("refactor|perf|style")),
+                ("docs".to_owned(), conventional("docs")),
+                ("test".to_owned(), conventional("test")),
+                ("chore".to_owned(), conventional("chore|build|ci")),
+            ]),
+        }
+    }
+}
+
+impl MessageConfig {
+    /// The compiled patterns of the classes, ordered by name
+    pub fn patterns(&self) -> Result<Vec<(String, Regex)>, regex::Error> {
+        self.classes
+            .iter()
+            .map(|(class, pattern)| Ok((class.clone(), Regex::new(pattern)?)))
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct TermFrequency {
+    pub term: String,
+    /// Occurrences in all messages
+    pub count: u64,
+    /// Commits mentioning the term
+    pub commits: u64,
+}
+
+/// Frequency of the lower-cased words with at least `min_length` letters,
+/// except stop words and numbers; most frequent first
+pub fn term_frequencies(log: &[CommitChanges], min_length: usize) -> Vec<TermFrequency> {
+    let mut terms: BTreeMap<String, (u64, u64)> = BTreeMap::new();
+    for commit in log {
+        let mut mentioned = BTreeSet::new();
+        for word in commit.message.split(|c: char| !c.is_alphanumeric()) {
+            let term = word.to_lowercase();
+            if term.chars().count() < min_length
+                || term.chars().all(|c| c.is_numeric())
+                || STOP_WORDS.contains(&term.as_str())
+            {
+                continue;
+            }
+            let (count, commits) = terms.entry(term.clone()).or_default();
+            *count += 1;
+            if mentioned.insert(term) {
+                *commits += 1;
+            }
+        }
+    }
+    let mut frequencies: Vec<TermFrequency> = terms
+        .into_iter()
+        .map(|(term, (count, commits))| TermFrequency {
+            term,
+            count,
+            commits,
+        })
+        .collect();
+    frequencies.sort_by_key(|t| Reverse(t.count));
+    frequencies
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct MessageClass {
+    pub entity: String,
+    pub class: String,
+    /// Revisions of the entity by commits of the class
+    pub matches: u64,
+    pub revisions: u64,
+}
+
+/// Revisions of every entity by the commits of every class; only entities
+/// and classes with matches, most matches first
+pub fn classify(log: &[CommitChanges], patterns: &[(String, Regex)]) -> Vec<MessageClass> {
+    let mut revisions: BTreeMap<&str, u64> = BTreeMap::new();
+    let mut matches: BTreeMap<(&str, &str), u64> = BTreeMap::new();
+    for commit in log {
+        let classes: Vec<&str> = patterns
+            .iter()
+            .filter(|(_, pattern)| pattern.is_match(&commit.message))
+            .map(|(class, _)| class.as_str())
+            .collect();
+        for change in &commit.changes {
+            *revisions.entry(&change.path).or_default() += 1;
+            for class in &classes {
+                *matches.entry((&change.path, class)).or_default() += 1;
+            }
+        }
+    }
+    let mut classified: Vec<MessageClass> = matches
+        .into_iter()
+        .map(|((entity, class), matches)| MessageClass {
+            entity: entity.to_owned(),
+            class: class.to_owned(),
+            matches,
+            revisions: revisions[entity],
+        })
+        .collect();
+    classified.sort_by_key(|c| Reverse(c.matches));
+    classified
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn verify_terms_and_classes() {
+        let log = vec![
+            CommitChanges::fixture("", 1)
+                .with_message("fix(parser): crash on empty input")
+                .with_changes(&["a.rs", "b.rs"]),
+            CommitChanges::fixture("", 2)
+                .with_message("feat: parse the input in 2 passes")
+                .with_changes(&["a.rs"]),
+            CommitChanges::fixture("", 3)
+                .with_message("Fixes bug #12 in Parser, parser tests")
+                .with_changes(&["a.rs"]),
+            CommitChanges::fixture("", 4)
+                .with_message("prefix all names")
+                .with_changes(&["a.rs"]),
+        ];
+        let terms = term_frequencies(&log, 3);
+        assert_eq!(
+            terms[0],
+            TermFrequency {
+                term: "parser".to_owned(),
+                count: 3,
+                commits: 2
+            }
+        );
+        assert!(!terms.iter().any(|t| t.term == "the" || t.term == "12"));
+
+        let patterns = MessageConfig::default().patterns().unwrap();
+        let classes = classify(&log, &patterns);
+        assert_eq!(
+            classes
+                .iter()
+                .map(|c| (c.entity.as_str(), c.class.as_str(), c.matches, c.revisions))
+                .collect::<Vec<_>>(),
+            vec![
+                ("a.rs", "fix", 2, 4),
+                ("a.rs", "feat", 1, 4),
+                ("b.rs", "fix", 1, 1)
+            ]
+        );
+    }
+}
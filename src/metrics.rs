@@ -0,0 +1,301 @@
+//! Entity metrics of the change log
+//!
+//! Besides the number of revisions, the change log tells how old an entity
+//! is, who knows it best, how much it is reworked and who works where. The
+//! metrics follow the Code Maat analyses `age`, `main-dev`, `entity-churn`
+//! and `entity-effort`.
+
+use serde::Serialize;
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use time::{macros::format_description, Duration, OffsetDateTime};
+
+use crate::changes::CommitChanges;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct EntityAge {
+    pub entity: String,
+    /// Whole months since the last change
+    #[serde(rename = "age-months")]
+    pub age_months: u32,
+    #[serde(rename = "last-change")]
+    pub last_change: String,
+}
+
+/// Age of all entities relative to `now`, oldest first
+pub fn age(log: &[CommitChanges], now: OffsetDateTime) -> Vec<EntityAge> {
+    let mut last_change: BTreeMap<&str, OffsetDateTime> = BTreeMap::new();
+    for commit in log {
+        let date = commit.date();
+        for change in &commit.changes {
+            let last = last_change.entry(&change.path).or_insert(date);
+            if date > *last {
+                *last = date;
+            }
+        }
+    }
+    let format = format_description!("[year]-[month]-[day]");
+    let mut ages: Vec<EntityAge> = last_change
+        .into_iter()
+        .map(|(entity, date)| EntityAge {
+            entity: entity.to_owned(),
+            age_months: months_between(date, now),
+            last_change: date.format(&format).unwrap_or_default(),
+        })
+        .collect();
+    ages.sort_by_key(|a| Reverse(a.age_months));
+    ages
+}
+
+/// Whole months from one point in time to a later one, 0 if not later
+pub fn months_between(from: OffsetDateTime, to: OffsetDateTime) -> u32 {
+    let months = (to.year() - from.year()) * 12 + (to.month() as i32 - from.month() as i32)
+        - i32::from(to.day() < from.day());
+    months.max(0) as u32
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Ownership {
+    pub entity: String,
+    /// The author who added most lines
+    #[serde(rename = "main-dev")]
+    pub main_dev: String,
+    pub added: u64,
+    #[serde(rename = "total-added")]
+    pub total_added: u64,
+    /// Share of the main developer of all added lines, 0 to 1
+    pub ownership: f64,
+}
+
+/// Main developer of all entities, by entity
+pub fn ownership(log: &[CommitChanges]) -> Vec<Ownership> {
+    contributions(log)
+        .into_iter()
+        .filter_map(|(entity, authors)| {
+            let total_added: u64 = authors.iter().map(|a| a.added).sum();
+            let main = authors.into_iter().next()?;
+            let ownership = if total_added == 0 {
+                0.0
+            } else {
+                (main.added as f64 / total_added as f64 * 100.0).round() / 100.0
+            };
+            Some(Ownership {
+                entity,
+                main_dev: main.author,
+                added: main.added,
+                total_added,
+                ownership,
+            })
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct Churn {
+    pub entity: String,
+    pub added: u64,
+    pub deleted: u64,
+    pub commits: u64,
+}
+
+/// Lines added and deleted per entity, most churned first
+pub fn churn(log: &[CommitChanges]) -> Vec<Churn> {
+    let mut churn: BTreeMap<&str, Churn> = BTreeMap::new();
+    for commit in log {
+        for change in &commit.changes {
+            let entry = churn.entry(&change.path).or_insert_with(|| Churn {
+                entity: change.path.clone(),
+                added: 0,
+                deleted: 0,
+                commits: 0,
+            });
+            entry.added += change.added;
+            entry.deleted += change.deleted;
+            entry.commits += 1;
+        }
+    }
+    let mut churn: Vec<Churn> = churn.into_values().collect();
+    churn.sort_by_key(|c| Reverse(c.added + c.deleted));
+    churn
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Contribution {
+    pub author: String,
+    pub revisions: u64,
+    pub added: u64,
+    pub deleted: u64,
+}
+
+/// Contributions of the authors per entity, biggest contributor first
+pub fn contributions(log: &[CommitChanges]) -> BTreeMap<String, Vec<Contribution>> {
+    let mut by_entity: BTreeMap<&str, BTreeMap<&str, Contribution>> = BTreeMap::new();
+    for commit in log {
+        for change in &commit.changes {
+            let contribution = by_entity
+                .entry(&change.path)
+                .or_default()
+                .entry(&commit.author)
+                .or_insert_with(|| Contribution {
+                    author: commit.author.clone(),
+                    revisions: 0,
+                    added: 0,
+                    deleted: 0,
+                });
+            contribution.revisions += 1;
+            contribution.added += change.added;
+            contribution.deleted += change.deleted;
+        }
+    }
+    by_entity
+        .into_iter()
+        .map(|(entity, authors)| {
+            let mut authors: Vec<Contribution> = authors.into_values().collect();
+            authors.sort_by(|a, b| b.added.cmp(&a.added).then(b.revisions.cmp(&a.revisions)));
+            (entity.to_owned(), authors)
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct EntityEffort {
+    pub entity: String,
+    pub author: String,
+    #[serde(rename = "author-revs")]
+    pub author_revs: u64,
+    #[serde(rename = "total-revs")]
+    pub total_revs: u64,
+}
+
+/// Revisions of every author per entity; the most revised entities first,
+/// within an entity the most active author first
+pub fn entity_effort(log: &[CommitChanges]) -> Vec<EntityEffort> {
+    let mut entities: Vec<(String, Vec<Contribution>)> = contributions(log).into_iter().collect();
+    let total = |authors: &[Contribution]| authors.iter().map(|a| a.revisions).sum::<u64>();
+    entities.sort_by_key(|(_, authors)| Reverse(total(authors)));
+    entities
+        .into_iter()
+        .flat_map(|(entity, mut authors)| {
+            let total_revs = total(&authors);
+            authors.sort_by_key(|a| Reverse(a.revisions));
+            authors.into_iter().map(move |a| EntityEffort {
+                entity: entity.clone(),
+                author: a.author,
+                author_revs: a.revisions,
+                total_revs,
+            })
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct WeeklyActivity {
+    pub author: String,
+    /// Monday of the week as `YYYY-MM-DD`
+    pub week: String,
+    pub commits: u64,
+    pub added: u64,
+    pub deleted: u64,
+}
+
+/// Commits and churn per author and week, by author and oldest week first
+pub fn developer_timeline(log: &[CommitChanges]) -> Vec<WeeklyActivity> {
+    let format = format_description!("[year]-[month]-[day]");
+    let mut by_author: BTreeMap<(&str, String), WeeklyActivity> = BTreeMap::new();
+    for commit in log {
+        let date = commit.date().date();
+        let monday = date - Duration::days(date.weekday().number_days_from_monday().into());
+        let week = monday.format(&format).unwrap_or_default();
+        let activity = by_author
+            .entry((&commit.author, week.clone()))
+            .or_insert_with(|| WeeklyActivity {
+                author: commit.author.clone(),
+                week,
+                commits: 0,
+                added: 0,
+                deleted: 0,
+            });
+        activity.commits += 1;
+        activity.added += commit.changes.iter().map(|c| c.added).sum::<u64>();
+        activity.deleted += commit.changes.iter().map(|c| c.deleted).sum::<u64>();
+    }
+    by_author.into_values().collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use time::macros::datetime;
+
+    #[test]
+    fn verify_age_in_months() {
+        let log = vec![
+            CommitChanges::fixture("a", datetime!(2024-01-15 12:00 UTC).unix_timestamp())
+                .with_change("x", 1, 1),
+            CommitChanges::fixture("a", datetime!(2024-03-20 12:00 UTC).unix_timestamp())
+                .with_change("x", 1, 1),
+            CommitChanges::fixture("a", datetime!(2023-11-01 12:00 UTC).unix_timestamp())
+                .with_change("y", 1, 1),
+        ];
+        let ages = age(&log, datetime!(2024-06-18 00:00 UTC));
+        assert_eq!(
+            ages.iter()
+                .map(|a| (a.entity.as_str(), a.age_months, a.last_change.as_str()))
+                .collect::<Vec<_>>(),
+            vec![("y", 7, "2023-11-01"), ("x", 2, "2024-03-20")]
+        );
+    }
+
+    #[test]
+    fn verify_main_developer() {
+        let log = vec![
+            CommitChanges::fixture("a", 1).with_change("x", 10, 1),
+            CommitChanges::fixture("b", 2).with_change("x", 25, 1),
+            CommitChanges::fixture("a", 3).with_change("x", 5, 1),
+        ];
+        let ownership = ownership(&log);
+        assert_eq!(ownership.len(), 1);
+        assert_eq!(ownership[0].main_dev, "b");
+        assert_eq!(ownership[0].added, 25);
+        assert_eq!(ownership[0].total_added, 40);
+        assert_eq!(ownership[0].ownership, 0.63);
+    }
+
+    #[test]
+    fn verify_effort_and_timeline() {
+        let time = |date: OffsetDateTime| date.unix_timestamp();
+        let log = vec![
+            CommitChanges::fixture("b", time(datetime!(2024-06-19 12:00 UTC)))
+                .with_change("y", 3, 1),
+            CommitChanges::fixture("a", time(datetime!(2024-06-17 08:00 UTC)))
+                .with_change("x", 1, 1),
+            CommitChanges::fixture("b", time(datetime!(2024-06-16 23:00 UTC)))
+                .with_change("x", 2, 1),
+            CommitChanges::fixture("b", time(datetime!(2024-06-10 09:00 UTC)))
+                .with_change("x", 4, 1),
+        ];
+        assert_eq!(
+            entity_effort(&log)
+                .iter()
+                .map(|e| (
+                    e.entity.as_str(),
+                    e.author.as_str(),
+                    e.author_revs,
+                    e.total_revs
+                ))
+                .collect::<Vec<_>>(),
+            vec![("x", "b", 2, 3), ("x", "a", 1, 3), ("y", "b", 1, 1)]
+        );
+        assert_eq!(
+            developer_timeline(&log)
+                .iter()
+                .map(|w| (w.author.as_str(), w.week.as_str(), w.commits, w.added))
+                .collect::<Vec<_>>(),
+            vec![
+                ("a", "2024-06-17", 1, 1),
+                ("b", "2024-06-10", 2, 6),
+                ("b", "2024-06-17", 1, 3)
+            ]
+        );
+    }
+}
@@ -0,0 +1,191 @@
+//! Ranking and filtering of analysis results
+//!
+//! `--sort-by`, `--desc`, `--top` and `--min` apply to the rows of any
+//! analysis result before it is formatted. Columns are addressed by the
+//! names of the CSV header (and JSON fields).
+
+use std::cmp::Ordering;
+
+use crate::result::{AnalysisResult, Value};
+
+/// Keep records whose column is at least `min`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Threshold {
+    pub column: String,
+    pub min: f64,
+}
+
+/// Parse a threshold like `revisions=10`
+pub fn parse_threshold(arg: &str) -> Result<Threshold, String> {
+    let (column, min) = arg
+        .split_once('=')
+        .ok_or_else(|| format!("'{}' is not a threshold like revisions=10", arg))?;
+    let min = min
+        .trim()
+        .parse()
+        .map_err(|_| format!("'{}' is not a number", min))?;
+    Ok(Threshold {
+        column: column.trim().to_owned(),
+        min,
+    })
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Ranking {
+    pub sort_by: Option<String>,
+    pub descending: bool,
+    pub top: Option<usize>,
+    pub min: Vec<Threshold>,
+}
+
+impl Ranking {
+    /// Filter by the thresholds, sort and keep the top rows
+    ///
+    /// Unknown columns are an error.
+    pub fn apply(&self, result: &mut AnalysisResult) -> Result<(), String> {
+        self.rank(result, true)
+    }
+
+    /// Like `apply`, but sort columns and thresholds the result does not
+    /// have are ignored; for commands combining analyses with different
+    /// columns
+    pub fn apply_where_present(&self, result: &mut AnalysisResult) -> Result<(), String> {
+        self.rank(result, false)
+    }
+
+    fn rank(&self, result: &mut AnalysisResult, strict: bool) -> Result<(), String> {
+        let column = |name: &str| -> Result<Option<usize>, String> {
+            match result.column(name) {
+                Some(column) => Ok(Some(column)),
+                None if strict => Err(format!(
+                    "Unknown column '{}'; columns are {}",
+                    name,
+                    result.column_names().join(", ")
+                )),
+                None => Ok(None),
+            }
+        };
+
+        let mut thresholds = Vec::new();
+        for threshold in &self.min {
+            if let Some(c) = column(&threshold.column)? {
+                if !result.columns[c].column_type.is_numeric() {
+                    return Err(format!("Column '{}' is not numeric", threshold.column));
+                }
+                thresholds.push((c, threshold.min));
+            }
+        }
+        let sort_by = match &self.sort_by {
+            Some(name) => column(name)?,
+            None => None,
+        };
+
+        result.rows.retain(|row| {
+            thresholds
+                .iter()
+                .all(|(c, min)| row[*c].as_f64().is_some_and(|v| v >= *min))
+        });
+        if let Some(c) = sort_by {
+            result.rows.sort_by(|a, b| {
+                let ordering = compare(&a[c], &b[c]);
+                if self.descending {
+                    ordering.reverse()
+                } else {
+                    ordering
+                }
+            });
+        }
+        if let Some(top) = self.top {
+            result.rows.truncate(top);
+        }
+        Ok(())
+    }
+}
+
+/// Numbers by value, anything else by its text
+fn compare(a: &Value, b: &Value) -> Ordering {
+    match (a.as_f64(), b.as_f64()) {
+        (Some(a), Some(b)) => a.partial_cmp(&b).unwrap_or(Ordering::Equal),
+        _ => a.to_string().cmp(&b.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::result::Metadata;
+    use serde::Serialize;
+
+    #[derive(Default, Serialize)]
+    struct Record {
+        entity: &'static str,
+        #[serde(rename = "n-revs")]
+        revisions: u64,
+    }
+
+    #[test]
+    fn verify_ranking() {
+        let records = [
+            Record {
+                entity: "a.rs",
+                revisions: 2,
+            },
+            Record {
+                entity: "b.rs",
+                revisions: 12,
+            },
+            Record {
+                entity: "c.rs",
+                revisions: 7,
+            },
+            Record {
+                entity: "d.rs",
+                revisions: 9,
+            },
+        ];
+        let mut result =
+            AnalysisResult::from_records("Revisions", Metadata::default(), &records).unwrap();
+        let ranking = Ranking {
+            sort_by: Some("n-revs".to_owned()),
+            descending: true,
+            top: Some(2),
+            min: vec![parse_threshold("n-revs=3").unwrap()],
+        };
+        ranking.apply(&mut result).unwrap();
+        assert_eq!(
+            result
+                .rows
+                .iter()
+                .map(|r| r[0].to_string())
+                .collect::<Vec<_>>(),
+            vec!["b.rs", "d.rs"]
+        );
+
+        let unknown = Ranking {
+            sort_by: Some("revisions".to_owned()),
+            ..Default::default()
+        };
+        assert!(unknown.apply(&mut result).is_err());
+    }
+
+    #[test]
+    fn verify_unknown_column_of_empty_result() {
+        let records: [Record; 0] = [];
+        let mut result =
+            AnalysisResult::from_records("Revisions", Metadata::default(), &records).unwrap();
+        let known = Ranking {
+            sort_by: Some("n-revs".to_owned()),
+            min: vec![parse_threshold("n-revs=3").unwrap()],
+            ..Default::default()
+        };
+        assert!(known.apply(&mut result).is_ok());
+        let unknown = Ranking {
+            min: vec![parse_threshold("revisions=3").unwrap()],
+            ..Default::default()
+        };
+        assert_eq!(
+            unknown.apply(&mut result),
+            Err("Unknown column 'revisions'; columns are entity, n-revs".to_owned())
+        );
+    }
+}
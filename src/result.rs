@@ -0,0 +1,648 @@
+//! Result model shared by all analyses
+//!
+//! Every analysis produces an [`AnalysisResult`]: rows of values in named,
+//! typed columns plus metadata about what was analysed. Ranking and all
+//! output formats work on this model, so they work alike for every analysis.
+//!
+//! Analyses keep computing their own record structs; `from_records` turns
+//! them into rows. The columns are the serialized fields, in declaration
+//! order and with their serde names.
+
+use serde::ser::{self, Impossible, SerializeSeq, SerializeStruct};
+use serde::{Serialize, Serializer};
+use std::error::Error;
+use std::fmt;
+use std::io::Write;
+use std::path::Path;
+use time::{macros::format_description, OffsetDateTime};
+
+use crate::changes::CommitChanges;
+use crate::chart::{Chart, ChartTemplates};
+use crate::cli::{CommonArgs, GitArgs, OutputFormatter};
+use crate::columnar;
+use crate::markdown;
+use crate::ranking::Ranking;
+use crate::sqlite;
+use crate::table::{self, TableOptions};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Null,
+    Bool(bool),
+    Integer(i64),
+    Float(f64),
+    Text(String),
+}
+
+impl Value {
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Value::Integer(i) => Some(*i as f64),
+            Value::Float(f) => Some(*f),
+            _ => None,
+        }
+    }
+
+    fn from_json(value: serde_json::Value) -> Self {
+        match value {
+            serde_json::Value::Null => Value::Null,
+            serde_json::Value::Bool(b) => Value::Bool(b),
+            serde_json::Value::Number(n) => match n.as_i64() {
+                Some(i) => Value::Integer(i),
+                None => Value::Float(n.as_f64().unwrap_or_default()),
+            },
+            serde_json::Value::String(s) => Value::Text(s),
+            other => Value::Text(other.to_string()),
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Value::Null => Ok(()),
+            Value::Bool(b) => write!(f, "{}", b),
+            Value::Integer(i) => write!(f, "{}", i),
+            Value::Float(x) => write!(f, "{}", x),
+            Value::Text(s) => f.write_str(s),
+        }
+    }
+}
+
+impl Serialize for Value {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Value::Null => serializer.serialize_none(),
+            Value::Bool(b) => serializer.serialize_bool(*b),
+            Value::Integer(i) => serializer.serialize_i64(*i),
+            Value::Float(x) => serializer.serialize_f64(*x),
+            Value::Text(s) => serializer.serialize_str(s),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ColumnType {
+    Text,
+    Integer,
+    Float,
+    Bool,
+}
+
+impl ColumnType {
+    pub fn is_numeric(&self) -> bool {
+        matches!(self, ColumnType::Integer | ColumnType::Float)
+    }
+
+    /// The type holding all values; empty columns are text
+    fn of<'v>(values: impl Iterator<Item = &'v Value>) -> Self {
+        let mut column_type = None;
+        for value in values {
+            column_type = match (column_type, value) {
+                (_, Value::Null) => column_type,
+                (None | Some(ColumnType::Integer), Value::Integer(_)) => Some(ColumnType::Integer),
+                (None | Some(ColumnType::Integer | ColumnType::Float), Value::Float(_))
+                | (Some(ColumnType::Float), Value::Integer(_)) => Some(ColumnType::Float),
+                (None | Some(ColumnType::Bool), Value::Bool(_)) => Some(ColumnType::Bool),
+                _ => return ColumnType::Text,
+            };
+        }
+        column_type.unwrap_or(ColumnType::Text)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Column {
+    pub name: String,
+    pub column_type: ColumnType,
+}
+
+/// What was analysed
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Metadata {
+    /// The command producing the result
+    pub analysis: String,
+    pub repositories: Vec<String>,
+    /// Limits of the analysed history as requested (`--after`, `--before`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub after: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub before: Option<String>,
+    /// Number of analysed commits and the dates of the first and last one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub commits: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub first_commit: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_commit: Option<String>,
+    /// Time of the analysis, RFC 3339
+    pub generated: String,
+}
+
+fn format_date(date: OffsetDateTime) -> String {
+    date.format(format_description!("[year]-[month]-[day]"))
+        .unwrap_or_default()
+}
+
+impl Metadata {
+    pub fn new(analysis: &str, common_args: &CommonArgs) -> Self {
+        Metadata {
+            analysis: analysis.to_owned(),
+            repositories: common_args
+                .repositories
+                .iter()
+                .map(|r| r.name.clone())
+                .collect(),
+            generated: OffsetDateTime::now_utc()
+                .format(&time::format_description::well_known::Rfc3339)
+                .unwrap_or_default(),
+            ..Default::default()
+        }
+    }
+
+    /// Metadata of an analysis of the change log
+    pub fn of_history(
+        analysis: &str,
+        common_args: &CommonArgs,
+        git_args: &GitArgs,
+        log: &[CommitChanges],
+    ) -> Self {
+        let git_date = |time: &git2::Time| {
+            OffsetDateTime::from_unix_timestamp(time.seconds())
+                .map(format_date)
+                .unwrap_or_default()
+        };
+        let dates = || log.iter().map(|c| c.date());
+        Metadata {
+            after: git_args.after.as_ref().map(git_date),
+            before: git_args.before.as_ref().map(git_date),
+            commits: Some(log.len()),
+            first_commit: dates().min().map(format_date),
+            last_commit: dates().max().map(format_date),
+            ..Self::new(analysis, common_args)
+        }
+    }
+
+    /// One line summary, e.g. for the heading of Markdown output
+    pub fn describe(&self) -> String {
+        let mut text = format!("{} of {}", self.analysis, self.repositories.join(", "));
+        if let Some(commits) = self.commits {
+            text.push_str(&format!(", {} commits", commits));
+        }
+        if let (Some(first), Some(last)) = (&self.first_commit, &self.last_commit) {
+            text.push_str(&format!(" from {} to {}", first, last));
+        }
+        text
+    }
+}
+
+/// The result of an analysis
+#[derive(Debug, Clone)]
+pub struct AnalysisResult {
+    pub title: String,
+    pub metadata: Metadata,
+    pub columns: Vec<Column>,
+    pub rows: Vec<Vec<Value>>,
+    /// How D3html output renders the result
+    chart: Option<Chart<'static>>,
+    /// Column whose values are coloured in table output
+    highlight: Option<&'static str>,
+    /// The change log the result is computed from, stored with the result
+    /// in SQLite output
+    history: Vec<CommitChanges>,
+}
+
+impl AnalysisResult {
+    /// Result with a row per record; records must serialize as structs
+    ///
+    /// The columns are those of the default record, so even an empty result
+    /// has them, plus fields of the records the default one skips.
+    pub fn from_records<T: Serialize + Default>(
+        title: &str,
+        metadata: Metadata,
+        records: &[T],
+    ) -> Result<Self, Box<dyn Error>> {
+        let default = T::default().serialize(RowSerializer)?;
+        let mut names: Vec<String> = Vec::new();
+        add_names(&mut names, &default);
+        let mut fields = Vec::with_capacity(records.len());
+        for record in records {
+            let record = record.serialize(RowSerializer)?;
+            add_names(&mut names, &record);
+            fields.push(record);
+        }
+        // fields skipped when serializing (`skip_serializing_if`) are null
+        let row = |fields: &[(String, Value)]| -> Vec<Value> {
+            names
+                .iter()
+                .map(|name| {
+                    fields
+                        .iter()
+                        .find(|(field, _)| field == name)
+                        .map(|(_, value)| value.clone())
+                        .unwrap_or(Value::Null)
+                })
+                .collect()
+        };
+        let rows: Vec<Vec<Value>> = fields.iter().map(|fields| row(fields)).collect();
+        // without rows the types are those of the default record
+        let typed_rows = match rows.is_empty() {
+            true => vec![row(&default)],
+            false => Vec::new(),
+        };
+        let columns = names
+            .iter()
+            .enumerate()
+            .map(|(i, name)| Column {
+                name: name.clone(),
+                column_type: ColumnType::of(rows.iter().chain(&typed_rows).map(|row| &row[i])),
+            })
+            .collect();
+        Ok(AnalysisResult {
+            title: title.to_owned(),
+            metadata,
+            columns,
+            rows,
+            chart: None,
+            highlight: None,
+            history: Vec::new(),
+        })
+    }
+
+    pub fn chart(mut self, chart: Chart<'static>) -> Self {
+        self.chart = Some(chart);
+        self
+    }
+
+    pub fn highlight(mut self, column: &'static str) -> Self {
+        self.highlight = Some(column);
+        self
+    }
+
+    pub fn history(mut self, log: Vec<CommitChanges>) -> Self {
+        self.history = log;
+        self
+    }
+
+    pub fn column(&self, name: &str) -> Option<usize> {
+        self.columns.iter().position(|c| c.name == name)
+    }
+
+    pub fn column_names(&self) -> Vec<&str> {
+        self.columns.iter().map(|c| c.name.as_str()).collect()
+    }
+
+    /// Value of a row by column name
+    pub fn get<'r>(&self, row: &'r [Value], column: &str) -> Option<&'r Value> {
+        self.column(column).and_then(|c| row.get(c))
+    }
+
+    /// Filter, sort and cut the rows; unknown columns are an error
+    pub fn rank(&mut self, ranking: &Ranking) -> Result<(), String> {
+        ranking.apply(self)
+    }
+
+    /// Rank by the command line options and write the requested format
+    pub fn output_ranked(mut self, common_args: &CommonArgs) -> Result<(), Box<dyn Error>> {
+        self.rank(&common_args.ranking)?;
+        self.output(common_args)
+    }
+
+    /// Like `rank`, but ignore sort columns and thresholds the result does
+    /// not have
+    pub fn rank_where_present(&mut self, ranking: &Ranking) -> Result<(), String> {
+        ranking.apply_where_present(self)
+    }
+}
+
+/// Add the field names missing so far, after the field preceding them
+fn add_names(names: &mut Vec<String>, fields: &[(String, Value)]) {
+    let mut position = 0;
+    for (name, _) in fields {
+        position = match names.iter().position(|n| n == name) {
+            Some(i) => i + 1,
+            None => {
+                names.insert(position, name.clone());
+                position + 1
+            }
+        };
+    }
+}
+
+/// Serializes as the records: a sequence of objects
+impl Serialize for AnalysisResult {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(Some(self.rows.len()))?;
+        for row in &self.rows {
+            seq.serialize_element(&Record {
+                columns: &self.columns,
+                row,
+            })?;
+        }
+        seq.end()
+    }
+}
+
+struct Record<'a> {
+    columns: &'a [Column],
+    row: &'a [Value],
+}
+
+impl Serialize for Record<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeMap;
+        let mut map = serializer.serialize_map(Some(self.columns.len()))?;
+        for (column, value) in self.columns.iter().zip(self.row) {
+            map.serialize_entry(&column.name, value)?;
+        }
+        map.end()
+    }
+}
+
+impl OutputFormatter for AnalysisResult {
+    fn csv_output(&self, writer: &mut dyn Write) -> Result<(), Box<dyn Error>> {
+        let mut wtr = csv::Writer::from_writer(writer);
+        wtr.write_record(self.column_names())?;
+        for row in &self.rows {
+            wtr.write_record(row.iter().map(|v| v.to_string()))?;
+        }
+        wtr.flush()?;
+        Ok(())
+    }
+
+    fn json_output(&self, writer: &mut dyn Write) -> Result<(), Box<dyn Error>> {
+        let mut wtr = serde_json::Serializer::pretty(&mut *writer);
+        self.serialize(&mut wtr)?;
+        writeln!(writer)?;
+        Ok(())
+    }
+
+    fn markdown_output(&self, writer: &mut dyn Write) -> Result<(), Box<dyn Error>> {
+        markdown::write_table(writer, self)
+    }
+
+    fn table_output(
+        &self,
+        writer: &mut dyn Write,
+        options: &TableOptions,
+    ) -> Result<(), Box<dyn Error>> {
+        match self.highlight {
+            Some(column) => table::write_table(writer, self, &options.highlight(column)),
+            None => table::write_table(writer, self, options),
+        }
+    }
+
+    fn parquet_output(&self, writer: &mut dyn Write) -> Result<(), Box<dyn Error>> {
+        columnar::write_parquet(writer, self)
+    }
+
+    fn sqlite_output(&self, database: &Path) -> Result<(), Box<dyn Error>> {
+        sqlite::write_database(database, &[self], &self.history)
+    }
+
+    fn d3_html_output(
+        &self,
+        writer: &mut dyn Write,
+        templates: &ChartTemplates,
+    ) -> Result<(), Box<dyn Error>> {
+        let chart = self
+            .chart
+            .as_ref()
+            .ok_or_else(|| format!("{} has no graphical output", self.title))?;
+        templates.render(writer, chart, self)
+    }
+}
+
+/// Collects the fields of a record struct
+struct RowSerializer;
+
+type Fields = Vec<(String, Value)>;
+
+fn not_a_struct<T>() -> Result<T, serde_json::Error> {
+    Err(ser::Error::custom("analysis records must be structs"))
+}
+
+impl Serializer for RowSerializer {
+    type Ok = Fields;
+    type Error = serde_json::Error;
+    type SerializeSeq = Impossible<Fields, serde_json::Error>;
+    type SerializeTuple = Impossible<Fields, serde_json::Error>;
+    type SerializeTupleStruct = Impossible<Fields, serde_json::Error>;
+    type SerializeTupleVariant = Impossible<Fields, serde_json::Error>;
+    type SerializeMap = Impossible<Fields, serde_json::Error>;
+    type SerializeStruct = FieldCollector;
+    type SerializeStructVariant = Impossible<Fields, serde_json::Error>;
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeStruct, Self::Error> {
+        Ok(FieldCollector(Vec::with_capacity(len)))
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<Fields, Self::Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_bool(self, _: bool) -> Result<Fields, Self::Error> {
+        not_a_struct()
+    }
+    fn serialize_i8(self, _: i8) -> Result<Fields, Self::Error> {
+        not_a_struct()
+    }
+    fn serialize_i16(self, _: i16) -> Result<Fields, Self::Error> {
+        not_a_struct()
+    }
+    fn serialize_i32(self, _: i32) -> Result<Fields, Self::Error> {
+        not_a_struct()
+    }
+    fn serialize_i64(self, _: i64) -> Result<Fields, Self::Error> {
+        not_a_struct()
+    }
+    fn serialize_u8(self, _: u8) -> Result<Fields, Self::Error> {
+        not_a_struct()
+    }
+    fn serialize_u16(self, _: u16) -> Result<Fields, Self::Error> {
+        not_a_struct()
+    }
+    fn serialize_u32(self, _: u32) -> Result<Fields, Self::Error> {
+        not_a_struct()
+    }
+    fn serialize_u64(self, _: u64) -> Result<Fields, Self::Error> {
+        not_a_struct()
+    }
+    fn serialize_f32(self, _: f32) -> Result<Fields, Self::Error> {
+        not_a_struct()
+    }
+    fn serialize_f64(self, _: f64) -> Result<Fields, Self::Error> {
+        not_a_struct()
+    }
+    fn serialize_char(self, _: char) -> Result<Fields, Self::Error> {
+        not_a_struct()
+    }
+    fn serialize_str(self, _: &str) -> Result<Fields, Self::Error> {
+        not_a_struct()
+    }
+    fn serialize_bytes(self, _: &[u8]) -> Result<Fields, Self::Error> {
+        not_a_struct()
+    }
+    fn serialize_none(self) -> Result<Fields, Self::Error> {
+        not_a_struct()
+    }
+    fn serialize_some<T: Serialize + ?Sized>(self, _: &T) -> Result<Fields, Self::Error> {
+        not_a_struct()
+    }
+    fn serialize_unit(self) -> Result<Fields, Self::Error> {
+        not_a_struct()
+    }
+    fn serialize_unit_struct(self, _: &'static str) -> Result<Fields, Self::Error> {
+        not_a_struct()
+    }
+    fn serialize_unit_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+    ) -> Result<Fields, Self::Error> {
+        not_a_struct()
+    }
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+        _: &T,
+    ) -> Result<Fields, Self::Error> {
+        not_a_struct()
+    }
+    fn serialize_seq(self, _: Option<usize>) -> Result<Self::SerializeSeq, Self::Error> {
+        not_a_struct()
+    }
+    fn serialize_tuple(self, _: usize) -> Result<Self::SerializeTuple, Self::Error> {
+        not_a_struct()
+    }
+    fn serialize_tuple_struct(
+        self,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeTupleStruct, Self::Error> {
+        not_a_struct()
+    }
+    fn serialize_tuple_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeTupleVariant, Self::Error> {
+        not_a_struct()
+    }
+    fn serialize_map(self, _: Option<usize>) -> Result<Self::SerializeMap, Self::Error> {
+        not_a_struct()
+    }
+    fn serialize_struct_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeStructVariant, Self::Error> {
+        not_a_struct()
+    }
+}
+
+struct FieldCollector(Fields);
+
+impl SerializeStruct for FieldCollector {
+    type Ok = Fields;
+    type Error = serde_json::Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Self::Error> {
+        let value = Value::from_json(serde_json::to_value(value)?);
+        self.0.push((key.to_owned(), value));
+        Ok(())
+    }
+
+    fn end(self) -> Result<Fields, Self::Error> {
+        Ok(self.0)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[derive(Default, Serialize)]
+    struct Record {
+        entity: &'static str,
+        #[serde(rename = "n-revs")]
+        revisions: u64,
+        ownership: f64,
+    }
+
+    #[test]
+    fn verify_typed_columns_of_records() {
+        let records = [
+            Record {
+                entity: "a.rs",
+                revisions: 3,
+                ownership: 1.0,
+            },
+            Record {
+                entity: "b.rs",
+                revisions: 1,
+                ownership: 0.5,
+            },
+        ];
+        let result = AnalysisResult::from_records("Test", Metadata::default(), &records).unwrap();
+        assert_eq!(result.column_names(), vec!["entity", "n-revs", "ownership"]);
+        assert_eq!(
+            result
+                .columns
+                .iter()
+                .map(|c| c.column_type)
+                .collect::<Vec<_>>(),
+            vec![ColumnType::Text, ColumnType::Integer, ColumnType::Float]
+        );
+        assert_eq!(
+            serde_json::to_string(&result).unwrap(),
+            r#"[{"entity":"a.rs","n-revs":3,"ownership":1.0},{"entity":"b.rs","n-revs":1,"ownership":0.5}]"#
+        );
+    }
+
+    #[test]
+    fn verify_columns_of_empty_result() {
+        let records: [Record; 0] = [];
+        let result = AnalysisResult::from_records("Test", Metadata::default(), &records).unwrap();
+        assert_eq!(result.column_names(), vec!["entity", "n-revs", "ownership"]);
+        assert_eq!(
+            result
+                .columns
+                .iter()
+                .map(|c| c.column_type)
+                .collect::<Vec<_>>(),
+            vec![ColumnType::Text, ColumnType::Integer, ColumnType::Float]
+        );
+    }
+
+    #[test]
+    fn verify_csv_and_json_of_empty_result() {
+        let records: [Record; 0] = [];
+        let result = AnalysisResult::from_records("Test", Metadata::default(), &records).unwrap();
+        let mut csv = Vec::new();
+        result.csv_output(&mut csv).unwrap();
+        assert_eq!(String::from_utf8(csv).unwrap(), "entity,n-revs,ownership\n");
+        let mut json = Vec::new();
+        result.json_output(&mut json).unwrap();
+        assert_eq!(String::from_utf8(json).unwrap(), "[]\n");
+    }
+}
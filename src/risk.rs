@@ -0,0 +1,219 @@
+//! Composite risk score of files
+//!
+//! No single metric tells where trouble is: a hotspot may be simple code and
+//! a complex file may never be touched. The risk score combines
+//!
+//! - change frequency: the number of revisions
+//! - size: the lines of code
+//! - complexity: the indentation complexity, i.e. the sum of the logical
+//!   indentation of all lines, a language neutral proxy of nesting
+//! - complexity trend: the growth of the indentation complexity since the
+//!   oldest analysed revision of the file
+//! - author fragmentation: `1 - Σ share²` of the authors' revisions, 0 for a
+//!   single author (Code Maat's `fragmentation`)
+//! - code age: recently changed code is riskier than code that settled
+//! - coupling: the strongest temporal coupling degree to another file
+//!
+//! Every factor is normalized to 0 to 1 relative to the maximum of all
+//! analysed files; the age is inverted and a shrinking complexity counts as
+//! 0. Fragmentation and coupling degree are ratios already and taken as is.
+//! The score is the weighted mean of the factors in percent. The weights
+//! are configured in the `[risk]` table of the configuration file.
+
+use serde::{Deserialize, Serialize};
+
+use crate::metrics::Contribution;
+
+/// Weights of the risk factors; a weight of 0 ignores the factor
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct RiskWeights {
+    pub revisions: f64,
+    pub size: f64,
+    pub complexity: f64,
+    pub complexity_trend: f64,
+    pub fragmentation: f64,
+    pub age: f64,
+    pub coupling: f64,
+}
+
+impl Default for RiskWeights {
+    fn default() -> Self {
+        RiskWeights {
+            revisions: 2.0,
+            size: 1.0,
+            complexity: 2.0,
+            complexity_trend: 1.0,
+            fragmentation: 1.0,
+            age: 1.0,
+            coupling: 1.0,
+        }
+    }
+}
+
+impl RiskWeights {
+    /// No weight is negative and at least one is positive
+    pub fn validate(&self) -> Result<(), String> {
+        let weights = [
+            ("revisions", self.revisions),
+            ("size", self.size),
+            ("complexity", self.complexity),
+            ("complexity-trend", self.complexity_trend),
+            ("fragmentation", self.fragmentation),
+            ("age", self.age),
+            ("coupling", self.coupling),
+        ];
+        if let Some((name, weight)) = weights.iter().find(|(_, weight)| !weight.is_finite()) {
+            return Err(format!(
+                "Risk weight {} = {} is not a finite number",
+                name, weight
+            ));
+        }
+        if let Some((name, weight)) = weights.iter().find(|(_, weight)| *weight < 0.0) {
+            return Err(format!("Risk weight {} = {} is negative", name, weight));
+        }
+        if weights.iter().map(|(_, weight)| weight).sum::<f64>() <= 0.0 {
+            return Err("All risk weights are 0".to_owned());
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Risk {
+    pub entity: String,
+    /// Weighted mean of the normalized factors, 0 to 100
+    pub score: f64,
+    pub revisions: u64,
+    pub code: usize,
+    /// Indentation complexity of the file at HEAD
+    pub complexity: u64,
+    /// Change of the indentation complexity since the oldest analysed revision
+    #[serde(rename = "complexity-trend")]
+    pub complexity_trend: i64,
+    pub fragmentation: f64,
+    #[serde(rename = "age-months")]
+    pub age_months: u32,
+    /// Strongest coupling degree to another file in percent
+    pub coupling: u64,
+}
+
+/// Sum of the logical indentation of all non-blank lines; a tab or four
+/// spaces are one level
+pub fn indentation_complexity(source: &str) -> u64 {
+    source
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            let spaces: u64 = line
+                .chars()
+                .take_while(|c| c.is_whitespace())
+                .map(|c| if c == '\t' { 4 } else { 1 })
+                .sum();
+            spaces / 4
+        })
+        .sum()
+}
+
+/// How scattered the revisions of an entity are among its authors, 0 to 1
+pub fn fragmentation(authors: &[Contribution]) -> f64 {
+    let total: u64 = authors.iter().map(|a| a.revisions).sum();
+    if total == 0 {
+        return 0.0;
+    }
+    let concentration: f64 = authors
+        .iter()
+        .map(|a| (a.revisions as f64 / total as f64).powi(2))
+        .sum();
+    ((1.0 - concentration) * 100.0).round() / 100.0
+}
+
+/// Score the risks by the weights, highest risk first
+pub fn score(risks: &mut [Risk], weights: &RiskWeights) {
+    let max = |factor: fn(&Risk) -> f64| risks.iter().map(factor).fold(0.0, f64::max);
+    let max_revisions = max(|r| r.revisions as f64);
+    let max_code = max(|r| r.code as f64);
+    let max_complexity = max(|r| r.complexity as f64);
+    let max_trend = max(|r| r.complexity_trend as f64);
+    let max_age = max(|r| r.age_months as f64);
+    let ratio = |value: f64, max: f64| if max > 0.0 { value.max(0.0) / max } else { 0.0 };
+    let total = weights.revisions
+        + weights.size
+        + weights.complexity
+        + weights.complexity_trend
+        + weights.fragmentation
+        + weights.age
+        + weights.coupling;
+
+    for risk in risks.iter_mut() {
+        let weighted = weights.revisions * ratio(risk.revisions as f64, max_revisions)
+            + weights.size * ratio(risk.code as f64, max_code)
+            + weights.complexity * ratio(risk.complexity as f64, max_complexity)
+            + weights.complexity_trend * ratio(risk.complexity_trend as f64, max_trend)
+            + weights.fragmentation * risk.fragmentation
+            + weights.age * ratio(max_age - risk.age_months as f64, max_age)
+            + weights.coupling * risk.coupling as f64 / 100.0;
+        risk.score = match total > 0.0 {
+            true => (weighted / total * 1000.0).round() / 10.0,
+            false => 0.0,
+        };
+    }
+    risks.sort_by(|a, b| {
+        b.score
+            .total_cmp(&a.score)
+            .then(b.revisions.cmp(&a.revisions))
+    });
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn risk(entity: &str, revisions: u64, age_months: u32) -> Risk {
+        Risk {
+            entity: entity.to_owned(),
+            score: 0.0,
+            revisions,
+            code: 100,
+            complexity: 10,
+            complexity_trend: -5,
+            fragmentation: 0.5,
+            age_months,
+            coupling: 0,
+        }
+    }
+
+    #[test]
+    fn verify_risk_score() {
+        assert_eq!(
+            indentation_complexity("fn f() {\n\tif x {\n        y();\n\n  }\n}\n"),
+            3
+        );
+        let contribution = |author: &str| Contribution {
+            author: author.to_owned(),
+            revisions: 2,
+            added: 0,
+            deleted: 0,
+        };
+        assert_eq!(
+            fragmentation(&[contribution("alice"), contribution("bob")]),
+            0.5
+        );
+
+        let mut risks = vec![risk("old.rs", 1, 12), risk("hot.rs", 4, 0)];
+        let weights = RiskWeights {
+            revisions: 1.0,
+            size: 1.0,
+            complexity: 0.0,
+            complexity_trend: 1.0,
+            fragmentation: 1.0,
+            age: 1.0,
+            coupling: 0.0,
+        };
+        score(&mut risks, &weights);
+        // (1 + 1 + 0 + 0.5 + 1) / 5 and (0.25 + 1 + 0 + 0.5 + 0) / 5
+        assert_eq!(risks[0].entity, "hot.rs");
+        assert_eq!(risks[0].score, 70.0);
+        assert_eq!(risks[1].score, 35.0);
+    }
+}
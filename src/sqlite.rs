@@ -0,0 +1,298 @@
+//! SQLite database output
+//!
+//! Analysis results and the change records they are computed from are
+//! written into one database, so ad-hoc questions can be answered with SQL.
+//! Writing into an existing database adds to it: an analysis replaces its
+//! previous table, commits already present are kept and their changes are
+//! replaced, e.g. when the paths are prefixed differently now.
+//!
+//! ## Schema
+//!
+//! - `commits`: one row per analysed commit
+//!   - `id` TEXT primary key, the commit hash
+//!   - `time` INTEGER, commit time in seconds since epoch
+//!   - `date` TEXT, commit time RFC 3339 in the committer's timezone
+//!   - `author`, `email`, `message` TEXT
+//! - `changes`: one row per file changed by a commit, unique by commit and
+//!   path
+//!   - `commit_id` TEXT references `commits(id)`
+//!   - `path` TEXT, as the analyses name it (prefixed by the repository if
+//!     several are analysed)
+//!   - `added`, `deleted` INTEGER lines
+//!   - `submodule` INTEGER 1 if the change is a submodule pointer bump
+//! - `analyses`: one row per analysis result in the database
+//!   - `name` TEXT primary key, also the name of the result table
+//!   - `title` TEXT
+//!   - `metadata` TEXT, JSON: repositories, analysed period, time of the
+//!     analysis
+//! - one table per analysis, named like the analysis, e.g. `hotspot`,
+//!   `coupling`, with the columns of the result; `-` in names becomes `_`,
+//!   e.g. `n_revs`
+//!
+//! ## Example
+//!
+//! Hotspots touched by authors of the example.com domain in the 3rd quarter:
+//!
+//! ```sql
+//! SELECT h.entity, h.revisions, count(DISTINCT c.id) AS q3_commits
+//! FROM hotspot h
+//! JOIN changes ch ON ch.path = h.entity
+//! JOIN commits c ON c.id = ch.commit_id
+//! WHERE c.email LIKE '%@example.com'
+//!   AND c.date BETWEEN '2024-07-01' AND '2024-10-01'
+//! GROUP BY h.entity ORDER BY q3_commits DESC;
+//! ```
+
+use rusqlite::types::{Null, ToSqlOutput};
+use rusqlite::{params, params_from_iter, Connection, ToSql, Transaction};
+use std::collections::HashSet;
+use std::error::Error;
+use std::path::Path;
+use time::format_description::well_known::Rfc3339;
+
+use crate::changes::CommitChanges;
+use crate::result::{AnalysisResult, ColumnType, Value};
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS commits (
+    id TEXT PRIMARY KEY,
+    time INTEGER NOT NULL,
+    date TEXT NOT NULL,
+    author TEXT NOT NULL,
+    email TEXT NOT NULL,
+    message TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS changes (
+    commit_id TEXT NOT NULL REFERENCES commits(id),
+    path TEXT NOT NULL,
+    added INTEGER NOT NULL,
+    deleted INTEGER NOT NULL,
+    submodule INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS changes_by_path ON changes(path);
+CREATE INDEX IF NOT EXISTS changes_by_commit ON changes(commit_id);
+CREATE UNIQUE INDEX IF NOT EXISTS changes_by_commit_and_path ON changes(commit_id, path);
+CREATE TABLE IF NOT EXISTS analyses (
+    name TEXT PRIMARY KEY,
+    title TEXT NOT NULL,
+    metadata TEXT NOT NULL
+);
+";
+
+impl ToSql for Value {
+    fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+        Ok(match self {
+            Value::Null => ToSqlOutput::from(Null),
+            Value::Bool(b) => ToSqlOutput::from(*b),
+            Value::Integer(i) => ToSqlOutput::from(*i),
+            Value::Float(x) => ToSqlOutput::from(*x),
+            Value::Text(s) => ToSqlOutput::from(s.as_str()),
+        })
+    }
+}
+
+fn sql_type(column_type: ColumnType) -> &'static str {
+    match column_type {
+        ColumnType::Text => "TEXT",
+        ColumnType::Integer | ColumnType::Bool => "INTEGER",
+        ColumnType::Float => "REAL",
+    }
+}
+
+/// Name usable in SQL without quoting, e.g. `n_revs` for `n-revs`
+fn identifier(name: &str) -> String {
+    name.chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect::<String>()
+        .to_lowercase()
+}
+
+/// Write the results and the change log into the database, creating the
+/// database if it does not exist
+pub fn write_database(
+    path: &Path,
+    results: &[&AnalysisResult],
+    log: &[CommitChanges],
+) -> Result<(), Box<dyn Error>> {
+    let mut connection = Connection::open(path)?;
+    let transaction = connection.transaction()?;
+    transaction.execute_batch(SCHEMA)?;
+    write_log(&transaction, log)?;
+    for result in results {
+        write_result(&transaction, result)?;
+    }
+    transaction.commit()?;
+    Ok(())
+}
+
+/// Commit records never change, commits already in the database are kept
+///
+/// The changes of a commit depend on how it was collected (path prefixes,
+/// submodule recursion), so the changes of an earlier write are replaced.
+/// Within one log the changes of a commit are combined, e.g. of forks
+/// sharing the commit.
+fn write_log(transaction: &Transaction, log: &[CommitChanges]) -> Result<(), Box<dyn Error>> {
+    let mut insert_commit = transaction.prepare(
+        "INSERT OR IGNORE INTO commits (id, time, date, author, email, message)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+    )?;
+    let mut delete_changes = transaction.prepare("DELETE FROM changes WHERE commit_id = ?1")?;
+    let mut insert_change = transaction.prepare(
+        "INSERT INTO changes (commit_id, path, added, deleted, submodule)
+         VALUES (?1, ?2, ?3, ?4, ?5)
+         ON CONFLICT (commit_id, path) DO UPDATE SET
+         added = excluded.added, deleted = excluded.deleted, submodule = excluded.submodule",
+    )?;
+    let mut written = HashSet::new();
+    for commit in log {
+        insert_commit.execute(params![
+            commit.id,
+            commit.time,
+            commit.date().format(&Rfc3339)?,
+            commit.author,
+            commit.email,
+            commit.message.trim_end(),
+        ])?;
+        if written.insert(commit.id.as_str()) {
+            delete_changes.execute(params![commit.id])?;
+        }
+        for change in &commit.changes {
+            insert_change.execute(params![
+                commit.id,
+                change.path,
+                change.added,
+                change.deleted,
+                change.submodule,
+            ])?;
+        }
+    }
+    Ok(())
+}
+
+fn write_result(transaction: &Transaction, result: &AnalysisResult) -> Result<(), Box<dyn Error>> {
+    let name = match result.metadata.analysis.is_empty() {
+        true => identifier(&result.title),
+        false => identifier(&result.metadata.analysis),
+    };
+    let columns: Vec<String> = result
+        .columns
+        .iter()
+        .map(|c| format!("\"{}\" {}", identifier(&c.name), sql_type(c.column_type)))
+        .collect();
+    transaction.execute(
+        "INSERT OR REPLACE INTO analyses (name, title, metadata) VALUES (?1, ?2, ?3)",
+        params![name, result.title, serde_json::to_string(&result.metadata)?],
+    )?;
+    transaction.execute_batch(&format!("DROP TABLE IF EXISTS \"{name}\";"))?;
+    transaction.execute_batch(&format!(
+        "CREATE TABLE \"{name}\" ({});",
+        columns.join(", ")
+    ))?;
+
+    let placeholders = vec!["?"; result.columns.len()].join(", ");
+    let mut insert =
+        transaction.prepare(&format!("INSERT INTO \"{name}\" VALUES ({placeholders})"))?;
+    for row in &result.rows {
+        insert.execute(params_from_iter(row))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::changes::FileChange;
+    use crate::result::Metadata;
+    use serde::Serialize;
+
+    #[derive(Default, Serialize)]
+    struct Record {
+        entity: &'static str,
+        #[serde(rename = "n-revs")]
+        revisions: u64,
+    }
+
+    #[test]
+    fn verify_result_and_log_tables() {
+        let log = vec![CommitChanges {
+            id: "c1".to_owned(),
+            time: 1_700_000_000,
+            offset: 60,
+            author: "ann".to_owned(),
+            email: "ann@example.com".to_owned(),
+            message: "Fix\n".to_owned(),
+            changes: vec![FileChange {
+                path: "a.rs".to_owned(),
+                added: 3,
+                deleted: 1,
+                submodule: false,
+                binary: false,
+            }],
+        }];
+        let metadata = Metadata {
+            analysis: "revisions".to_owned(),
+            ..Default::default()
+        };
+        let records = [Record {
+            entity: "a.rs",
+            revisions: 1,
+        }];
+        let result = AnalysisResult::from_records("Revisions", metadata, &records).unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("ocs.sqlite");
+        // a second write replaces the analysis and keeps the commits
+        write_database(&path, &[&result], &log).unwrap();
+        write_database(&path, &[&result], &log).unwrap();
+
+        let connection = Connection::open(&path).unwrap();
+        let (author, revisions): (String, i64) = connection
+            .query_row(
+                "SELECT c.author, r.n_revs FROM revisions r
+                 JOIN changes ch ON ch.path = r.entity
+                 JOIN commits c ON c.id = ch.commit_id",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!((author.as_str(), revisions), ("ann", 1));
+        let changes: i64 = connection
+            .query_row("SELECT count(*) FROM changes", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(changes, 1);
+    }
+
+    #[test]
+    fn verify_changes_replaced_by_later_write() {
+        let log = |prefixes: &[&str]| -> Vec<CommitChanges> {
+            prefixes
+                .iter()
+                .map(|prefix| {
+                    CommitChanges::fixture("ann", 1_700_000_000).with_change(
+                        &format!("{}a.rs", prefix),
+                        3,
+                        1,
+                    )
+                })
+                .collect()
+        };
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("ocs.sqlite");
+        let paths = || -> Vec<String> {
+            let connection = Connection::open(&path).unwrap();
+            let mut query = connection
+                .prepare("SELECT path FROM changes ORDER BY path")
+                .unwrap();
+            let paths = query.query_map([], |row| row.get(0)).unwrap();
+            paths.collect::<Result<_, _>>().unwrap()
+        };
+
+        write_database(&path, &[], &log(&[""])).unwrap();
+        assert_eq!(paths(), vec!["a.rs"]);
+        // analysed as one of several repositories now
+        write_database(&path, &[], &log(&["api/"])).unwrap();
+        assert_eq!(paths(), vec!["api/a.rs"]);
+        // forks sharing the commit
+        write_database(&path, &[], &log(&["api/", "fork/"])).unwrap();
+        assert_eq!(paths(), vec!["api/a.rs", "fork/a.rs"]);
+    }
+}
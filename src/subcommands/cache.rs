@@ -0,0 +1,89 @@
+use crate::cache::{tip_state, ChangeCache, TipState};
+use crate::cli::CommonArgs;
+use crate::result::{AnalysisResult, Metadata};
+use clap::ArgMatches;
+use git2::Repository;
+use serde::Serialize;
+use std::error::Error;
+use std::fs;
+
+use log::info;
+
+pub const COMMAND: &str = "cache";
+
+#[macro_export]
+macro_rules! cache_command {
+    ($command_builder:expr) => {
+        $command_builder.subcommand(
+            Command::new(subcommands::cache::COMMAND)
+                .about("Inspect or clear the cache of analysed commits (stored in .git/ocs/)")
+                .subcommand_required(true)
+                .arg_required_else_help(true)
+                .subcommand(Command::new("status").about("Show what is cached"))
+                .subcommand(Command::new("clear").about("Remove all cached data")),
+        )
+    };
+}
+
+pub fn run(common_args: CommonArgs, sub_matches: &ArgMatches) -> Result<(), Box<dyn Error>> {
+    match sub_matches.subcommand() {
+        Some(("status", _)) => {
+            let mut statuses = Vec::new();
+            for repository in &common_args.repositories {
+                let repo = Repository::open(&repository.path)?;
+                statuses.push(status(&repository.name, &repo));
+            }
+            let metadata = Metadata::new(COMMAND, &common_args);
+            AnalysisResult::from_records("Cache status", metadata, &statuses)?
+                .output_ranked(&common_args)
+        }
+        Some(("clear", _)) => {
+            for repository in &common_args.repositories {
+                info!("Clear change record cache of {}", repository.name);
+                let repo = Repository::open(&repository.path)?;
+                ChangeCache::clear(&repo)?;
+            }
+            Ok(())
+        }
+        _ => unreachable!(),
+    }
+}
+
+#[derive(Default, Serialize)]
+struct CacheStatus {
+    repository: String,
+    location: String,
+    #[serde(rename = "size-in-bytes")]
+    size_in_bytes: u64,
+    #[serde(rename = "cached-commits")]
+    cached_commits: usize,
+    tip: Option<String>,
+    state: String,
+}
+
+fn status(name: &str, repo: &Repository) -> CacheStatus {
+    let cache = ChangeCache::load(repo);
+    let directory = ChangeCache::directory(repo);
+    let size: u64 = match fs::read_dir(&directory) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok()?.metadata().ok())
+            .map(|metadata| metadata.len())
+            .sum(),
+        Err(_) => 0,
+    };
+    let state = match tip_state(repo, cache.tip()) {
+        TipState::Empty => "empty".to_owned(),
+        TipState::UpToDate => "up-to-date".to_owned(),
+        TipState::Behind(n) => format!("{} new commits", n),
+        TipState::Rewritten => "history rewritten".to_owned(),
+    };
+
+    CacheStatus {
+        repository: name.to_owned(),
+        location: directory.display().to_string(),
+        size_in_bytes: size,
+        cached_commits: cache.len(),
+        tip: cache.tip().map(str::to_owned),
+        state,
+    }
+}
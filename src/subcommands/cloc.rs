@@ -1,9 +1,15 @@
+use crate::chart::{Chart, ChartType};
 use crate::cli::CommonArgs;
+use crate::result::{AnalysisResult, Metadata};
 use log::info;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::path::Path;
 
 pub const COMMAND: &str = "cloc";
 
-use tokei::{Config, Languages, Report};
+use tokei::{Config, LanguageType, Languages, Report};
 
 #[macro_export]
 macro_rules! cloc_command {
@@ -11,23 +17,34 @@ macro_rules! cloc_command {
         $command_builder.subcommand(
             Command::new(subcommands::cloc::COMMAND)
                 .about("Count lines of code, comments and empty lines")
-                .after_help("Output is in csv only; first line is column header")
                 .help_expected(true),
         )
     };
 }
 
-pub fn run(common_args: CommonArgs) {
+#[derive(Default, Serialize)]
+struct FileLines {
+    entity: String,
+    code: usize,
+    comments: usize,
+    blanks: usize,
+}
+
+pub fn run(common_args: CommonArgs) -> Result<(), Box<dyn Error>> {
     info!("Run cloc - count lines of code");
 
     let config = Config::default();
     let mut languages = Languages::new();
-    let paths = &[common_args.project_dir];
+    let paths: Vec<&str> = common_args
+        .repositories
+        .iter()
+        .map(|repository| repository.path.as_str())
+        .collect();
     let excluded = &["target", "build"];
 
-    languages.get_statistics(paths, excluded, &config);
+    languages.get_statistics(&paths, excluded, &config);
 
-    println!("file name;lines of code, lines of comments, lines of space");
+    let mut files = Vec::new();
     for (_name, language) in languages {
         let reports: Vec<&Report> = language.reports.iter().collect();
 
@@ -36,14 +53,68 @@ pub fn run(common_args: CommonArgs) {
 
         for reports in &[&a, &b] {
             for report in reports.iter() {
-                println!(
-                    "{},{},{},{}",
-                    report.name.display(),
-                    report.stats.code,
-                    report.stats.comments,
-                    report.stats.blanks
-                );
+                files.push(FileLines {
+                    entity: report.name.display().to_string(),
+                    code: report.stats.code,
+                    comments: report.stats.comments,
+                    blanks: report.stats.blanks,
+                });
+            }
+        }
+    }
+
+    AnalysisResult::from_records(
+        "Lines of code",
+        Metadata::new(COMMAND, &common_args),
+        &files,
+    )?
+    .chart(
+        Chart::new(ChartType::Treemap, "Lines of code")
+            .field("path", "entity")
+            .field("size", "code"),
+    )
+    .highlight("code")
+    .output_ranked(&common_args)
+}
+
+/// Lines of code per file of the checked out sources
+///
+/// Paths are relative to the repository like in the change records, and
+/// prefixed with the repository name if several repositories are analysed.
+pub fn lines_of_code(common_args: &CommonArgs) -> BTreeMap<String, usize> {
+    let config = Config::default();
+    let excluded = &["target", "build"];
+    let mut loc = BTreeMap::new();
+
+    for repository in &common_args.repositories {
+        let mut languages = Languages::new();
+        languages.get_statistics(&[repository.path.as_str()], excluded, &config);
+        let root = Path::new(&repository.path);
+        for (_name, language) in languages {
+            for report in language.reports {
+                let relative = report
+                    .name
+                    .strip_prefix(root)
+                    .unwrap_or(report.name.as_path());
+                let mut path = relative
+                    .components()
+                    .map(|c| c.as_os_str().to_string_lossy())
+                    .collect::<Vec<_>>()
+                    .join("/");
+                if common_args.is_multi_repository() {
+                    path = format!("{}/{}", repository.name, path);
+                }
+                loc.insert(path, report.stats.code);
             }
         }
     }
+    loc
+}
+
+/// Lines of code of a file's content, e.g. of a past revision; `None` if
+/// the language is not recognized by the path
+pub fn code_lines(path: &str, source: &str) -> Option<usize> {
+    let config = Config::default();
+    let language = LanguageType::from_path(Path::new(path), &config)?;
+    Some(language.parse_from_str(source, &config).code)
 }
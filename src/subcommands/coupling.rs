@@ -0,0 +1,224 @@
+use crate::changes::collect_project_changes;
+use crate::chart::{Chart, ChartType};
+use crate::cli::{CommonArgs, GitArgs};
+use crate::coupling::{changesets, coupling, ChangesetGrouping, CouplingOptions, TICKET_PATTERN};
+use crate::result::{AnalysisResult, Metadata};
+use clap::{builder::PossibleValue, value_parser, Arg, ArgMatches, Command, ValueEnum};
+use regex::Regex;
+use std::error::Error;
+
+#[allow(unused_imports)]
+use crate::git_common_args_extension;
+
+use log::info;
+
+pub const COMMAND: &str = "coupling";
+
+#[macro_export]
+macro_rules! coupling_command {
+    ($command_builder:expr) => {
+        $command_builder.subcommand(git_common_args_extension(
+            subcommands::coupling::coupling_args_extension(
+                Command::new(subcommands::coupling::COMMAND)
+                    .about("Temporal coupling of entities changing together"),
+            ),
+        ))
+    };
+}
+
+/// Options of the changeset extraction; shared by all commands grouping
+/// commits into changesets
+pub fn changeset_args_extension(builder: Command) -> Command {
+    builder
+        .arg(
+            Arg::new("group-by")
+                .long("group-by")
+                .short('g')
+                .default_value("commit")
+                .value_parser(value_parser!(Grouping))
+                .help("How commits are grouped into logical changesets"),
+        )
+        .arg(
+            Arg::new("temporal-period")
+                .long("temporal-period")
+                .default_value("1d")
+                .value_parser(parse_period)
+                .help(
+                    "Maximum time between consecutive commits of an author in the same changeset,
+                    e.g. 90m, 4h or 2d (plain numbers are days)",
+                ),
+        )
+        .arg(
+            Arg::new("ticket-pattern")
+                .long("ticket-pattern")
+                .default_value(TICKET_PATTERN)
+                .value_parser(value_parser!(regex::Regex))
+                .help("Regular expression matching ticket ids in commit messages"),
+        )
+        .arg(
+            Arg::new("max-changeset-size")
+                .long("max-changeset-size")
+                .default_value("30")
+                .value_parser(value_parser!(usize))
+                .help("Ignore changesets with more entities"),
+        )
+}
+
+/// Options of the coupling analysis; shared by all commands running it
+pub fn coupling_args_extension(builder: Command) -> Command {
+    changeset_args_extension(builder)
+        .arg(
+            Arg::new("min-revs")
+                .long("min-revs")
+                .default_value("5")
+                .value_parser(value_parser!(u64))
+                .help("Minimum number of changesets of each entity"),
+        )
+        .arg(
+            Arg::new("min-shared-revs")
+                .long("min-shared-revs")
+                .default_value("5")
+                .value_parser(value_parser!(u64))
+                .help("Minimum number of changesets both entities are part of"),
+        )
+        .arg(
+            Arg::new("min-coupling")
+                .long("min-coupling")
+                .default_value("30")
+                .value_parser(value_parser!(u64))
+                .help("Minimum coupling degree in percent"),
+        )
+        .arg(
+            Arg::new("max-coupling")
+                .long("max-coupling")
+                .default_value("100")
+                .value_parser(value_parser!(u64))
+                .help("Maximum coupling degree in percent"),
+        )
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum Grouping {
+    Commit,
+    TimeWindow,
+    Ticket,
+}
+
+impl ValueEnum for Grouping {
+    fn value_variants<'a>() -> &'a [Self] {
+        &[Grouping::Commit, Grouping::TimeWindow, Grouping::Ticket]
+    }
+
+    fn to_possible_value(&self) -> Option<PossibleValue> {
+        Some(match self {
+            Grouping::Commit => PossibleValue::new("commit").help("Every commit is a changeset"),
+            Grouping::TimeWindow => PossibleValue::new("time-window")
+                .help("Commits of an author within the temporal period form a changeset"),
+            Grouping::Ticket => PossibleValue::new("ticket")
+                .help("Commits referencing the same ticket id form a changeset"),
+        })
+    }
+}
+
+/// Parse a period like `90m`, `4h` or `2d` into seconds
+pub fn parse_period(arg: &str) -> Result<i64, String> {
+    let arg = arg.trim();
+    let (number, unit) = match arg.find(|c: char| !c.is_ascii_digit()) {
+        Some(idx) => arg.split_at(idx),
+        None => (arg, "d"),
+    };
+    let number: i64 = number
+        .parse()
+        .map_err(|_| format!("'{}' is not a period like 90m, 4h or 2d", arg))?;
+    let seconds = match unit {
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        _ => return Err(format!("Unknown unit '{}'; use m, h or d", unit)),
+    };
+    number
+        .checked_mul(seconds)
+        .ok_or_else(|| format!("Period '{}' is too long", arg))
+}
+
+pub struct CouplingArgs {
+    pub grouping: ChangesetGrouping,
+    pub options: CouplingOptions,
+}
+
+/// The changeset grouping of the `changeset_args_extension` options
+pub fn grouping_from_cli_args(matches: &ArgMatches) -> ChangesetGrouping {
+    match matches.get_one::<Grouping>("group-by") {
+        Some(Grouping::TimeWindow) => ChangesetGrouping::TimeWindow(
+            *matches
+                .get_one::<i64>("temporal-period")
+                .expect("Option with default is never None"),
+        ),
+        Some(Grouping::Ticket) => ChangesetGrouping::Ticket(
+            matches
+                .get_one::<Regex>("ticket-pattern")
+                .expect("Option with default is never None")
+                .clone(),
+        ),
+        _ => ChangesetGrouping::Commit,
+    }
+}
+
+impl CouplingArgs {
+    pub fn from_cli_args(matches: &ArgMatches) -> Self {
+        let grouping = grouping_from_cli_args(matches);
+        let get = |id: &str| -> u64 {
+            *matches
+                .get_one::<u64>(id)
+                .expect("Option with default is never None")
+        };
+        CouplingArgs {
+            grouping,
+            options: CouplingOptions {
+                min_revs: get("min-revs"),
+                min_shared_revs: get("min-shared-revs"),
+                min_coupling: get("min-coupling"),
+                max_coupling: get("max-coupling"),
+                max_changeset_size: *matches
+                    .get_one::<usize>("max-changeset-size")
+                    .expect("Option with default is never None"),
+            },
+        }
+    }
+}
+
+pub fn run(
+    common_args: CommonArgs,
+    git_args: GitArgs,
+    coupling_args: CouplingArgs,
+) -> Result<(), Box<dyn Error>> {
+    info!("Run temporal coupling analysis");
+    let log = collect_project_changes(&common_args, git_args.clone())?;
+    let metadata = Metadata::of_history(COMMAND, &common_args, &git_args, &log);
+
+    let changesets = changesets(&log, &coupling_args.grouping);
+    let coupling = coupling(&changesets, &coupling_args.options);
+    AnalysisResult::from_records("Temporal coupling", metadata, &coupling)?
+        .chart(
+            Chart::new(ChartType::Network, "Temporal coupling")
+                .field("source", "entity")
+                .field("target", "coupled")
+                .field("weight", "degree"),
+        )
+        .highlight("degree")
+        .history(log)
+        .output_ranked(&common_args)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn verify_parse_period() {
+        assert_eq!(parse_period("90m"), Ok(90 * 60));
+        assert_eq!(parse_period("2"), Ok(2 * 24 * 60 * 60));
+        assert!(parse_period("2w").is_err());
+        assert!(parse_period("999999999999999d").is_err());
+    }
+}
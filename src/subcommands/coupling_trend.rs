@@ -0,0 +1,175 @@
+use crate::changes::{collect_project_changes, CommitChanges};
+use crate::chart::{Chart, ChartType};
+use crate::cli::{CommonArgs, GitArgs};
+use crate::coupling::{changesets, pair_coupling, ChangesetGrouping, PairCoupling};
+use crate::result::{AnalysisResult, Metadata};
+use crate::trend::{windows, Window};
+use serde::Serialize;
+use std::error::Error;
+
+use super::hotspot_trend::TrendArgs;
+#[allow(unused_imports)]
+use crate::git_common_args_extension;
+
+use log::info;
+
+pub const COMMAND: &str = "coupling-trend";
+
+#[macro_export]
+macro_rules! coupling_trend_command {
+    ($command_builder:expr) => {
+        $command_builder.subcommand(git_common_args_extension(
+            subcommands::hotspot_trend::trend_args_extension(
+                subcommands::coupling::changeset_args_extension(
+                    Command::new(subcommands::coupling_trend::COMMAND)
+                        .about("Coupling degree of two entities over sliding time windows")
+                        .after_help(
+                            "An entity is a file or a component, i.e. a directory whose files
+                            are changed as one. Changesets are the ones of the coupling analysis",
+                        )
+                        .arg(
+                            Arg::new("ENTITY")
+                                .required(true)
+                                .help("File or directory as named by the other analyses"),
+                        )
+                        .arg(
+                            Arg::new("COUPLED")
+                                .required(true)
+                                .help("File or directory the coupling to is traced"),
+                        ),
+                ),
+            ),
+        ))
+    };
+}
+
+#[derive(Default, Serialize)]
+pub struct CouplingTrend {
+    /// First day of the last period of the window
+    pub period: String,
+    #[serde(rename = "entity-revs")]
+    pub entity_revs: u64,
+    #[serde(rename = "coupled-revs")]
+    pub coupled_revs: u64,
+    #[serde(rename = "shared-revs")]
+    pub shared_revs: u64,
+    /// None if neither entity changed in the window
+    pub degree: Option<u64>,
+}
+
+pub struct PairArgs {
+    pub entity: String,
+    pub coupled: String,
+    pub grouping: ChangesetGrouping,
+    pub max_changeset_size: usize,
+}
+
+pub fn run(
+    common_args: CommonArgs,
+    git_args: GitArgs,
+    trend_args: TrendArgs,
+    pair_args: PairArgs,
+) -> Result<(), Box<dyn Error>> {
+    info!(
+        "Run coupling trend analysis of {} and {}",
+        pair_args.entity, pair_args.coupled
+    );
+    let log = collect_project_changes(&common_args, git_args.clone())?;
+    let metadata = Metadata::of_history(COMMAND, &common_args, &git_args, &log);
+    let windows = windows(&log, trend_args.period, trend_args.span);
+    let trend = coupling_trend(&log, &windows, &pair_args);
+
+    AnalysisResult::from_records(
+        &format!(
+            "Coupling trend of {} and {}",
+            pair_args.entity, pair_args.coupled
+        ),
+        metadata,
+        &trend,
+    )?
+    .chart(
+        Chart::new(ChartType::TimeSeries, "Coupling trend")
+            .field("x", "period")
+            .field("y", "degree"),
+    )
+    .highlight("degree")
+    .history(log)
+    .output_ranked(&common_args)
+}
+
+/// Coupling of the pair in the changesets of every window
+pub fn coupling_trend(
+    log: &[CommitChanges],
+    windows: &[Window],
+    pair_args: &PairArgs,
+) -> Vec<CouplingTrend> {
+    windows
+        .iter()
+        .map(|window| {
+            let commits: Vec<CommitChanges> =
+                log.iter().filter(|c| window.contains(c)).cloned().collect();
+            let changesets = changesets(&commits, &pair_args.grouping);
+            let PairCoupling {
+                entity_revs,
+                coupled_revs,
+                shared_revs,
+                degree,
+            } = pair_coupling(
+                &changesets,
+                &pair_args.entity,
+                &pair_args.coupled,
+                pair_args.max_changeset_size,
+            );
+            CouplingTrend {
+                period: window.label(),
+                entity_revs,
+                coupled_revs,
+                shared_revs,
+                degree,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::trend::Period;
+    use time::macros::datetime;
+
+    #[test]
+    fn verify_coupling_trend_with_idle_window() {
+        let log = vec![
+            CommitChanges::fixture("", datetime!(2024-03-20 12:00 UTC).unix_timestamp())
+                .with_changes(&["a.rs"]),
+            CommitChanges::fixture("", datetime!(2024-03-05 12:00 UTC).unix_timestamp())
+                .with_changes(&["a.rs", "b.rs"]),
+            CommitChanges::fixture("", datetime!(2024-01-10 12:00 UTC).unix_timestamp())
+                .with_changes(&["a.rs", "b.rs"]),
+        ];
+        let pair_args = PairArgs {
+            entity: "a.rs".to_owned(),
+            coupled: "b.rs".to_owned(),
+            grouping: ChangesetGrouping::Commit,
+            max_changeset_size: 30,
+        };
+        let trend = coupling_trend(&log, &windows(&log, Period::Month, 1), &pair_args);
+        assert_eq!(
+            trend
+                .iter()
+                .map(|t| (
+                    t.period.as_str(),
+                    t.entity_revs,
+                    t.coupled_revs,
+                    t.shared_revs,
+                    t.degree
+                ))
+                .collect::<Vec<_>>(),
+            vec![
+                ("2024-01-01", 1, 1, 1, Some(100)),
+                ("2024-02-01", 0, 0, 0, None),
+                ("2024-03-01", 2, 1, 1, Some(67)),
+            ]
+        );
+    }
+}
@@ -0,0 +1,115 @@
+use crate::changes::collect_project_changes;
+use crate::chart::{Chart, ChartType};
+use crate::cli::{CommonArgs, GitArgs};
+use crate::defects::{defect_hotspots, read_ticket_keys, FixDetection};
+use crate::messages::MessageConfig;
+use crate::result::{AnalysisResult, Metadata};
+use regex::Regex;
+use std::error::Error;
+use std::path::PathBuf;
+
+use super::cloc::lines_of_code;
+#[allow(unused_imports)]
+use crate::git_common_args_extension;
+
+use log::info;
+
+pub const COMMAND: &str = "defects";
+
+#[macro_export]
+macro_rules! defects_command {
+    ($command_builder:expr) => {
+        $command_builder.subcommand(git_common_args_extension(
+            Command::new(subcommands::defects::COMMAND)
+                .about(
+                    "Defect hotspots: files by bug fix changes and defects per 1000 lines of code",
+                )
+                .after_help(
+                    "Bug fixes are the commits matching --fix-pattern, by default the fix class
+                    of the commit messages (see the messages command), or with --bug-tickets the
+                    commits referencing one of the tickets. The hotspot rank is the position of
+                    the file by revisions",
+                )
+                .arg(
+                    Arg::new("fix-pattern")
+                        .long("fix-pattern")
+                        .value_parser(value_parser!(regex::Regex))
+                        .conflicts_with("bug-tickets")
+                        .help("Regular expression matching the messages of bug fix commits"),
+                )
+                .arg(
+                    Arg::new("bug-tickets")
+                        .long("bug-tickets")
+                        .value_parser(value_parser!(PathBuf))
+                        .help(
+                            "CSV file of bug tickets with header; keys in the column key, id or
+                            issue key, else the first one",
+                        ),
+                )
+                .arg(
+                    Arg::new("ticket-pattern")
+                        .long("ticket-pattern")
+                        .default_value(coupling::TICKET_PATTERN)
+                        .value_parser(value_parser!(regex::Regex))
+                        .requires("bug-tickets")
+                        .help("Regular expression matching ticket ids in commit messages"),
+                ),
+        ))
+    };
+}
+
+/// Options telling bug fix commits apart
+pub struct DefectArgs {
+    pub fix_pattern: Option<Regex>,
+    pub bug_tickets: Option<PathBuf>,
+    pub ticket_pattern: Regex,
+}
+
+impl DefectArgs {
+    /// Bug fixes by ticket, by pattern or by the `fix` class of the
+    /// configured commit message classes, the default one if there is none
+    fn detection(self, messages: &MessageConfig) -> Result<FixDetection, Box<dyn Error>> {
+        if let Some(bug_tickets) = self.bug_tickets {
+            let keys = read_ticket_keys(&bug_tickets)?;
+            info!("Read {} bug tickets", keys.len());
+            return Ok(FixDetection::Tickets {
+                pattern: self.ticket_pattern,
+                keys,
+            });
+        }
+        let pattern = match self.fix_pattern {
+            Some(pattern) => pattern,
+            None => {
+                let fix = match messages.classes.get("fix") {
+                    Some(fix) => fix.clone(),
+                    None => MessageConfig::default().classes["fix"].clone(),
+                };
+                Regex::new(&fix)?
+            }
+        };
+        Ok(FixDetection::Pattern(pattern))
+    }
+}
+
+pub fn run(
+    common_args: CommonArgs,
+    git_args: GitArgs,
+    defect_args: DefectArgs,
+) -> Result<(), Box<dyn Error>> {
+    info!("Run defect hotspot analysis");
+    let detection = defect_args.detection(&common_args.config.messages)?;
+    let log = collect_project_changes(&common_args, git_args.clone())?;
+    let metadata = Metadata::of_history(COMMAND, &common_args, &git_args, &log);
+    let hotspots = defect_hotspots(&log, lines_of_code(&common_args), &detection);
+
+    AnalysisResult::from_records("Defect hotspots", metadata, &hotspots)?
+        .chart(
+            Chart::new(ChartType::BarChart, "Defect hotspots")
+                .field("label", "entity")
+                .field("value", "fixes")
+                .field("color", "density"),
+        )
+        .highlight("fixes")
+        .history(log)
+        .output_ranked(&common_args)
+}
@@ -0,0 +1,42 @@
+use crate::changes::collect_project_changes;
+use crate::chart::{Chart, ChartType};
+use crate::cli::{CommonArgs, GitArgs};
+use crate::metrics::developer_timeline;
+use crate::result::{AnalysisResult, Metadata};
+use std::error::Error;
+
+#[allow(unused_imports)]
+use crate::git_common_args_extension;
+
+use log::info;
+
+pub const COMMAND: &str = "developer-timeline";
+
+#[macro_export]
+macro_rules! developer_timeline_command {
+    ($command_builder:expr) => {
+        $command_builder.subcommand(git_common_args_extension(
+            Command::new(subcommands::developer_timeline::COMMAND)
+                .about("Activity of every author per week: commits, added and deleted lines")
+                .after_help("Weeks start on Monday in the time zone of the commit"),
+        ))
+    };
+}
+
+pub fn run(common_args: CommonArgs, git_args: GitArgs) -> Result<(), Box<dyn Error>> {
+    info!("Run developer timeline analysis");
+    let log = collect_project_changes(&common_args, git_args.clone())?;
+    let metadata = Metadata::of_history(COMMAND, &common_args, &git_args, &log);
+    let timeline = developer_timeline(&log);
+
+    AnalysisResult::from_records("Developer timeline", metadata, &timeline)?
+        .chart(
+            Chart::new(ChartType::Heatmap, "Developer timeline")
+                .field("x", "week")
+                .field("y", "author")
+                .field("value", "commits"),
+        )
+        .highlight("commits")
+        .history(log)
+        .output_ranked(&common_args)
+}
@@ -0,0 +1,48 @@
+use crate::changes::collect_project_changes;
+use crate::chart::{Chart, ChartType};
+use crate::cli::{CommonArgs, GitArgs};
+use crate::knowledge::component_log;
+use crate::metrics::entity_effort;
+use crate::result::{AnalysisResult, Metadata};
+use std::error::Error;
+
+#[allow(unused_imports)]
+use crate::git_common_args_extension;
+
+use log::info;
+
+pub const COMMAND: &str = "entity-effort";
+
+#[macro_export]
+macro_rules! entity_effort_command {
+    ($command_builder:expr) => {
+        $command_builder.subcommand(git_common_args_extension(
+            subcommands::knowledge_loss::component_args_extension(
+                Command::new(subcommands::entity_effort::COMMAND)
+                    .about("Effort distribution: revisions of every author per entity")
+                    .after_help(
+                        "With --depth the effort is spread across components; a commit
+                        changing several files of a component is one revision of it",
+                    ),
+            ),
+        ))
+    };
+}
+
+pub fn run(common_args: CommonArgs, git_args: GitArgs, depth: usize) -> Result<(), Box<dyn Error>> {
+    info!("Run entity effort analysis");
+    let log = collect_project_changes(&common_args, git_args.clone())?;
+    let metadata = Metadata::of_history(COMMAND, &common_args, &git_args, &log);
+    let effort = entity_effort(&component_log(&log, depth));
+
+    AnalysisResult::from_records("Entity effort", metadata, &effort)?
+        .chart(
+            Chart::new(ChartType::Heatmap, "Entity effort")
+                .field("x", "author")
+                .field("y", "entity")
+                .field("value", "author-revs"),
+        )
+        .highlight("author-revs")
+        .history(log)
+        .output_ranked(&common_args)
+}
@@ -0,0 +1,265 @@
+use crate::changes::{collect_project_changes, CommitChanges};
+use crate::cli::{CommonArgs, GitArgs};
+use crate::columnar;
+use crate::result::{AnalysisResult, Metadata};
+use crate::sqlite;
+use clap::{builder::PossibleValue, parser::ValueSource, ArgMatches, ValueEnum};
+use serde::Serialize;
+use std::error::Error;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use time::format_description::well_known::Rfc3339;
+use time::macros::format_description;
+
+#[allow(unused_imports)]
+use crate::git_common_args_extension;
+
+use log::info;
+
+pub const COMMAND: &str = "export-log";
+
+#[macro_export]
+macro_rules! export_log_command {
+    ($command_builder:expr) => {
+        $command_builder.subcommand(git_common_args_extension(
+            Command::new(subcommands::export_log::COMMAND)
+                .about("Export the change records of all analysed commits")
+                .after_help(
+                    "One record per file changed by a commit: \
+                     commit, date, author, message, path, added and deleted lines.\n\
+                     Of the global options, -C, --manifest, --output, --no-cache and \
+                     --recurse-submodules apply. The format is set by --log-format, not \
+                     --format, and all records are exported, so --sort-by, --desc, --top \
+                     and --min are rejected",
+                )
+                .arg(
+                    Arg::new("log-format")
+                        .long("log-format")
+                        .short('l')
+                        .default_value("csv")
+                        .value_parser(value_parser!(subcommands::export_log::LogFormat))
+                        .help("Set the format of the exported change log"),
+                ),
+        ))
+    };
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum LogFormat {
+    Csv,
+    JsonLines,
+    Parquet,
+    Sqlite,
+    CodeMaatGit2,
+}
+
+impl ValueEnum for LogFormat {
+    fn value_variants<'a>() -> &'a [Self] {
+        &[
+            LogFormat::Csv,
+            LogFormat::JsonLines,
+            LogFormat::Parquet,
+            LogFormat::Sqlite,
+            LogFormat::CodeMaatGit2,
+        ]
+    }
+
+    fn to_possible_value(&self) -> Option<PossibleValue> {
+        Some(match self {
+            LogFormat::Csv => PossibleValue::new("csv")
+                .help("Character separated value, ',' is delimiter, 1st line is item name"),
+            LogFormat::JsonLines => {
+                PossibleValue::new("jsonl").help("One JSON object per line and change record")
+            }
+            LogFormat::Parquet => PossibleValue::new("parquet")
+                .help("Apache Parquet file, e.g. for DuckDB or Polars; requires --output"),
+            LogFormat::Sqlite => PossibleValue::new("sqlite")
+                .help("SQLite database of commits and their changes; requires --output"),
+            LogFormat::CodeMaatGit2 => PossibleValue::new("git2").help(
+                "Code Maat's git2 log format (git log --numstat --pretty=format:'--%h--%ad--%aN')",
+            ),
+        })
+    }
+}
+
+#[derive(Default, Serialize)]
+struct ChangeRecord<'a> {
+    commit: &'a str,
+    date: String,
+    author: &'a str,
+    message: &'a str,
+    path: &'a str,
+    added: u64,
+    deleted: u64,
+}
+
+fn change_records(log: &[CommitChanges]) -> Result<Vec<ChangeRecord<'_>>, Box<dyn Error>> {
+    let mut records = Vec::new();
+    for commit in log {
+        let date = commit.date().format(&Rfc3339)?;
+        for change in &commit.changes {
+            records.push(ChangeRecord {
+                commit: &commit.id,
+                date: date.clone(),
+                author: &commit.author,
+                message: commit.message.trim_end(),
+                path: &change.path,
+                added: change.added,
+                deleted: change.deleted,
+            });
+        }
+    }
+    Ok(records)
+}
+
+fn csv_output(log: &[CommitChanges], writer: &mut dyn Write) -> Result<(), Box<dyn Error>> {
+    let mut wtr = csv::Writer::from_writer(writer);
+    for record in change_records(log)? {
+        wtr.serialize(record)?;
+    }
+    wtr.flush()?;
+    Ok(())
+}
+
+fn json_lines_output(log: &[CommitChanges], writer: &mut dyn Write) -> Result<(), Box<dyn Error>> {
+    for record in change_records(log)? {
+        serde_json::to_writer(&mut *writer, &record)?;
+        writeln!(writer)?;
+    }
+    Ok(())
+}
+
+fn parquet_output(
+    log: &[CommitChanges],
+    metadata: Metadata,
+    writer: &mut dyn Write,
+) -> Result<(), Box<dyn Error>> {
+    let result = AnalysisResult::from_records("Change log", metadata, &change_records(log)?)?;
+    columnar::write_parquet(writer, &result)
+}
+
+/// Code Maat's git2 format: a header line per commit, followed by one
+/// numstat line per changed file and an empty line
+fn code_maat_git2_output(
+    log: &[CommitChanges],
+    writer: &mut dyn Write,
+) -> Result<(), Box<dyn Error>> {
+    let date_format = format_description!("[year]-[month]-[day]");
+    for commit in log.iter().filter(|c| !c.changes.is_empty()) {
+        writeln!(
+            writer,
+            "--{}--{}--{}",
+            &commit.id[..7.min(commit.id.len())],
+            commit.date().format(&date_format)?,
+            commit.author
+        )?;
+        for change in &commit.changes {
+            // like git's numstat, binary files have no line counts
+            match change.binary {
+                true => writeln!(writer, "-\t-\t{}", change.path)?,
+                false => writeln!(
+                    writer,
+                    "{}\t{}\t{}",
+                    change.added, change.deleted, change.path
+                )?,
+            }
+        }
+        writeln!(writer)?;
+    }
+    Ok(())
+}
+
+/// Global options for analysis results that do not apply to the change log
+const RESULT_ARGS: [(&str, &str); 5] = [
+    ("format", "--format"),
+    ("SORT_BY", "--sort-by"),
+    ("desc", "--desc"),
+    ("top", "--top"),
+    ("min", "--min"),
+];
+
+/// Reject global options given for analysis results, e.g. `--format`,
+/// instead of silently ignoring them
+pub fn check_global_args(matches: &ArgMatches) -> Result<(), Box<dyn Error>> {
+    for (id, name) in RESULT_ARGS {
+        if matches.value_source(id) == Some(ValueSource::CommandLine) {
+            let hint = match id {
+                "format" => "set the format with --log-format",
+                _ => "all change records are exported",
+            };
+            return Err(format!("{} does not apply to {}; {}", name, COMMAND, hint).into());
+        }
+    }
+    Ok(())
+}
+
+pub fn run(
+    common_args: CommonArgs,
+    git_args: GitArgs,
+    log_format: LogFormat,
+) -> Result<(), Box<dyn Error>> {
+    info!("Run export of the change log");
+    let log = collect_project_changes(&common_args, git_args.clone())?;
+
+    if log_format == LogFormat::Sqlite {
+        let path = common_args
+            .output
+            .ok_or("sqlite output requires --output")?;
+        return sqlite::write_database(path, &[], &log);
+    }
+    // binary data is never written to the terminal
+    if log_format == LogFormat::Parquet && common_args.output.is_none() {
+        return Err("parquet output requires --output".into());
+    }
+
+    let mut writer = match common_args.output {
+        Some(path) => Box::new(BufWriter::new(File::create(path)?)) as Box<dyn Write>,
+        None => Box::new(io::stdout()) as Box<dyn Write>,
+    };
+    match log_format {
+        LogFormat::Csv => csv_output(&log, &mut writer)?,
+        LogFormat::JsonLines => json_lines_output(&log, &mut writer)?,
+        LogFormat::Sqlite => unreachable!("written above"),
+        LogFormat::Parquet => {
+            let metadata = Metadata::of_history(COMMAND, &common_args, &git_args, &log);
+            parquet_output(&log, metadata, &mut writer)?
+        }
+        LogFormat::CodeMaatGit2 => code_maat_git2_output(&log, &mut writer)?,
+    }
+    writer.flush()?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn verify_code_maat_git2_output() {
+        let commit = |id: &str, time: i64| CommitChanges {
+            id: id.to_owned(),
+            ..CommitChanges::fixture("ann", time)
+        };
+        let log = vec![
+            commit("4b825dc642cb6eb9a060e54bf8d69288fbee4904", 1_700_000_000)
+                .with_change("src/main.rs", 12, 3)
+                .with_binary_change("logo.png"),
+            // merge commits have no changes of their own
+            commit("9f2c1e0d", 1_699_990_000),
+            commit("e69de29b", 1_699_900_000).with_change("README.md", 1, 0),
+        ];
+        let mut out = Vec::new();
+        code_maat_git2_output(&log, &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "--4b825dc--2023-11-14--ann\n\
+             12\t3\tsrc/main.rs\n\
+             -\t-\tlogo.png\n\
+             \n\
+             --e69de29--2023-11-13--ann\n\
+             1\t0\tREADME.md\n\
+             \n"
+        );
+    }
+}
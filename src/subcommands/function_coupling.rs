@@ -0,0 +1,193 @@
+use crate::chart::{Chart, ChartType};
+use crate::cli::{CommonArgs, GitArgs};
+use crate::coupling::{changesets, coupling, Coupling};
+use crate::result::{AnalysisResult, Metadata};
+use crate::xray::{function_history, function_log};
+use git2::Repository;
+use serde::Serialize;
+use std::error::Error;
+
+use super::coupling::CouplingArgs;
+use super::xray::locate;
+#[allow(unused_imports)]
+use crate::git_common_args_extension;
+
+use log::info;
+
+pub const COMMAND: &str = "function-coupling";
+
+#[macro_export]
+macro_rules! function_coupling_command {
+    ($command_builder:expr) => {
+        $command_builder.subcommand(git_common_args_extension(
+            subcommands::coupling::coupling_args_extension(
+                Command::new(subcommands::function_coupling::COMMAND)
+                    .about("Temporal coupling of functions, within and across files")
+                    .after_help(
+                        "Functions are named <file>:<function>. Coupling is only computed
+                        between the functions of the files given, not with other files.
+                        Functions of the same file changing together often hint at duplicated
+                        logic to be kept in sync. Supported are Rust, C, Java, Python and
+                        TypeScript sources",
+                    )
+                    .arg(
+                        Arg::new("ENTITY")
+                            .required(true)
+                            .num_args(1..)
+                            .help("The files as named by the other analyses, e.g. src/main.rs"),
+                    ),
+            ),
+        ))
+    };
+}
+
+#[derive(Default, Serialize)]
+pub struct FunctionCoupling {
+    pub entity: String,
+    pub coupled: String,
+    pub degree: u64,
+    #[serde(rename = "average-revs")]
+    pub average_revs: u64,
+    /// Both functions are in the same file
+    #[serde(rename = "same-file")]
+    pub same_file: bool,
+}
+
+impl FunctionCoupling {
+    /// Coupling of functions of the files; paths and function names may
+    /// contain `:`, so a function's file is the longest one it starts with
+    pub fn new(coupling: Coupling, files: &[&str]) -> Self {
+        let file = |function: &str| {
+            files
+                .iter()
+                .filter(|file| {
+                    function
+                        .strip_prefix(**file)
+                        .is_some_and(|name| name.starts_with(':'))
+                })
+                .max_by_key(|file| file.len())
+                .copied()
+        };
+        FunctionCoupling {
+            same_file: file(&coupling.entity) == file(&coupling.coupled),
+            entity: coupling.entity,
+            coupled: coupling.coupled,
+            degree: coupling.degree,
+            average_revs: coupling.average_revs,
+        }
+    }
+}
+
+pub fn run(
+    common_args: CommonArgs,
+    git_args: GitArgs,
+    coupling_args: CouplingArgs,
+    entities: &[&str],
+) -> Result<(), Box<dyn Error>> {
+    info!(
+        "Run function level coupling analysis of {}",
+        entities.join(", ")
+    );
+    let mut histories = Vec::new();
+    for entity in entities {
+        let (repository, path) = locate(&common_args, entity)?;
+        let repo = Repository::open(repository)?;
+        histories.push((*entity, function_history(&repo, path, git_args.clone())?));
+    }
+    let log = function_log(&histories);
+    let mut metadata = Metadata::new(COMMAND, &common_args);
+    metadata.commits = Some(log.len());
+
+    let changesets = changesets(&log, &coupling_args.grouping);
+    let coupling: Vec<FunctionCoupling> = coupling(&changesets, &coupling_args.options)
+        .into_iter()
+        .map(|coupling| FunctionCoupling::new(coupling, entities))
+        .collect();
+    AnalysisResult::from_records("Function coupling", metadata, &coupling)?
+        .chart(
+            Chart::new(ChartType::Network, "Function coupling")
+                .field("source", "entity")
+                .field("target", "coupled")
+                .field("weight", "degree"),
+        )
+        .highlight("degree")
+        .output_ranked(&common_args)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::coupling::{ChangesetGrouping, CouplingOptions};
+    use crate::xray::{changed_functions, functions, FunctionHistory, FunctionRevision, Language};
+
+    /// History of the file with a revision per pair of old and new source
+    fn history(sources: &[(&str, &str)]) -> FunctionHistory {
+        let revisions = sources
+            .iter()
+            .enumerate()
+            .map(|(i, (old, new))| FunctionRevision {
+                commit: i.to_string(),
+                time: i as i64,
+                author: String::new(),
+                message: String::new(),
+                changes: changed_functions(
+                    &functions(Language::Rust, old).unwrap(),
+                    &functions(Language::Rust, new).unwrap(),
+                )
+                .unwrap(),
+            })
+            .collect();
+        FunctionHistory {
+            commits: sources.len(),
+            revisions,
+            functions: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn verify_function_coupling() {
+        // commit 0 changes both functions of v1:a.rs, commit 1 the method of
+        // v1:a.rs and the function of v1:b.rs; the paths contain `:` like
+        // the function names
+        let a = history(&[
+            (
+                "impl Parser {\n    fn parse() {}\n}\nfn helper() {}\n",
+                "impl Parser {\n    fn parse() { 1 }\n}\nfn helper() { 1 }\n",
+            ),
+            (
+                "impl Parser {\n    fn parse() { 1 }\n}\n",
+                "impl Parser {\n    fn parse() { 2 }\n}\n",
+            ),
+        ]);
+        let b = history(&[("", ""), ("fn lex() {}\n", "fn lex() { 1 }\n")]);
+        let log = function_log(&[("v1:a.rs", a), ("v1:b.rs", b)]);
+        let options = CouplingOptions {
+            min_revs: 1,
+            min_shared_revs: 1,
+            min_coupling: 0,
+            ..CouplingOptions::default()
+        };
+        let mut coupling: Vec<(String, String, bool)> =
+            coupling(&changesets(&log, &ChangesetGrouping::Commit), &options)
+                .into_iter()
+                .map(|coupling| FunctionCoupling::new(coupling, &["v1:a.rs", "v1:b.rs"]))
+                .map(|c| (c.entity, c.coupled, c.same_file))
+                .collect();
+        coupling.sort();
+        assert_eq!(
+            coupling,
+            vec![
+                (
+                    "v1:a.rs:Parser::parse".to_owned(),
+                    "v1:a.rs:helper".to_owned(),
+                    true
+                ),
+                (
+                    "v1:a.rs:Parser::parse".to_owned(),
+                    "v1:b.rs:lex".to_owned(),
+                    false
+                ),
+            ]
+        );
+    }
+}
@@ -1,11 +1,12 @@
+use crate::changes::{collect_project_changes, CommitChanges};
+use crate::chart::{Chart, ChartType};
 use crate::cli::{CommonArgs, GitArgs};
-use git2::Error;
-use git2::{Commit, ObjectType, Oid, Repository, TreeWalkMode, TreeWalkResult};
-use std::cmp::{Ord, Ordering};
-use std::collections::BTreeSet;
-use std::str;
+use crate::result::{AnalysisResult, Metadata};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::error::Error;
 
-use crate::determine_commits_to_analyse;
+use super::cloc::lines_of_code;
 #[allow(unused_imports)]
 use crate::git_common_args_extension;
 
@@ -17,82 +18,93 @@ pub const COMMAND: &str = "hotspot";
 macro_rules! hotspot_command {
     ($command_builder:expr) => {
         $command_builder.subcommand(git_common_args_extension(
-            Command::new(subcommands::hotspot::COMMAND).about("Determine hotspots"),
+            Command::new(subcommands::hotspot::COMMAND)
+                .about("Determine hotspots: change frequency and size of files")
+                .after_help(
+                    "Lists entity, revisions and code: the analysed commits changing the file \
+                     and its lines of code, most frequently changed first. Only files of the \
+                     current checkout with recognized source code are listed",
+                ),
         ))
     };
 }
 
-#[derive(Clone)]
-struct EntryRevisions {
-    name: String,
-    revisions: BTreeSet<Oid>,
+#[derive(Default, Serialize)]
+pub struct Hotspot {
+    pub entity: String,
+    pub revisions: u64,
+    pub code: usize,
 }
 
-impl EntryRevisions {
-    pub fn new(name: String) -> EntryRevisions {
-        EntryRevisions {
-            name,
-            revisions: BTreeSet::new(),
-        }
-    }
-}
+pub fn run(common_args: CommonArgs, git_args: GitArgs) -> Result<(), Box<dyn Error>> {
+    info!("Run hotspot analysis");
+    let log = collect_project_changes(&common_args, git_args.clone())?;
+    let metadata = Metadata::of_history(COMMAND, &common_args, &git_args, &log);
+    let hotspots = hotspots(&log, lines_of_code(&common_args));
 
-impl Ord for EntryRevisions {
-    fn cmp(&self, other: &Self) -> Ordering {
-        self.name.cmp(&other.name)
-    }
-}
-
-impl PartialOrd for EntryRevisions {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.cmp(other))
-    }
+    // Zoomable circle packing of the directory hierarchy: circle size is
+    // the lines of code, the colour intensity the number of revisions
+    AnalysisResult::from_records("Hotspots", metadata, &hotspots)?
+        .chart(
+            Chart::new(ChartType::CirclePacking, "Hotspots")
+                .field("path", "entity")
+                .field("size", "code")
+                .field("color", "revisions"),
+        )
+        .highlight("revisions")
+        .history(log)
+        .output_ranked(&common_args)
 }
 
-impl PartialEq for EntryRevisions {
-    fn eq(&self, other: &Self) -> bool {
-        self.name == other.name
+/// Join the revisions of the change log with the lines of code per file,
+/// most frequently changed first
+pub fn hotspots(log: &[CommitChanges], lines_of_code: BTreeMap<String, usize>) -> Vec<Hotspot> {
+    let mut revisions: BTreeMap<&str, u64> = BTreeMap::new();
+    for commit in log {
+        for change in &commit.changes {
+            *revisions.entry(&change.path).or_default() += 1;
+        }
     }
-}
-
-impl Eq for EntryRevisions {}
 
-fn analyse_entries_in_commit(commit: &Commit, entries: &mut BTreeSet<EntryRevisions>) {
-    commit
-        .tree()
-        .expect("Every commit has a tree object")
-        .walk(TreeWalkMode::PreOrder, |_, entry| {
-            if entry.kind() == Some(ObjectType::Blob) {
-                if let Some(n) = entry.name() {
-                    let entry_revision = EntryRevisions::new(n.to_owned());
-                    entries.insert(entry_revision.clone());
-                    if let Some(entry_revision) = entries.get(&entry_revision) {
-                        let mut e = entry_revision.clone();
-                        e.revisions.insert(entry.id().clone());
-                        entries.replace(e);
-                    }
-                }
-            }
-            TreeWalkResult::Ok
+    let mut hotspots: Vec<Hotspot> = lines_of_code
+        .into_iter()
+        .filter_map(|(entity, code)| {
+            let revisions = *revisions.get(entity.as_str())?;
+            Some(Hotspot {
+                entity,
+                revisions,
+                code,
+            })
         })
-        .unwrap();
+        .collect();
+    hotspots.sort_by(|a, b| b.revisions.cmp(&a.revisions).then(b.code.cmp(&a.code)));
+    hotspots
 }
 
-pub fn run(common_args: CommonArgs, git_args: GitArgs) -> Result<(), Error> {
-    info!("Run git revision summary");
-    let repo = Repository::open(common_args.project_dir)?;
+#[cfg(test)]
+mod test {
+    use super::*;
 
-    let revwalk = determine_commits_to_analyse(&repo, git_args)?;
-    let mut entries: BTreeSet<EntryRevisions> = BTreeSet::new();
-
-    for commit in revwalk {
-        let commit = commit?;
-        analyse_entries_in_commit(&commit, &mut entries);
-    }
-    println!("entry,n-revs");
-    for entry_revision in entries {
-        println!("{},{}", entry_revision.name, entry_revision.revisions.len());
+    #[test]
+    fn verify_hotspots() {
+        let log = vec![
+            CommitChanges::fixture("ann", 3).with_changes(&["a.rs", "b.rs", "logo.png"]),
+            CommitChanges::fixture("ann", 2).with_changes(&["b.rs", "deleted.rs"]),
+            CommitChanges::fixture("ann", 1).with_changes(&["a.rs"]),
+        ];
+        // files without revisions or code are no hotspots
+        let code = BTreeMap::from([
+            ("a.rs".to_owned(), 10),
+            ("b.rs".to_owned(), 50),
+            ("new.rs".to_owned(), 5),
+        ]);
+        let hotspots: Vec<(String, u64, usize)> = hotspots(&log, code)
+            .into_iter()
+            .map(|h| (h.entity, h.revisions, h.code))
+            .collect();
+        assert_eq!(
+            hotspots,
+            vec![("b.rs".to_owned(), 2, 50), ("a.rs".to_owned(), 2, 10)]
+        );
     }
-
-    Ok(())
 }
@@ -0,0 +1,292 @@
+use crate::changes::{collect_project_changes, CommitChanges};
+use crate::chart::{Chart, ChartType};
+use crate::cli::{CommonArgs, GitArgs};
+use crate::git::tip_commit;
+use crate::result::{AnalysisResult, Metadata};
+use crate::trend::{windows, Period, Window};
+use clap::{value_parser, Arg, ArgMatches, Command};
+use git2::{Oid, Repository};
+use serde::Serialize;
+use std::collections::btree_map::Entry;
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::path::Path;
+
+use super::cloc::{code_lines, lines_of_code};
+use super::hotspot::hotspots;
+#[allow(unused_imports)]
+use crate::git_common_args_extension;
+
+use log::info;
+
+pub const COMMAND: &str = "hotspot-trend";
+
+#[macro_export]
+macro_rules! hotspot_trend_command {
+    ($command_builder:expr) => {
+        $command_builder.subcommand(git_common_args_extension(
+            subcommands::hotspot_trend::trend_args_extension(
+                Command::new(subcommands::hotspot_trend::COMMAND)
+                    .about("Hotspots over sliding time windows: change frequency, rank and size")
+                    .after_help(
+                        "Without entities the hotspots with the most revisions are traced.
+                        The rank is the position among all files changed in the window,
+                        the size the lines of code at the end of the window",
+                    )
+                    .arg(
+                        Arg::new("entities")
+                            .long("entities")
+                            .default_value("10")
+                            .value_parser(value_parser!(usize))
+                            .help("Number of hotspots to trace"),
+                    )
+                    .arg(
+                        Arg::new("ENTITY")
+                            .num_args(0..)
+                            .help("Files to trace as named by the other analyses"),
+                    ),
+            ),
+        ))
+    };
+}
+
+/// Options of trend analyses; shared by all commands computing trends
+pub fn trend_args_extension(builder: Command) -> Command {
+    builder
+        .arg(
+            Arg::new("period")
+                .long("period")
+                .default_value("month")
+                .value_parser(value_parser!(Period))
+                .help("Calendar period the history is cut into"),
+        )
+        .arg(
+            Arg::new("span")
+                .long("span")
+                .default_value("3")
+                .value_parser(value_parser!(u32).range(1..))
+                .help("Number of periods a sliding window covers"),
+        )
+}
+
+pub struct TrendArgs {
+    pub period: Period,
+    pub span: u32,
+}
+
+impl TrendArgs {
+    pub fn from_cli_args(matches: &ArgMatches) -> Self {
+        TrendArgs {
+            period: *matches
+                .get_one::<Period>("period")
+                .expect("Option with default is never None"),
+            span: *matches
+                .get_one::<u32>("span")
+                .expect("Option with default is never None"),
+        }
+    }
+}
+
+#[derive(Default, Serialize)]
+pub struct HotspotTrend {
+    pub entity: String,
+    /// First day of the last period of the window
+    pub period: String,
+    pub revisions: u64,
+    /// Position among all files changed in the window
+    pub rank: Option<u64>,
+    /// Lines of code at the end of the window, none if the file did not exist
+    pub code: Option<usize>,
+}
+
+pub fn run(
+    common_args: CommonArgs,
+    git_args: GitArgs,
+    trend_args: TrendArgs,
+    entities: Vec<String>,
+    max_entities: usize,
+) -> Result<(), Box<dyn Error>> {
+    info!("Run hotspot trend analysis");
+    let log = collect_project_changes(&common_args, git_args.clone())?;
+    let metadata = Metadata::of_history(COMMAND, &common_args, &git_args, &log);
+
+    let entities = match entities.is_empty() {
+        true => hotspots(&log, lines_of_code(&common_args))
+            .into_iter()
+            .take(max_entities)
+            .map(|h| h.entity)
+            .collect(),
+        false => entities,
+    };
+    let windows = windows(&log, trend_args.period, trend_args.span);
+    let sizes = sizes(&common_args, &git_args, &entities, &windows)?;
+    let trend = hotspot_trend(&log, &entities, &windows, &sizes);
+
+    AnalysisResult::from_records("Hotspot trend", metadata, &trend)?
+        .chart(
+            Chart::new(ChartType::TimeSeries, "Hotspot trend")
+                .field("x", "period")
+                .field("y", "revisions")
+                .field("series", "entity"),
+        )
+        .highlight("revisions")
+        .history(log)
+        .output_ranked(&common_args)
+}
+
+/// Revisions and rank of the entities per window, by window and entity
+pub fn hotspot_trend(
+    log: &[CommitChanges],
+    entities: &[String],
+    windows: &[Window],
+    sizes: &BTreeMap<(usize, String), usize>,
+) -> Vec<HotspotTrend> {
+    let mut trend = Vec::new();
+    for (w, window) in windows.iter().enumerate() {
+        let mut revisions: BTreeMap<&str, u64> = BTreeMap::new();
+        for commit in log.iter().filter(|c| window.contains(c)) {
+            for change in &commit.changes {
+                *revisions.entry(&change.path).or_default() += 1;
+            }
+        }
+        for entity in entities {
+            let count = revisions.get(entity.as_str()).copied().unwrap_or_default();
+            let rank =
+                (count > 0).then(|| 1 + revisions.values().filter(|r| **r > count).count() as u64);
+            trend.push(HotspotTrend {
+                entity: entity.clone(),
+                period: window.label(),
+                revisions: count,
+                rank,
+                code: sizes.get(&(w, entity.clone())).copied(),
+            });
+        }
+    }
+    trend
+}
+
+/// Lines of code of the entities at the end of every window, by window
+/// index and entity
+fn sizes(
+    common_args: &CommonArgs,
+    git_args: &GitArgs,
+    entities: &[String],
+    windows: &[Window],
+) -> Result<BTreeMap<(usize, String), usize>, Box<dyn Error>> {
+    at_window_ends(common_args, git_args, entities, windows, code_lines)
+}
+
+/// A measure of the source of the entities at the end of every window, by
+/// window index and entity; none where the file did not exist or the
+/// measure does not apply
+///
+/// The revision at the end of a window is the newest commit before it on
+/// the first parent line of the analysed tip, `HEAD` unless `--commit`
+/// selects another.
+pub fn at_window_ends<T: Clone>(
+    common_args: &CommonArgs,
+    git_args: &GitArgs,
+    entities: &[String],
+    windows: &[Window],
+    measure: impl Fn(&str, &str) -> Option<T>,
+) -> Result<BTreeMap<(usize, String), T>, Box<dyn Error>> {
+    let mut values = BTreeMap::new();
+    for repository in &common_args.repositories {
+        let prefix = match common_args.is_multi_repository() {
+            true => format!("{}/", repository.name),
+            false => String::new(),
+        };
+        let paths: Vec<(&String, &str)> = entities
+            .iter()
+            .filter_map(|entity| Some((entity, entity.strip_prefix(&prefix)?)))
+            .collect();
+        if paths.is_empty() {
+            continue;
+        }
+
+        let repo = Repository::open(&repository.path)?;
+        let mut first_parents: Vec<(i64, Oid)> = Vec::new();
+        let mut commit = tip_commit(&repo, git_args)?;
+        loop {
+            first_parents.push((commit.time().seconds(), commit.id()));
+            match commit.parent(0) {
+                Ok(parent) => commit = parent,
+                Err(_) => break,
+            }
+        }
+
+        // most files do not change from one window to the next
+        let mut measured: BTreeMap<Oid, Option<T>> = BTreeMap::new();
+        for (w, window) in windows.iter().enumerate() {
+            let Some((_, id)) = first_parents.iter().find(|(time, _)| *time < window.to) else {
+                continue;
+            };
+            let tree = repo.find_commit(*id)?.tree()?;
+            for (entity, path) in &paths {
+                let Ok(entry) = tree.get_path(Path::new(path)) else {
+                    continue;
+                };
+                let value = match measured.entry(entry.id()) {
+                    Entry::Occupied(value) => value.into_mut(),
+                    Entry::Vacant(value) => {
+                        let blob = repo.find_blob(entry.id())?;
+                        let source = String::from_utf8_lossy(blob.content());
+                        value.insert(measure(path, &source))
+                    }
+                };
+                if let Some(value) = value {
+                    values.insert((w, (*entity).clone()), value.clone());
+                }
+            }
+        }
+    }
+    Ok(values)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use time::macros::datetime;
+
+    #[test]
+    fn verify_hotspot_trend_per_window() {
+        let log = vec![
+            CommitChanges::fixture("", datetime!(2024-03-20 12:00 UTC).unix_timestamp())
+                .with_changes(&["a.rs", "c.rs"]),
+            CommitChanges::fixture("", datetime!(2024-03-05 12:00 UTC).unix_timestamp())
+                .with_changes(&["b.rs", "c.rs"]),
+            CommitChanges::fixture("", datetime!(2024-01-10 12:00 UTC).unix_timestamp())
+                .with_change("a.rs", 10, 0),
+        ];
+        let entities = vec!["a.rs".to_owned(), "b.rs".to_owned()];
+        let sizes = BTreeMap::from([
+            ((0, "a.rs".to_owned()), 10),
+            ((1, "a.rs".to_owned()), 10),
+            ((2, "a.rs".to_owned()), 12),
+            ((2, "b.rs".to_owned()), 3),
+        ]);
+        let trend = hotspot_trend(&log, &entities, &windows(&log, Period::Month, 1), &sizes);
+        assert_eq!(
+            trend
+                .iter()
+                .map(|t| (
+                    t.entity.as_str(),
+                    t.period.as_str(),
+                    t.revisions,
+                    t.rank,
+                    t.code
+                ))
+                .collect::<Vec<_>>(),
+            vec![
+                ("a.rs", "2024-01-01", 1, Some(1), Some(10)),
+                ("b.rs", "2024-01-01", 0, None, None),
+                // an idle window ranks no file
+                ("a.rs", "2024-02-01", 0, None, Some(10)),
+                ("b.rs", "2024-02-01", 0, None, None),
+                // c.rs changed twice ranks first
+                ("a.rs", "2024-03-01", 1, Some(2), Some(12)),
+                ("b.rs", "2024-03-01", 1, Some(2), Some(3)),
+            ]
+        );
+    }
+}
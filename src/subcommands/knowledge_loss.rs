@@ -0,0 +1,112 @@
+use crate::changes::collect_project_changes;
+use crate::chart::{Chart, ChartType};
+use crate::cli::{CommonArgs, GitArgs};
+use crate::knowledge::{component_log, component_sizes, inactive_authors, knowledge_loss};
+use crate::result::{AnalysisResult, Metadata};
+use clap::{value_parser, Arg, Command};
+use std::collections::BTreeSet;
+use std::error::Error;
+use time::OffsetDateTime;
+
+use super::cloc::lines_of_code;
+#[allow(unused_imports)]
+use crate::git_common_args_extension;
+
+use log::{info, warn};
+
+pub const COMMAND: &str = "knowledge-loss";
+
+#[macro_export]
+macro_rules! knowledge_loss_command {
+    ($command_builder:expr) => {
+        $command_builder.subcommand(git_common_args_extension(
+            subcommands::knowledge_loss::component_args_extension(
+                Command::new(subcommands::knowledge_loss::COMMAND)
+                    .about("Knowledge islands: code mostly written by authors who left")
+                    .after_help(
+                        "Departed authors are the given ones or, without --author, the ones
+                        without commits in the last --inactive-months. The loss is the share
+                        of added lines written by departed authors, the risk the loss weighted
+                        by the revisions relative to the most revised entity",
+                    )
+                    .arg(
+                        Arg::new("author")
+                            .long("author")
+                            .action(ArgAction::Append)
+                            .help("Author who left, as named in the commits; repeat for several"),
+                    )
+                    .arg(
+                        Arg::new("inactive-months")
+                            .long("inactive-months")
+                            .default_value("6")
+                            .value_parser(value_parser!(u32))
+                            .conflicts_with("author")
+                            .help(
+                                "Months without commits after which an author counts as departed",
+                            ),
+                    ),
+            ),
+        ))
+    };
+}
+
+/// Aggregation of files into components; shared by the knowledge analyses
+pub fn component_args_extension(builder: Command) -> Command {
+    builder.arg(
+        Arg::new("depth")
+            .long("depth")
+            .default_value("0")
+            .value_parser(value_parser!(usize))
+            .help("Aggregate files into components, the directories this deep; 0 analyses files"),
+    )
+}
+
+/// Who is considered to have left the project
+pub enum Departed {
+    Authors(BTreeSet<String>),
+    InactiveMonths(u32),
+}
+
+pub fn run(
+    common_args: CommonArgs,
+    git_args: GitArgs,
+    departed: Departed,
+    depth: usize,
+) -> Result<(), Box<dyn Error>> {
+    info!("Run knowledge loss analysis");
+    let log = collect_project_changes(&common_args, git_args.clone())?;
+    let metadata = Metadata::of_history(COMMAND, &common_args, &git_args, &log);
+    let departed = match departed {
+        Departed::Authors(authors) => {
+            for author in authors
+                .iter()
+                .filter(|a| !log.iter().any(|c| c.author == **a))
+            {
+                warn!("Author {} has no commits in the analysed history", author);
+            }
+            authors
+        }
+        Departed::InactiveMonths(months) => {
+            inactive_authors(&log, months, OffsetDateTime::now_utc())
+        }
+    };
+    info!("Departed authors: {:?}", departed);
+    let losses = knowledge_loss(
+        &component_log(&log, depth),
+        component_sizes(lines_of_code(&common_args), depth),
+        &departed,
+    );
+
+    // Circle packing of the directory hierarchy: circle size is the lines
+    // of code, the colour intensity the share of lost knowledge
+    AnalysisResult::from_records("Knowledge loss", metadata, &losses)?
+        .chart(
+            Chart::new(ChartType::CirclePacking, "Knowledge loss")
+                .field("path", "entity")
+                .field("size", "code")
+                .field("color", "loss"),
+        )
+        .highlight("risk")
+        .history(log)
+        .output_ranked(&common_args)
+}
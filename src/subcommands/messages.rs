@@ -0,0 +1,83 @@
+use crate::changes::collect_project_changes;
+use crate::chart::{Chart, ChartType};
+use crate::cli::{CommonArgs, GitArgs};
+use crate::messages::{classify, term_frequencies};
+use crate::result::{AnalysisResult, Metadata};
+use std::error::Error;
+
+#[allow(unused_imports)]
+use crate::git_common_args_extension;
+
+use log::info;
+
+pub const COMMAND: &str = "messages";
+
+#[macro_export]
+macro_rules! messages_command {
+    ($command_builder:expr) => {
+        $command_builder.subcommand(git_common_args_extension(
+            Command::new(subcommands::messages::COMMAND)
+                .about("Commit messages: term frequencies or commits per class and file")
+                .after_help(
+                    "Classes are regular expressions searched in the commit messages,
+                    by default the conventional commit types. They are replaced by the
+                    [messages.classes] table of the configuration file",
+                )
+                .arg(
+                    Arg::new("classify")
+                        .long("classify")
+                        .action(ArgAction::SetTrue)
+                        .help("Count the revisions of every file by commits of every class"),
+                )
+                .arg(
+                    Arg::new("min-length")
+                        .long("min-length")
+                        .default_value("3")
+                        .value_parser(value_parser!(usize))
+                        .conflicts_with("classify")
+                        .help("Minimum number of letters of a term"),
+                ),
+        ))
+    };
+}
+
+pub enum MessageAnalysis {
+    Terms { min_length: usize },
+    Classes,
+}
+
+pub fn run(
+    common_args: CommonArgs,
+    git_args: GitArgs,
+    analysis: MessageAnalysis,
+) -> Result<(), Box<dyn Error>> {
+    info!("Run commit message analysis");
+    let log = collect_project_changes(&common_args, git_args.clone())?;
+    let metadata = Metadata::of_history(COMMAND, &common_args, &git_args, &log);
+
+    let result = match analysis {
+        MessageAnalysis::Terms { min_length } => AnalysisResult::from_records(
+            "Commit message terms",
+            metadata,
+            &term_frequencies(&log, min_length),
+        )?
+        .chart(
+            Chart::new(ChartType::BarChart, "Commit message terms")
+                .field("label", "term")
+                .field("value", "count"),
+        )
+        .highlight("count"),
+        MessageAnalysis::Classes => {
+            let patterns = common_args.config.messages.patterns()?;
+            AnalysisResult::from_records("Commit classes", metadata, &classify(&log, &patterns))?
+                .chart(
+                    Chart::new(ChartType::Heatmap, "Commit classes")
+                        .field("x", "class")
+                        .field("y", "entity")
+                        .field("value", "matches"),
+                )
+                .highlight("matches")
+        }
+    };
+    result.history(log).output_ranked(&common_args)
+}
@@ -0,0 +1,698 @@
+use crate::changes::{collect_project_changes, CommitChanges};
+use crate::chart::{escape_html, script_json, Chart, ChartTemplates, ChartType};
+use crate::cli::{CommonArgs, GitArgs, OutputFormat, OutputFormatter};
+use crate::coupling::{changesets, coupling};
+use crate::html;
+use crate::metrics::{age, churn, contributions, ownership, Contribution};
+use crate::result::{AnalysisResult, Metadata, Value};
+use crate::risk::indentation_complexity;
+use crate::sqlite;
+use crate::table::TableOptions;
+use crate::trend::{windows, Period};
+use clap::{builder::PossibleValue, ArgMatches, ValueEnum};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::error::Error;
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use time::OffsetDateTime;
+
+use super::cloc::lines_of_code;
+use super::coupling::CouplingArgs;
+use super::hotspot::{hotspots, Hotspot};
+use super::hotspot_trend::at_window_ends;
+use super::summary::summarize;
+#[allow(unused_imports)]
+use crate::git_common_args_extension;
+
+use log::info;
+
+pub const COMMAND: &str = "report";
+
+#[macro_export]
+macro_rules! report_command {
+    ($command_builder:expr) => {
+        $command_builder.subcommand(git_common_args_extension(
+            subcommands::coupling::coupling_args_extension(
+                Command::new(subcommands::report::COMMAND)
+                    .about("Combined HTML report of several analyses with cross-links")
+                    .after_help(
+                        "The report is html with --format D3html, with --layout site a directory of pages,
+                        default ocs-report/. With --format csv it is a directory with a file per analysis,
+                        default ocs-report/ as well. Clicking an entity shows its coupling partners,
+                        authors and complexity trend",
+                    )
+                    .arg(
+                        Arg::new("analyses")
+                            .long("analyses")
+                            .value_delimiter(',')
+                            .default_value("summary,hotspots,coupling,age,ownership,churn")
+                            .value_parser(value_parser!(subcommands::report::Analysis))
+                            .help("Comma separated analyses to include in the report"),
+                    )
+                    .arg(
+                        Arg::new("layout")
+                            .long("layout")
+                            .default_value("dashboard")
+                            .value_parser(value_parser!(subcommands::report::Layout))
+                            .help("Single page dashboard or static site with a page per analysis"),
+                    ),
+            ),
+        ))
+    };
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum Analysis {
+    Summary,
+    Hotspots,
+    Coupling,
+    Age,
+    Ownership,
+    Churn,
+}
+
+impl ValueEnum for Analysis {
+    fn value_variants<'a>() -> &'a [Self] {
+        &[
+            Analysis::Summary,
+            Analysis::Hotspots,
+            Analysis::Coupling,
+            Analysis::Age,
+            Analysis::Ownership,
+            Analysis::Churn,
+        ]
+    }
+
+    fn to_possible_value(&self) -> Option<PossibleValue> {
+        Some(match self {
+            Analysis::Summary => PossibleValue::new("summary").help("Commits, authors and entries"),
+            Analysis::Hotspots => {
+                PossibleValue::new("hotspots").help("Change frequency and size of files")
+            }
+            Analysis::Coupling => PossibleValue::new("coupling").help("Entities changing together"),
+            Analysis::Age => PossibleValue::new("age").help("Months since the last change"),
+            Analysis::Ownership => {
+                PossibleValue::new("ownership").help("Main developer of each entity")
+            }
+            Analysis::Churn => PossibleValue::new("churn").help("Lines added and deleted"),
+        })
+    }
+}
+
+impl Analysis {
+    fn id(&self) -> &'static str {
+        match self {
+            Analysis::Summary => "summary",
+            Analysis::Hotspots => "hotspots",
+            Analysis::Coupling => "coupling",
+            Analysis::Age => "age",
+            Analysis::Ownership => "ownership",
+            Analysis::Churn => "churn",
+        }
+    }
+
+    fn title(&self) -> &'static str {
+        match self {
+            Analysis::Summary => "Summary",
+            Analysis::Hotspots => "Hotspots",
+            Analysis::Coupling => "Temporal coupling",
+            Analysis::Age => "Code age",
+            Analysis::Ownership => "Ownership",
+            Analysis::Churn => "Churn",
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum Layout {
+    Dashboard,
+    Site,
+}
+
+impl ValueEnum for Layout {
+    fn value_variants<'a>() -> &'a [Self] {
+        &[Layout::Dashboard, Layout::Site]
+    }
+
+    fn to_possible_value(&self) -> Option<PossibleValue> {
+        Some(match self {
+            Layout::Dashboard => {
+                PossibleValue::new("dashboard").help("All analyses on a single html page")
+            }
+            Layout::Site => {
+                PossibleValue::new("site").help("Directory with an html page per analysis")
+            }
+        })
+    }
+}
+
+pub struct ReportArgs {
+    pub analyses: Vec<Analysis>,
+    pub layout: Layout,
+    pub coupling: CouplingArgs,
+}
+
+impl ReportArgs {
+    pub fn from_cli_args(matches: &ArgMatches) -> Self {
+        let mut analyses: Vec<Analysis> = matches
+            .get_many::<Analysis>("analyses")
+            .expect("Option with default is never None")
+            .copied()
+            .collect();
+        analyses.sort();
+        analyses.dedup();
+        ReportArgs {
+            analyses,
+            layout: *matches
+                .get_one::<Layout>("layout")
+                .expect("Option with default is never None"),
+            coupling: CouplingArgs::from_cli_args(matches),
+        }
+    }
+}
+
+/// Columns naming entities; clicking them shows the entity's details
+const ENTITY_COLUMNS: [&str; 2] = ["entity", "coupled"];
+
+/// Number of entities shown in the churn chart
+const CHURN_CHART_ENTITIES: usize = 30;
+
+/// Results of all analyses of the report plus the details of every entity
+/// needed for the cross-links
+#[derive(Serialize, Default)]
+struct Report {
+    /// By analysis id
+    #[serde(flatten)]
+    results: BTreeMap<&'static str, AnalysisResult>,
+    authors: BTreeMap<String, Vec<Contribution>>,
+    /// Complexity of the hotspots at the end of every month, by entity
+    trend: BTreeMap<String, Vec<ComplexityTrend>>,
+}
+
+#[derive(Clone, Serialize)]
+struct ComplexityTrend {
+    /// First day of the month
+    period: String,
+    /// Indentation complexity at the end of the month
+    complexity: u64,
+}
+
+impl Report {
+    /// One table per analysis; authors and trends are left out
+    fn markdown_output(
+        &self,
+        writer: &mut dyn Write,
+        analyses: &[Analysis],
+    ) -> Result<(), Box<dyn Error>> {
+        writeln!(writer, "# Code analysis report")?;
+        for result in analyses.iter().filter_map(|a| self.results.get(a.id())) {
+            writeln!(writer)?;
+            result.markdown_output(writer)?;
+        }
+        Ok(())
+    }
+
+    /// One table per analysis; authors and trends are left out
+    fn table_output(
+        &self,
+        writer: &mut dyn Write,
+        analyses: &[Analysis],
+        options: &TableOptions,
+    ) -> Result<(), Box<dyn Error>> {
+        for (i, result) in analyses
+            .iter()
+            .filter_map(|a| self.results.get(a.id()))
+            .enumerate()
+        {
+            if i > 0 {
+                writeln!(writer)?;
+            }
+            writeln!(writer, "{}", result.title)?;
+            writeln!(writer)?;
+            result.table_output(writer, options)?;
+        }
+        Ok(())
+    }
+
+    /// The data of a site page: the result of its analysis, and of the other
+    /// results, the authors and the trends what the details of its entities
+    /// show
+    fn page(&self, analysis: Analysis) -> Report {
+        let entity_columns =
+            |result: &AnalysisResult| ENTITY_COLUMNS.map(|name| result.column(name));
+        let mut entities = BTreeSet::new();
+        if let Some(result) = self.results.get(analysis.id()) {
+            for row in &result.rows {
+                for column in entity_columns(result).into_iter().flatten() {
+                    entities.insert(row[column].to_string());
+                }
+            }
+        }
+        let results = self
+            .results
+            .iter()
+            .map(|(id, result)| {
+                let mut result = result.clone();
+                if *id != analysis.id() {
+                    let columns = entity_columns(&result);
+                    result.rows.retain(|row| {
+                        columns
+                            .iter()
+                            .flatten()
+                            .any(|column| entities.contains(&row[*column].to_string()))
+                    });
+                }
+                (*id, result)
+            })
+            .collect();
+        let of_entities = |entity: &&String| entities.contains(entity.as_str());
+        Report {
+            results,
+            authors: self
+                .authors
+                .iter()
+                .filter(|(entity, _)| of_entities(entity))
+                .map(|(entity, authors)| (entity.clone(), authors.clone()))
+                .collect(),
+            trend: self
+                .trend
+                .iter()
+                .filter(|(entity, _)| of_entities(entity))
+                .map(|(entity, trend)| (entity.clone(), trend.clone()))
+                .collect(),
+        }
+    }
+
+    /// A CSV file holds a single table, so a file per analysis into the
+    /// output directory
+    fn write_csv_files(
+        &self,
+        analyses: &[Analysis],
+        common_args: &CommonArgs,
+    ) -> Result<(), Box<dyn Error>> {
+        let directory = output_directory(common_args.output);
+        fs::create_dir_all(&directory)?;
+        for analysis in analyses {
+            let mut writer = create_file(&directory.join(format!("{}.csv", analysis.id())))?;
+            self.results[analysis.id()].csv_output(&mut writer)?;
+            writer.flush()?;
+        }
+        info!("Report written to {}", directory.display());
+        Ok(())
+    }
+
+    /// Bars of the most churned entities, in the order of the churn result
+    fn churn_bars(&self) -> Vec<ChurnBar> {
+        let Some(churn) = self.results.get(Analysis::Churn.id()) else {
+            return Vec::new();
+        };
+        let lines = |row: &[Value], column: &str| {
+            churn
+                .get(row, column)
+                .and_then(Value::as_f64)
+                .unwrap_or_default() as u64
+        };
+        churn
+            .rows
+            .iter()
+            .take(CHURN_CHART_ENTITIES)
+            .map(|row| ChurnBar {
+                entity: churn
+                    .get(row, "entity")
+                    .map(Value::to_string)
+                    .unwrap_or_default(),
+                churn: lines(row, "added") + lines(row, "deleted"),
+                added: lines(row, "added"),
+                deleted: lines(row, "deleted"),
+            })
+            .collect()
+    }
+}
+
+#[derive(Serialize)]
+struct ChurnBar {
+    entity: String,
+    churn: u64,
+    added: u64,
+    deleted: u64,
+}
+
+pub fn run(
+    common_args: CommonArgs,
+    git_args: GitArgs,
+    report_args: ReportArgs,
+) -> Result<(), Box<dyn Error>> {
+    info!("Run report of {} analyses", report_args.analyses.len());
+    let log = collect_project_changes(&common_args, git_args.clone())?;
+
+    let hotspots = hotspots(&log, lines_of_code(&common_args));
+
+    let mut report = Report {
+        authors: contributions(&log),
+        ..Default::default()
+    };
+    // only the html and json output show the trends
+    if matches!(
+        common_args.format,
+        OutputFormat::D3Graphics | OutputFormat::Json
+    ) {
+        report.trend = complexity_trend(&common_args, &git_args, &hotspots, &log)?;
+    }
+    for analysis in &report_args.analyses {
+        let title = analysis.title();
+        let metadata = Metadata::of_history(analysis.id(), &common_args, &git_args, &log);
+        let mut result = match analysis {
+            Analysis::Summary => AnalysisResult::from_records(title, metadata, &summarize(&log))?,
+            Analysis::Hotspots => {
+                AnalysisResult::from_records(title, metadata, &hotspots)?.highlight("revisions")
+            }
+            Analysis::Coupling => {
+                let changesets = changesets(&log, &report_args.coupling.grouping);
+                let coupling = coupling(&changesets, &report_args.coupling.options);
+                AnalysisResult::from_records(title, metadata, &coupling)?.highlight("degree")
+            }
+            Analysis::Age => AnalysisResult::from_records(
+                title,
+                metadata,
+                &age(&log, OffsetDateTime::now_utc()),
+            )?,
+            Analysis::Ownership => AnalysisResult::from_records(title, metadata, &ownership(&log))?,
+            Analysis::Churn => AnalysisResult::from_records(title, metadata, &churn(&log))?,
+        };
+        // the analyses differ in their columns; rank each by the columns it has
+        result.rank_where_present(&common_args.ranking)?;
+        report.results.insert(analysis.id(), result);
+    }
+
+    let page = ReportPage {
+        report: &report,
+        analyses: &report_args.analyses,
+        layout: report_args.layout,
+        log: &log,
+    };
+    match (common_args.format, report_args.layout) {
+        (OutputFormat::Csv, _) => report.write_csv_files(&report_args.analyses, &common_args),
+        (OutputFormat::D3Graphics, Layout::Site) => page.write_site(&common_args),
+        // a Parquet file holds a single table, the report has one per analysis
+        (OutputFormat::Parquet, _) => Err("The report is not available as parquet, \
+                                           export the analyses one by one instead"
+            .into()),
+        _ => page.output(&common_args),
+    }
+}
+
+/// Indentation complexity of the hotspots at the end of every month they
+/// existed, oldest first
+fn complexity_trend(
+    common_args: &CommonArgs,
+    git_args: &GitArgs,
+    hotspots: &[Hotspot],
+    log: &[CommitChanges],
+) -> Result<BTreeMap<String, Vec<ComplexityTrend>>, Box<dyn Error>> {
+    let entities: Vec<String> = hotspots.iter().map(|h| h.entity.clone()).collect();
+    let windows = windows(log, Period::Month, 1);
+    let complexities = at_window_ends(common_args, git_args, &entities, &windows, |_, source| {
+        Some(indentation_complexity(source))
+    })?;
+    let mut trend: BTreeMap<String, Vec<ComplexityTrend>> = BTreeMap::new();
+    // ordered by window first
+    for ((w, entity), complexity) in complexities {
+        trend.entry(entity).or_default().push(ComplexityTrend {
+            period: windows[w].label(),
+            complexity,
+        });
+    }
+    Ok(trend)
+}
+
+/// The output directory of the formats writing several files
+fn output_directory(target: Option<&PathBuf>) -> PathBuf {
+    target
+        .cloned()
+        .unwrap_or_else(|| PathBuf::from("ocs-report"))
+}
+
+fn create_file(path: &Path) -> Result<BufWriter<File>, Box<dyn Error>> {
+    let file =
+        File::create(path).map_err(|e| format!("Unable to create {}: {}", path.display(), e))?;
+    Ok(BufWriter::new(file))
+}
+
+const REPORT_CSS: &str = include_str!("../../assets/report/report.css");
+const REPORT_JS: &str = include_str!("../../assets/report/report.js");
+
+struct ReportPage<'a> {
+    report: &'a Report,
+    analyses: &'a [Analysis],
+    layout: Layout,
+    /// The change log of the analyses, stored with them in SQLite output
+    log: &'a [CommitChanges],
+}
+
+impl ReportPage<'_> {
+    /// The first analysis is the start page of a site
+    fn file_name(&self, analysis: Analysis) -> String {
+        if self.analyses.first() == Some(&analysis) {
+            "index.html".to_owned()
+        } else {
+            format!("{}.html", analysis.id())
+        }
+    }
+
+    fn href(&self, analysis: Analysis) -> String {
+        match self.layout {
+            Layout::Dashboard => format!("#{}", analysis.id()),
+            Layout::Site => self.file_name(analysis),
+        }
+    }
+
+    /// A page per analysis into the output directory
+    fn write_site(&self, common_args: &CommonArgs) -> Result<(), Box<dyn Error>> {
+        let templates = ChartTemplates::new(common_args.template_dir.map(|d| d.as_path()));
+        let directory = output_directory(common_args.output);
+        fs::create_dir_all(&directory)?;
+        for analysis in self.analyses {
+            let mut writer = create_file(&directory.join(self.file_name(*analysis)))?;
+            html::write_head(&mut writer, common_args.d3_source)?;
+            self.write(&mut writer, Some(*analysis), &templates)?;
+            writer.flush()?;
+        }
+        info!("Report written to {}", directory.display());
+        Ok(())
+    }
+
+    /// Write the body of the page with all analyses, or a single one for a
+    /// site
+    fn write(
+        &self,
+        writer: &mut dyn Write,
+        only: Option<Analysis>,
+        templates: &ChartTemplates,
+    ) -> Result<(), Box<dyn Error>> {
+        writeln!(writer, "<title>Code analysis report</title>")?;
+        writeln!(writer, "<style>\n{}</style>", REPORT_CSS)?;
+        writeln!(writer, "<header><h1>Code analysis report</h1><nav>")?;
+        for analysis in self.analyses {
+            writeln!(
+                writer,
+                "<a href=\"{}\"{}>{}</a>",
+                self.href(*analysis),
+                if only == Some(*analysis) {
+                    " class=\"current\""
+                } else {
+                    ""
+                },
+                escape_html(analysis.title())
+            )?;
+        }
+        writeln!(writer, "</nav></header>")?;
+        writeln!(writer, "<div class=\"layout\"><main>")?;
+        for analysis in self.analyses {
+            if only.is_some_and(|only| only != *analysis) {
+                continue;
+            }
+            writeln!(
+                writer,
+                "<section id=\"{}\"><h2>{}</h2>",
+                analysis.id(),
+                escape_html(analysis.title())
+            )?;
+            self.write_section(writer, *analysis, templates)?;
+            writeln!(writer, "</section>")?;
+        }
+        writeln!(writer, "</main>")?;
+        writeln!(
+            writer,
+            "<aside id=\"details\"><p class=\"hint\">Click an entity to see its coupling partners, authors and complexity trend.</p></aside>"
+        )?;
+        writeln!(writer, "</div>")?;
+
+        let pages: BTreeMap<&str, String> = self
+            .analyses
+            .iter()
+            .map(|analysis| (analysis.id(), self.href(*analysis)))
+            .collect();
+        writeln!(writer, "<script type=\"module\">")?;
+        // a page of a site has its own data only
+        let report = match only {
+            Some(analysis) => script_json(&self.report.page(analysis))?,
+            None => script_json(self.report)?,
+        };
+        writeln!(writer, "const report = {};", report)?;
+        writeln!(writer, "const pages = {};", script_json(&pages)?)?;
+        writer.write_all(REPORT_JS.as_bytes())?;
+        writeln!(writer, "</script>")?;
+        Ok(())
+    }
+
+    fn write_section(
+        &self,
+        writer: &mut dyn Write,
+        analysis: Analysis,
+        templates: &ChartTemplates,
+    ) -> Result<(), Box<dyn Error>> {
+        let report = self.report;
+        let results = |analysis: Analysis| report.results.get(analysis.id());
+        match analysis {
+            Analysis::Hotspots => {
+                let chart = Chart::new(ChartType::CirclePacking, "Lines of code and revisions")
+                    .id("hotspots-chart")
+                    .field("path", "entity")
+                    .field("size", "code")
+                    .field("color", "revisions");
+                templates.render_section(writer, &chart, &results(analysis))?;
+            }
+            Analysis::Coupling => {
+                let chart = Chart::new(ChartType::Network, "Coupled entities")
+                    .id("coupling-chart")
+                    .field("source", "entity")
+                    .field("target", "coupled")
+                    .field("weight", "degree");
+                templates.render_section(writer, &chart, &results(analysis))?;
+            }
+            Analysis::Churn => {
+                let bars = report.churn_bars();
+                let chart = Chart::new(ChartType::BarChart, "Most churned entities")
+                    .id("churn-chart")
+                    .field("label", "entity")
+                    .field("value", "churn");
+                templates.render_section(writer, &chart, &bars)?;
+            }
+            Analysis::Summary | Analysis::Age | Analysis::Ownership => {}
+        }
+        writeln!(
+            writer,
+            "<div class=\"table\" data-table=\"{}\"></div>",
+            analysis.id()
+        )?;
+        Ok(())
+    }
+}
+
+impl OutputFormatter for ReportPage<'_> {
+    fn csv_output(&self, _writer: &mut dyn Write) -> Result<(), Box<dyn Error>> {
+        unreachable!("a file per analysis, written by run")
+    }
+
+    fn json_output(&self, writer: &mut dyn Write) -> Result<(), Box<dyn Error>> {
+        serde_json::to_writer_pretty(&mut *writer, self.report)?;
+        writeln!(writer)?;
+        Ok(())
+    }
+
+    fn markdown_output(&self, writer: &mut dyn Write) -> Result<(), Box<dyn Error>> {
+        self.report.markdown_output(writer, self.analyses)
+    }
+
+    fn table_output(
+        &self,
+        writer: &mut dyn Write,
+        options: &TableOptions,
+    ) -> Result<(), Box<dyn Error>> {
+        self.report.table_output(writer, self.analyses, options)
+    }
+
+    fn parquet_output(&self, _writer: &mut dyn Write) -> Result<(), Box<dyn Error>> {
+        unreachable!("rejected by run")
+    }
+
+    fn sqlite_output(&self, database: &Path) -> Result<(), Box<dyn Error>> {
+        let results: Vec<&AnalysisResult> = self.report.results.values().collect();
+        sqlite::write_database(database, &results, self.log)
+    }
+
+    fn d3_html_output(
+        &self,
+        writer: &mut dyn Write,
+        templates: &ChartTemplates,
+    ) -> Result<(), Box<dyn Error>> {
+        self.write(writer, None, templates)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[derive(Default, Serialize)]
+    struct Entity {
+        entity: &'static str,
+    }
+
+    #[derive(Default, Serialize)]
+    struct Pair {
+        entity: &'static str,
+        coupled: &'static str,
+    }
+
+    #[test]
+    fn verify_page_data_of_its_entities() {
+        let result = |records: &[Entity]| {
+            AnalysisResult::from_records("", Metadata::default(), records).unwrap()
+        };
+        let pairs = [
+            Pair {
+                entity: "b.rs",
+                coupled: "a.rs",
+            },
+            Pair {
+                entity: "x.rs",
+                coupled: "y.rs",
+            },
+        ];
+        let contribution = Contribution {
+            author: "ann".to_owned(),
+            revisions: 1,
+            added: 1,
+            deleted: 0,
+        };
+        let report = Report {
+            results: BTreeMap::from([
+                (
+                    Analysis::Hotspots.id(),
+                    result(&[Entity { entity: "a.rs" }]),
+                ),
+                (
+                    Analysis::Age.id(),
+                    result(&[Entity { entity: "a.rs" }, Entity { entity: "z.rs" }]),
+                ),
+                (
+                    Analysis::Coupling.id(),
+                    AnalysisResult::from_records("", Metadata::default(), &pairs).unwrap(),
+                ),
+            ]),
+            authors: BTreeMap::from([
+                ("a.rs".to_owned(), vec![contribution.clone()]),
+                ("z.rs".to_owned(), vec![contribution]),
+            ]),
+            trend: BTreeMap::new(),
+        };
+        let page = report.page(Analysis::Hotspots);
+        assert_eq!(
+            serde_json::to_string(&page).unwrap(),
+            r#"{"age":[{"entity":"a.rs"}],"coupling":[{"entity":"b.rs","coupled":"a.rs"}],"hotspots":[{"entity":"a.rs"}],"authors":{"a.rs":[{"author":"ann","revisions":1,"added":1,"deleted":0}]},"trend":{}}"#
+        );
+    }
+}
@@ -1,13 +1,11 @@
+use crate::changes::{collect_project_changes, CommitChanges};
+use crate::chart::{Chart, ChartType};
 use crate::cli::{CommonArgs, GitArgs};
-use crate::progress;
-use git2::Error;
-use git2::{Commit, ObjectType, Oid, Repository};
-use std::cmp::{Ord, Ordering};
-use std::collections::BTreeSet;
-use std::path::PathBuf;
-use std::str;
+use crate::result::{AnalysisResult, Metadata};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::error::Error;
 
-use crate::determine_commits_to_analyse;
 #[allow(unused_imports)]
 use crate::git_common_args_extension;
 
@@ -19,112 +17,77 @@ pub const COMMAND: &str = "revisions";
 macro_rules! revisions_command {
     ($command_builder:expr) => {
         $command_builder.subcommand(git_common_args_extension(
-            Command::new(subcommands::revisions::COMMAND).about("Git revision frequency"),
+            Command::new(subcommands::revisions::COMMAND)
+                .about("Git revision frequency")
+                .after_help(
+                    "The revisions of a file are the analysed commits changing it; merge \
+                     commits have no changes of their own. Paths are relative to the \
+                     repository, prefixed by its name if several repositories are analysed",
+                ),
         ))
     };
 }
 
-#[derive(Clone)]
-struct EntryRevisions {
-    name: PathBuf,
-    revisions: BTreeSet<Oid>,
-}
-
-impl EntryRevisions {
-    pub fn new(name: PathBuf) -> EntryRevisions {
-        EntryRevisions {
-            name,
-            revisions: BTreeSet::new(),
-        }
-    }
-}
-
-impl Ord for EntryRevisions {
-    fn cmp(&self, other: &Self) -> Ordering {
-        self.name.cmp(&other.name)
-    }
-}
-
-impl PartialOrd for EntryRevisions {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.cmp(other))
-    }
-}
-
-impl PartialEq for EntryRevisions {
-    fn eq(&self, other: &Self) -> bool {
-        self.name == other.name
-    }
+pub fn run(common_args: CommonArgs, git_args: GitArgs) -> Result<(), Box<dyn Error>> {
+    info!("Run git revision frequencies");
+    let log = collect_project_changes(&common_args, git_args.clone())?;
+    let metadata = Metadata::of_history(COMMAND, &common_args, &git_args, &log);
+
+    AnalysisResult::from_records("Revisions", metadata, &revisions(&log))?
+        .chart(
+            Chart::new(ChartType::BarChart, "Revisions")
+                .field("label", "entry")
+                .field("value", "n-revs")
+                .field("color", "n-revs"),
+        )
+        .highlight("n-revs")
+        .history(log)
+        .output_ranked(&common_args)
 }
 
-impl Eq for EntryRevisions {}
-
-fn analyze_tree_object(
-    repo: &Repository,
-    tree: git2::Tree,
-    path: PathBuf,
-    entries: &mut BTreeSet<EntryRevisions>,
-) {
-    for entry in tree.iter() {
-        if let Some(n) = entry.name() {
-            let mut p = path.clone();
-            p.push(n.to_owned());
-            if entry.kind() == Some(ObjectType::Blob) {
-                let entry_revision = EntryRevisions::new(p);
-                entries.insert(entry_revision.clone());
-                if let Some(entry_revision) = entries.get(&entry_revision) {
-                    let mut e = entry_revision.clone();
-                    e.revisions.insert(entry.id().clone());
-                    entries.replace(e);
-                }
-            } else {
-                if entry.kind() == Some(ObjectType::Tree) {
-                    let tree = repo.find_tree(entry.id()).unwrap();
-                    analyze_tree_object(repo, tree, p, entries);
-                }
-            }
+/// The number of commits changing each path, by path
+fn revisions(log: &[CommitChanges]) -> Vec<EntryRevisions> {
+    let mut entries: BTreeMap<&str, u64> = BTreeMap::new();
+    for commit in log {
+        for change in &commit.changes {
+            *entries.entry(&change.path).or_default() += 1;
         }
     }
+    entries
+        .into_iter()
+        .map(|(entry, revisions)| EntryRevisions {
+            entry: entry.to_owned(),
+            revisions,
+        })
+        .collect()
 }
 
-fn analyse_entries_in_commit(
-    repo: &Repository,
-    commit: &Commit,
-    path: PathBuf,
-    entries: &mut BTreeSet<EntryRevisions>,
-) {
-    analyze_tree_object(
-        repo,
-        commit.tree().expect("Every commit has a tree object"),
-        path,
-        entries,
-    );
+#[derive(Default, Serialize)]
+struct EntryRevisions {
+    entry: String,
+    #[serde(rename = "n-revs")]
+    revisions: u64,
 }
 
-pub fn run(common_args: CommonArgs, git_args: GitArgs) -> Result<(), Error> {
-    info!("Run git revision frequencies");
-    let repo = Repository::open(common_args.project_dir.clone())?;
-
-    let revwalk = determine_commits_to_analyse(&repo, git_args)?;
-    let mut entries: BTreeSet<EntryRevisions> = BTreeSet::new();
-    progress::start_commit_analysing();
-
-    for commit in revwalk {
-        progress::increment_commit_analysing();
-        let commit = commit?;
-        let path = PathBuf::from(common_args.project_dir.clone());
-        analyse_entries_in_commit(&repo, &commit, path, &mut entries);
-    }
-    progress::finish_commit_analysing();
-
-    println!("entry,n-revs");
-    for entry_revision in entries {
-        println!(
-            "{},{}",
-            entry_revision.name.display(),
-            entry_revision.revisions.len()
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn verify_revisions_per_path() {
+        let log = vec![
+            CommitChanges::fixture("ann", 3).with_changes(&["src/main.rs", "README.md"]),
+            // a merge commit
+            CommitChanges::fixture("bob", 2),
+            CommitChanges::fixture("ann", 1).with_change("src/main.rs", 20, 0),
+        ];
+        let revisions: Vec<(String, u64)> = revisions(&log)
+            .into_iter()
+            .map(|r| (r.entry, r.revisions))
+            .collect();
+        assert_eq!(
+            revisions,
+            vec![("README.md".to_owned(), 1), ("src/main.rs".to_owned(), 2)]
         );
     }
-
-    Ok(())
 }
@@ -0,0 +1,142 @@
+use crate::changes::{collect_project_changes, CommitChanges};
+use crate::chart::{Chart, ChartType};
+use crate::cli::{CommonArgs, GitArgs};
+use crate::coupling::{changesets, coupling, ChangesetGrouping, CouplingOptions};
+use crate::metrics::{age, contributions};
+use crate::result::{AnalysisResult, Metadata};
+use crate::risk::{fragmentation, indentation_complexity, score, Risk};
+use git2::{Oid, Repository};
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::path::Path;
+use time::OffsetDateTime;
+
+use super::cloc::lines_of_code;
+use super::hotspot::hotspots;
+use super::xray::locate;
+#[allow(unused_imports)]
+use crate::git_common_args_extension;
+
+use log::info;
+
+pub const COMMAND: &str = "risk";
+
+#[macro_export]
+macro_rules! risk_command {
+    ($command_builder:expr) => {
+        $command_builder.subcommand(git_common_args_extension(
+            Command::new(subcommands::risk::COMMAND)
+                .about(
+                    "Composite risk score of files: change frequency, size, complexity, \
+                    complexity trend, author fragmentation, code age and coupling",
+                )
+                .after_help(
+                    "The score is the weighted mean of the factors normalized over all files,
+                    0 to 100. Weights are set in the [risk] table of the configuration file.
+                    Only files of the current checkout with recognized source code are listed",
+                ),
+        ))
+    };
+}
+
+pub fn run(common_args: CommonArgs, git_args: GitArgs) -> Result<(), Box<dyn Error>> {
+    info!("Run risk analysis");
+    let log = collect_project_changes(&common_args, git_args.clone())?;
+    let metadata = Metadata::of_history(COMMAND, &common_args, &git_args, &log);
+    let mut risks = risk_factors(&common_args, &log, OffsetDateTime::now_utc())?;
+    score(&mut risks, &common_args.config.risk);
+
+    // Treemap of the directory hierarchy: tile size is the lines of code,
+    // the colour intensity the risk score
+    AnalysisResult::from_records("Risk", metadata, &risks)?
+        .chart(
+            Chart::new(ChartType::Treemap, "Risk map")
+                .field("path", "entity")
+                .field("size", "code")
+                .field("color", "score"),
+        )
+        .highlight("score")
+        .history(log)
+        .output_ranked(&common_args)
+}
+
+/// The unscored risk factors of the hotspots
+fn risk_factors(
+    common_args: &CommonArgs,
+    log: &[CommitChanges],
+    now: OffsetDateTime,
+) -> Result<Vec<Risk>, Box<dyn Error>> {
+    let ages: BTreeMap<String, u32> = age(log, now)
+        .into_iter()
+        .map(|a| (a.entity, a.age_months))
+        .collect();
+    let authors = contributions(log);
+    let mut strongest: BTreeMap<String, u64> = BTreeMap::new();
+    let options = CouplingOptions::default();
+    for pair in coupling(&changesets(log, &ChangesetGrouping::Commit), &options) {
+        for entity in [pair.entity, pair.coupled] {
+            let degree = strongest.entry(entity).or_default();
+            *degree = (*degree).max(pair.degree);
+        }
+    }
+    // The commit of the earliest change of each path; the log is not
+    // ordered by time
+    let mut oldest: BTreeMap<&str, (i64, &str)> = BTreeMap::new();
+    for commit in log {
+        for change in &commit.changes {
+            let earliest = oldest
+                .entry(&change.path)
+                .or_insert((commit.time, &commit.id));
+            if commit.time < earliest.0 {
+                *earliest = (commit.time, &commit.id);
+            }
+        }
+    }
+
+    let mut repositories: BTreeMap<String, (Repository, String)> = BTreeMap::new();
+    let mut risks = Vec::new();
+    for hotspot in hotspots(log, lines_of_code(common_args)) {
+        let (repository, path) = locate(common_args, &hotspot.entity)?;
+        if !repositories.contains_key(&repository) {
+            let repo = Repository::open(&repository)?;
+            let head = repo.head()?.peel_to_commit()?.id().to_string();
+            repositories.insert(repository.clone(), (repo, head));
+        }
+        let (repo, head) = &repositories[&repository];
+        let complexity = complexity_at(repo, head, path).unwrap_or_default();
+        let initial = oldest
+            .get(hotspot.entity.as_str())
+            .and_then(|(_, id)| complexity_at(repo, id, path));
+
+        risks.push(Risk {
+            score: 0.0,
+            revisions: hotspot.revisions,
+            code: hotspot.code,
+            complexity,
+            complexity_trend: initial.map_or(0, |initial| complexity as i64 - initial as i64),
+            fragmentation: authors
+                .get(&hotspot.entity)
+                .map_or(0.0, |authors| fragmentation(authors)),
+            age_months: ages.get(&hotspot.entity).copied().unwrap_or_default(),
+            coupling: strongest.get(&hotspot.entity).copied().unwrap_or_default(),
+            entity: hotspot.entity,
+        });
+    }
+    Ok(risks)
+}
+
+/// Indentation complexity of a file as of a commit; `None` if not readable,
+/// e.g. a path within a submodule
+fn complexity_at(repo: &Repository, commit: &str, path: &str) -> Option<u64> {
+    let tree = repo
+        .find_commit(Oid::from_str(commit).ok()?)
+        .ok()?
+        .tree()
+        .ok()?;
+    let blob = repo
+        .find_blob(tree.get_path(Path::new(path)).ok()?.id())
+        .ok()?;
+    Some(indentation_complexity(&String::from_utf8_lossy(
+        blob.content(),
+    )))
+}
@@ -0,0 +1,149 @@
+use crate::changes::collect_project_changes;
+use crate::chart::{Chart, ChartType};
+use crate::cli::{CommonArgs, GitArgs};
+use crate::knowledge::{component, owners, truck_factor};
+use crate::metrics::{contributions, Contribution};
+use crate::result::{AnalysisResult, Metadata};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::error::Error;
+
+use super::cloc::lines_of_code;
+#[allow(unused_imports)]
+use crate::git_common_args_extension;
+
+use log::info;
+
+pub const COMMAND: &str = "truck-factor";
+
+/// Name of the component of all files
+pub const ALL_FILES: &str = "(all)";
+
+#[macro_export]
+macro_rules! truck_factor_command {
+    ($command_builder:expr) => {
+        $command_builder.subcommand(git_common_args_extension(
+            subcommands::knowledge_loss::component_args_extension(
+                Command::new(subcommands::truck_factor::COMMAND)
+                    .about(
+                        "Truck factor: the authors whose departure leaves most files without owner",
+                    )
+                    .after_help(
+                        "Owners of a file are its main developer and all authors who added at
+                        least a quarter of its lines. The truck factor is the number of critical
+                        authors leaving more than half of the files without owner. With --depth
+                        it is computed per component as well",
+                    )
+                    .arg(
+                        Arg::new("orphaned")
+                            .long("orphaned")
+                            .action(ArgAction::SetTrue)
+                            .help("List the files left without owner by the critical authors"),
+                    ),
+            ),
+        ))
+    };
+}
+
+#[derive(Default, Serialize)]
+pub struct TruckFactor {
+    pub component: String,
+    #[serde(rename = "truck-factor")]
+    pub truck_factor: usize,
+    pub files: usize,
+    /// Files without owner after the critical authors left
+    pub orphaned: usize,
+    /// The critical authors in order of departure
+    pub authors: String,
+}
+
+#[derive(Default, Serialize)]
+pub struct OrphanedFile {
+    pub entity: String,
+    pub revisions: u64,
+    pub code: usize,
+    pub owners: String,
+}
+
+pub fn run(
+    common_args: CommonArgs,
+    git_args: GitArgs,
+    depth: usize,
+    list_orphaned: bool,
+) -> Result<(), Box<dyn Error>> {
+    info!("Run truck factor analysis");
+    let log = collect_project_changes(&common_args, git_args.clone())?;
+    let metadata = Metadata::of_history(COMMAND, &common_args, &git_args, &log);
+    let authors = contributions(&log);
+    let code = lines_of_code(&common_args);
+    let owners: BTreeMap<String, Vec<String>> = code
+        .keys()
+        .filter_map(|file| Some((file.clone(), owners(authors.get(file)?))))
+        .collect();
+    let (critical, orphaned) = truck_factor(&owners);
+
+    if list_orphaned {
+        let revisions = |file: &String| -> u64 {
+            authors
+                .get(file)
+                .map_or(0, |a| a.iter().map(|c: &Contribution| c.revisions).sum())
+        };
+        let mut files: Vec<OrphanedFile> = orphaned
+            .into_iter()
+            .map(|file| OrphanedFile {
+                revisions: revisions(&file),
+                code: code[&file],
+                owners: owners[&file].join(", "),
+                entity: file,
+            })
+            .collect();
+        files.sort_by(|a, b| b.revisions.cmp(&a.revisions).then(b.code.cmp(&a.code)));
+        return AnalysisResult::from_records("Orphaned files", metadata, &files)?
+            .chart(
+                Chart::new(ChartType::CirclePacking, "Orphaned files")
+                    .field("path", "entity")
+                    .field("size", "code")
+                    .field("color", "revisions"),
+            )
+            .highlight("revisions")
+            .history(log)
+            .output_ranked(&common_args);
+    }
+
+    let mut truck_factors = vec![TruckFactor {
+        component: ALL_FILES.to_owned(),
+        truck_factor: critical.len(),
+        files: owners.len(),
+        orphaned: orphaned.len(),
+        authors: critical.join(", "),
+    }];
+    if depth > 0 {
+        let mut components: BTreeMap<String, BTreeMap<String, Vec<String>>> = BTreeMap::new();
+        for (file, file_owners) in &owners {
+            components
+                .entry(component(file, depth))
+                .or_default()
+                .insert(file.clone(), file_owners.clone());
+        }
+        for (component, owners) in components {
+            let (critical, orphaned) = truck_factor(&owners);
+            truck_factors.push(TruckFactor {
+                component,
+                truck_factor: critical.len(),
+                files: owners.len(),
+                orphaned: orphaned.len(),
+                authors: critical.join(", "),
+            });
+        }
+    }
+
+    AnalysisResult::from_records("Truck factor", metadata, &truck_factors)?
+        .chart(
+            Chart::new(ChartType::BarChart, "Truck factor")
+                .field("label", "component")
+                .field("value", "truck-factor"),
+        )
+        .highlight("truck-factor")
+        .history(log)
+        .output_ranked(&common_args)
+}
@@ -0,0 +1,120 @@
+use crate::chart::{Chart, ChartType};
+use crate::cli::{CommonArgs, GitArgs};
+use crate::result::{AnalysisResult, Metadata};
+use crate::xray::{function_history, FunctionHistory};
+use git2::Repository;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::error::Error;
+
+#[allow(unused_imports)]
+use crate::git_common_args_extension;
+
+use log::info;
+
+pub const COMMAND: &str = "xray";
+
+#[macro_export]
+macro_rules! xray_command {
+    ($command_builder:expr) => {
+        $command_builder.subcommand(git_common_args_extension(
+            Command::new(subcommands::xray::COMMAND)
+                .about("Function level hotspots of a file: change frequency, size and complexity")
+                .after_help(
+                    "Every revision of the file is parsed into its functions and methods.
+                    Supported are Rust, C, Java, Python and TypeScript sources",
+                )
+                .arg(
+                    Arg::new("ENTITY")
+                        .required(true)
+                        .help("The file as named by the other analyses, e.g. src/main.rs"),
+                ),
+        ))
+    };
+}
+
+#[derive(Default, Serialize)]
+pub struct FunctionHotspot {
+    pub function: String,
+    pub start: usize,
+    pub lines: usize,
+    pub complexity: u64,
+    pub revisions: u64,
+    pub added: u64,
+    pub deleted: u64,
+}
+
+/// The repository holding the entity and the entity's path within it;
+/// with several repositories entities are prefixed by the repository name
+pub fn locate<'e>(common_args: &CommonArgs, entity: &'e str) -> Result<(String, &'e str), String> {
+    if !common_args.is_multi_repository() {
+        return Ok((common_args.project_dir.clone(), entity));
+    }
+    common_args
+        .repositories
+        .iter()
+        .find_map(|repository| {
+            let path = entity.strip_prefix(&repository.name)?.strip_prefix('/')?;
+            Some((repository.path.clone(), path))
+        })
+        .ok_or_else(|| format!("{} is not prefixed by a repository name", entity))
+}
+
+pub fn run(common_args: CommonArgs, git_args: GitArgs, entity: &str) -> Result<(), Box<dyn Error>> {
+    info!("Run function level analysis of {}", entity);
+    let (repository, path) = locate(&common_args, entity)?;
+    let repo = Repository::open(repository)?;
+    let history = function_history(&repo, path, git_args)?;
+
+    let mut metadata = Metadata::new(COMMAND, &common_args);
+    metadata.commits = Some(history.commits);
+    AnalysisResult::from_records(
+        &format!("X-ray of {}", entity),
+        metadata,
+        &function_hotspots(&history),
+    )?
+    .chart(
+        Chart::new(ChartType::BarChart, "Function hotspots")
+            .field("label", "function")
+            .field("value", "revisions")
+            .field("color", "complexity"),
+    )
+    .highlight("revisions")
+    .output_ranked(&common_args)
+}
+
+/// The functions of the newest revision with their change frequency and
+/// churn, most frequently changed first
+pub fn function_hotspots(history: &FunctionHistory) -> Vec<FunctionHotspot> {
+    let mut changes: BTreeMap<&str, (u64, u64, u64)> = BTreeMap::new();
+    for revision in &history.revisions {
+        for change in &revision.changes {
+            let (revisions, added, deleted) = changes.entry(&change.name).or_default();
+            *revisions += 1;
+            *added += change.added;
+            *deleted += change.deleted;
+        }
+    }
+
+    let mut hotspots: Vec<FunctionHotspot> = history
+        .functions
+        .iter()
+        .map(|function| {
+            let (revisions, added, deleted) = changes
+                .get(function.name.as_str())
+                .copied()
+                .unwrap_or_default();
+            FunctionHotspot {
+                function: function.name.clone(),
+                start: function.start,
+                lines: function.lines(),
+                complexity: function.complexity,
+                revisions,
+                added,
+                deleted,
+            }
+        })
+        .collect();
+    hotspots.sort_by(|a, b| b.revisions.cmp(&a.revisions).then(b.lines.cmp(&a.lines)));
+    hotspots
+}
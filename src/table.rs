@@ -0,0 +1,175 @@
+//! Aligned, optionally coloured tables for the terminal
+//!
+//! Numeric columns are right aligned. The values of a highlight column, e.g. the
+//! revisions of hotspots, are coloured by their share of the column maximum.
+
+use console::{measure_text_width, pad_str, Alignment, Style};
+use std::error::Error;
+use std::io::Write;
+
+use crate::result::AnalysisResult;
+
+#[derive(Debug, Clone, Default)]
+pub struct TableOptions {
+    pub colors: bool,
+    highlight: Option<&'static str>,
+}
+
+impl TableOptions {
+    pub fn new(colors: bool) -> Self {
+        TableOptions {
+            colors,
+            highlight: None,
+        }
+    }
+
+    /// Colour the values of the column, the bigger the hotter
+    pub fn highlight(&self, column: &'static str) -> Self {
+        TableOptions {
+            highlight: Some(column),
+            ..self.clone()
+        }
+    }
+}
+
+pub fn write_table(
+    writer: &mut dyn Write,
+    result: &AnalysisResult,
+    options: &TableOptions,
+) -> Result<(), Box<dyn Error>> {
+    let rows: Vec<Vec<String>> = result
+        .rows
+        .iter()
+        .map(|row| row.iter().map(|v| v.to_string()).collect())
+        .collect();
+    let numeric: Vec<bool> = result
+        .columns
+        .iter()
+        .map(|c| c.column_type.is_numeric())
+        .collect();
+    let widths: Vec<usize> = result
+        .columns
+        .iter()
+        .enumerate()
+        .map(|(c, column)| {
+            rows.iter()
+                .map(|row| measure_text_width(&row[c]))
+                .chain([measure_text_width(&column.name)])
+                .max()
+                .unwrap_or_default()
+        })
+        .collect();
+    let alignment = |column: usize| {
+        if numeric[column] {
+            Alignment::Right
+        } else {
+            Alignment::Left
+        }
+    };
+    let highlight = options
+        .highlight
+        .and_then(|name| result.column(name))
+        .filter(|column| numeric[*column]);
+    let max = highlight
+        .map(|column| {
+            result
+                .rows
+                .iter()
+                .filter_map(|row| row[column].as_f64())
+                .fold(0.0, f64::max)
+        })
+        .unwrap_or_default();
+
+    let header_style = Style::new().bold().force_styling(options.colors);
+    let hot = Style::new().red().bold().force_styling(options.colors);
+    let warm = Style::new().yellow().force_styling(options.colors);
+    let plain = Style::new().force_styling(false);
+
+    let cells: Vec<String> = result
+        .columns
+        .iter()
+        .enumerate()
+        .map(|(c, column)| {
+            header_style
+                .apply_to(pad_str(&column.name, widths[c], alignment(c), None))
+                .to_string()
+        })
+        .collect();
+    writeln!(writer, "{}", cells.join("  ").trim_end())?;
+    let rule: Vec<String> = widths.iter().map(|w| "-".repeat(*w)).collect();
+    writeln!(writer, "{}", rule.join("  "))?;
+    for (row, texts) in result.rows.iter().zip(&rows) {
+        let cells: Vec<String> = texts
+            .iter()
+            .enumerate()
+            .map(|(c, value)| {
+                let style = match (highlight, row[c].as_f64()) {
+                    (Some(h), Some(v)) if h == c && max > 0.0 && v / max >= 0.75 => &hot,
+                    (Some(h), Some(v)) if h == c && max > 0.0 && v / max >= 0.5 => &warm,
+                    _ => &plain,
+                };
+                style
+                    .apply_to(pad_str(value, widths[c], alignment(c), None))
+                    .to_string()
+            })
+            .collect();
+        writeln!(writer, "{}", cells.join("  ").trim_end())?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::result::Metadata;
+    use serde::Serialize;
+
+    #[derive(Default, Serialize)]
+    struct Record {
+        entity: &'static str,
+        revisions: u64,
+    }
+
+    #[test]
+    fn verify_aligned_columns() {
+        let records = [
+            Record {
+                entity: "a.rs",
+                revisions: 2,
+            },
+            Record {
+                entity: "src/b.rs",
+                revisions: 12,
+            },
+            Record {
+                entity: "c.rs",
+                revisions: 7,
+            },
+        ];
+        let mut out = Vec::new();
+        let options = TableOptions::new(false).highlight("revisions");
+        let result = AnalysisResult::from_records("Test", Metadata::default(), &records).unwrap();
+        write_table(&mut out, &result, &options).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "entity    revisions\n\
+             --------  ---------\n\
+             a.rs              2\n\
+             src/b.rs         12\n\
+             c.rs              7\n"
+        );
+    }
+
+    #[test]
+    fn verify_header_of_empty_table() {
+        let records: [Record; 0] = [];
+        let mut out = Vec::new();
+        let result = AnalysisResult::from_records("Test", Metadata::default(), &records).unwrap();
+        write_table(&mut out, &result, &TableOptions::new(false)).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "entity  revisions\n\
+             ------  ---------\n"
+        );
+    }
+}
@@ -0,0 +1,148 @@
+//! Analyses over sliding time windows
+//!
+//! A snapshot analysis tells how things are; a trend tells whether they get
+//! better, e.g. whether refactoring a hotspot cooled it down. The analysed
+//! history is cut into calendar periods (months, quarters or years). For
+//! every period a window covers that period and the `span - 1` periods
+//! before it, so consecutive windows overlap and the trend is smoothed.
+//!
+//! Periods are in UTC. The first window starts with the period of the
+//! oldest commit, the last one ends with the period of the newest commit.
+
+use clap::{builder::PossibleValue, ValueEnum};
+use time::{Date, Month, OffsetDateTime, Time};
+
+use crate::changes::CommitChanges;
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum Period {
+    Month,
+    Quarter,
+    Year,
+}
+
+impl ValueEnum for Period {
+    fn value_variants<'a>() -> &'a [Self] {
+        &[Period::Month, Period::Quarter, Period::Year]
+    }
+
+    fn to_possible_value(&self) -> Option<PossibleValue> {
+        Some(match self {
+            Period::Month => PossibleValue::new("month"),
+            Period::Quarter => PossibleValue::new("quarter"),
+            Period::Year => PossibleValue::new("year"),
+        })
+    }
+}
+
+impl Period {
+    fn months(&self) -> i32 {
+        match self {
+            Period::Month => 1,
+            Period::Quarter => 3,
+            Period::Year => 12,
+        }
+    }
+
+    /// First day of the period containing the date
+    fn start_of(&self, date: Date) -> Date {
+        let month0 = date.month() as i32 - 1;
+        first_of_month(date.year(), month0 - month0 % self.months())
+    }
+}
+
+/// First day of a month counted from January of the year, may overflow
+fn first_of_month(year: i32, month0: i32) -> Date {
+    let year = year + month0.div_euclid(12);
+    let month = Month::try_from(month0.rem_euclid(12) as u8 + 1).expect("month is 1 to 12");
+    Date::from_calendar_date(year, month, 1).expect("first of a month is a valid date")
+}
+
+fn add_months(date: Date, months: i32) -> Date {
+    first_of_month(date.year(), date.month() as i32 - 1 + months)
+}
+
+fn timestamp(date: Date) -> i64 {
+    date.with_time(Time::MIDNIGHT).assume_utc().unix_timestamp()
+}
+
+/// A time window; commits with `from <= time < to` are in the window
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Window {
+    /// The period the window ends with, named by its first day
+    pub period: Date,
+    /// Start and end in seconds since epoch
+    pub from: i64,
+    pub to: i64,
+}
+
+impl Window {
+    pub fn contains(&self, commit: &CommitChanges) -> bool {
+        self.from <= commit.time && commit.time < self.to
+    }
+
+    /// The first day of the period, `YYYY-MM-DD`
+    pub fn label(&self) -> String {
+        self.period.to_string()
+    }
+}
+
+/// The sliding windows over the history of the change log, oldest first;
+/// a window spans `span` periods
+pub fn windows(log: &[CommitChanges], period: Period, span: u32) -> Vec<Window> {
+    let date = |time: i64| {
+        OffsetDateTime::from_unix_timestamp(time)
+            .unwrap_or(OffsetDateTime::UNIX_EPOCH)
+            .date()
+    };
+    let (Some(first), Some(last)) = (
+        log.iter().map(|c| c.time).min(),
+        log.iter().map(|c| c.time).max(),
+    ) else {
+        return Vec::new();
+    };
+
+    let step = period.months();
+    let span = span.max(1) as i32;
+    let last = period.start_of(date(last));
+    let mut windows = Vec::new();
+    let mut current = period.start_of(date(first));
+    while current <= last {
+        windows.push(Window {
+            period: current,
+            from: timestamp(add_months(current, -step * (span - 1))),
+            to: timestamp(add_months(current, step)),
+        });
+        current = add_months(current, step);
+    }
+    windows
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use time::macros::{date, datetime};
+
+    #[test]
+    fn verify_sliding_windows() {
+        let log = vec![
+            CommitChanges::fixture("", datetime!(2024-02-10 12:00 UTC).unix_timestamp()),
+            CommitChanges::fixture("", datetime!(2023-11-30 23:00 UTC).unix_timestamp()),
+        ];
+        let windows = windows(&log, Period::Quarter, 2);
+        assert_eq!(
+            windows.iter().map(|w| w.period).collect::<Vec<_>>(),
+            vec![date!(2023 - 10 - 01), date!(2024 - 01 - 01)]
+        );
+        assert_eq!(
+            windows[1].from,
+            datetime!(2023-10-01 00:00 UTC).unix_timestamp()
+        );
+        assert_eq!(
+            windows[1].to,
+            datetime!(2024-04-01 00:00 UTC).unix_timestamp()
+        );
+        assert!(windows[1].contains(&log[0]) && windows[1].contains(&log[1]));
+        assert!(!windows[0].contains(&log[0]));
+    }
+}
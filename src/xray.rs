@@ -0,0 +1,530 @@
+//! Function level history of a file ("Software Design X-Rays")
+//!
+//! File level hotspots tell where to look; in a file of thousands of lines
+//! the X-ray tells which functions. Every revision of the file is parsed
+//! with a tree-sitter grammar into its functions and methods. A function
+//! changed in a commit if its source text differs from the one of the first
+//! parent; functions only moved by changes elsewhere in the file are not
+//! changed.
+//!
+//! ## Design Decisions
+//!
+//! - Functions are identified by their name qualified by the enclosing
+//!   types, modules or functions, e.g. `Parser::parse` or `Parser.parse`.
+//!   Functions sharing a qualified name (overloads, several impl blocks)
+//!   are numbered in source order: `fmt`, `fmt#2`. A renamed function is a
+//!   new function.
+//! - Like for file changes, merge commits carry no changes and commits are
+//!   compared against their first parent.
+//! - For analyses across functions, e.g. coupling, the function changes are
+//!   turned into a change log whose entities are `<file>:<function>`.
+//! - Complexity is the cyclomatic complexity: one plus the number of
+//!   branches (conditions, loops, cases, catches, boolean operators) of the
+//!   function, not counting nested functions.
+
+use git2::{Commit, Oid, Patch, Repository};
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::path::Path;
+use tree_sitter::{Node, Parser};
+
+use crate::changes::{CommitChanges, FileChange};
+use crate::cli::GitArgs;
+use crate::git::determine_commits_to_analyse;
+use crate::progress;
+
+/// Languages with a grammar for function parsing
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Language {
+    Rust,
+    C,
+    Java,
+    Python,
+    TypeScript,
+    Tsx,
+}
+
+impl Language {
+    pub const EXTENSIONS: &'static str = "rs, c, h, java, py, ts, tsx";
+
+    /// Language by file extension
+    pub fn of(path: &str) -> Option<Self> {
+        match Path::new(path).extension()?.to_str()? {
+            "rs" => Some(Language::Rust),
+            "c" | "h" => Some(Language::C),
+            "java" => Some(Language::Java),
+            "py" => Some(Language::Python),
+            "ts" | "mts" | "cts" => Some(Language::TypeScript),
+            "tsx" => Some(Language::Tsx),
+            _ => None,
+        }
+    }
+
+    fn grammar(&self) -> tree_sitter::Language {
+        match self {
+            Language::Rust => tree_sitter_rust::LANGUAGE.into(),
+            Language::C => tree_sitter_c::LANGUAGE.into(),
+            Language::Java => tree_sitter_java::LANGUAGE.into(),
+            Language::Python => tree_sitter_python::LANGUAGE.into(),
+            Language::TypeScript => tree_sitter_typescript::LANGUAGE_TYPESCRIPT.into(),
+            Language::Tsx => tree_sitter_typescript::LANGUAGE_TSX.into(),
+        }
+    }
+
+    fn separator(&self) -> &'static str {
+        match self {
+            Language::Rust | Language::C => "::",
+            _ => ".",
+        }
+    }
+
+    /// Name of the function the node defines, if it defines one
+    fn function_name(&self, node: Node, source: &str) -> Option<String> {
+        let name = |field| Some(text(node.child_by_field_name(field)?, source));
+        match (self, node.kind()) {
+            // abstract and interface methods have no body to change
+            (Language::Java, "method_declaration" | "constructor_declaration")
+                if node.child_by_field_name("body").is_some() =>
+            {
+                name("name")
+            }
+            (Language::Rust, "function_item")
+            | (Language::Python, "function_definition")
+            | (
+                Language::TypeScript | Language::Tsx,
+                "function_declaration" | "generator_function_declaration" | "method_definition",
+            ) => name("name"),
+            (Language::C, "function_definition") => {
+                // the name is nested in (pointer, function) declarators
+                let mut declarator = node.child_by_field_name("declarator")?;
+                while let Some(inner) = declarator.child_by_field_name("declarator") {
+                    declarator = inner;
+                }
+                Some(text(declarator, source))
+            }
+            // const handler = (event) => { ... }
+            (Language::TypeScript | Language::Tsx, "variable_declarator")
+                if matches!(
+                    node.child_by_field_name("value").map(|v| v.kind()),
+                    Some("arrow_function" | "function_expression" | "function")
+                ) =>
+            {
+                name("name")
+            }
+            _ => None,
+        }
+    }
+
+    /// Name of the type or module the node defines, qualifying its functions
+    fn scope_name(&self, node: Node, source: &str) -> Option<String> {
+        let name = |field| Some(text(node.child_by_field_name(field)?, source));
+        match (self, node.kind()) {
+            (Language::Rust, "impl_item") => name("type"),
+            (Language::Rust, "trait_item" | "mod_item")
+            | (
+                Language::Java,
+                "class_declaration" | "interface_declaration" | "enum_declaration",
+            )
+            | (Language::Python, "class_definition")
+            | (
+                Language::TypeScript | Language::Tsx,
+                "class_declaration" | "abstract_class_declaration" | "interface_declaration",
+            ) => name("name"),
+            _ => None,
+        }
+    }
+
+    fn is_branch(&self, node: Node, source: &str) -> bool {
+        let kinds: &[&str] = match self {
+            Language::Rust => &[
+                "if_expression",
+                "for_expression",
+                "while_expression",
+                "match_arm",
+            ],
+            Language::C => &[
+                "if_statement",
+                "for_statement",
+                "while_statement",
+                "do_statement",
+                "case_statement",
+                "conditional_expression",
+            ],
+            Language::Java => &[
+                "if_statement",
+                "for_statement",
+                "enhanced_for_statement",
+                "while_statement",
+                "do_statement",
+                "switch_label",
+                "catch_clause",
+                "ternary_expression",
+            ],
+            Language::Python => &[
+                "if_statement",
+                "elif_clause",
+                "for_statement",
+                "while_statement",
+                "except_clause",
+                "conditional_expression",
+                "boolean_operator",
+                "for_in_clause",
+                "if_clause",
+            ],
+            Language::TypeScript | Language::Tsx => &[
+                "if_statement",
+                "for_statement",
+                "for_in_statement",
+                "while_statement",
+                "do_statement",
+                "switch_case",
+                "catch_clause",
+                "ternary_expression",
+            ],
+        };
+        kinds.contains(&node.kind())
+            || node.kind() == "binary_expression"
+                && node
+                    .child_by_field_name("operator")
+                    .is_some_and(|op| matches!(&source[op.byte_range()], "&&" | "||" | "??"))
+    }
+}
+
+fn text(node: Node, source: &str) -> String {
+    source[node.byte_range()].to_owned()
+}
+
+/// A function or method of one revision of a file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Function {
+    /// Qualified name, unique within the file
+    pub name: String,
+    /// First and last line, starting at 1
+    pub start: usize,
+    pub end: usize,
+    pub complexity: u64,
+    source: String,
+}
+
+impl Function {
+    pub fn lines(&self) -> usize {
+        self.end - self.start + 1
+    }
+}
+
+/// The functions of a source file in source order
+pub fn functions(language: Language, source: &str) -> Result<Vec<Function>, Box<dyn Error>> {
+    let mut parser = Parser::new();
+    parser.set_language(&language.grammar())?;
+    let tree = parser
+        .parse(source, None)
+        .ok_or("Parsing the source was cancelled")?;
+
+    let mut functions = Vec::new();
+    collect_functions(
+        language,
+        tree.root_node(),
+        source,
+        &mut Vec::new(),
+        &mut functions,
+    );
+
+    let mut seen: BTreeMap<String, usize> = BTreeMap::new();
+    for function in functions.iter_mut() {
+        let count = seen.entry(function.name.clone()).or_default();
+        *count += 1;
+        if *count > 1 {
+            function.name = format!("{}#{}", function.name, count);
+        }
+    }
+    Ok(functions)
+}
+
+fn collect_functions(
+    language: Language,
+    node: Node,
+    source: &str,
+    scope: &mut Vec<String>,
+    functions: &mut Vec<Function>,
+) {
+    let mut cursor = node.walk();
+    for child in node.named_children(&mut cursor) {
+        let function = language.function_name(child, source);
+        let name = function
+            .clone()
+            .or_else(|| language.scope_name(child, source));
+        if let Some(name) = &function {
+            let mut qualified = scope.clone();
+            qualified.push(name.clone());
+            functions.push(Function {
+                name: qualified.join(language.separator()),
+                start: child.start_position().row + 1,
+                end: child.end_position().row + 1,
+                complexity: 1 + branches(language, child, source),
+                source: text(child, source),
+            });
+        }
+        match name {
+            Some(name) => {
+                scope.push(name);
+                collect_functions(language, child, source, scope, functions);
+                scope.pop();
+            }
+            None => collect_functions(language, child, source, scope, functions),
+        }
+    }
+}
+
+/// Branches of a function, without the ones of nested functions
+fn branches(language: Language, node: Node, source: &str) -> u64 {
+    let mut cursor = node.walk();
+    node.named_children(&mut cursor)
+        .filter(|child| language.function_name(*child, source).is_none())
+        .map(|child| language.is_branch(child, source) as u64 + branches(language, child, source))
+        .sum()
+}
+
+/// How a commit changed a function
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FunctionChange {
+    pub name: String,
+    pub added: u64,
+    pub deleted: u64,
+}
+
+/// Functions changed from one revision of a file to the next, including
+/// added ones; deleted functions are left out
+pub fn changed_functions(
+    old: &[Function],
+    new: &[Function],
+) -> Result<Vec<FunctionChange>, Box<dyn Error>> {
+    let old: BTreeMap<&str, &Function> = old.iter().map(|f| (f.name.as_str(), f)).collect();
+    let mut changes = Vec::new();
+    for function in new {
+        let old_source = match old.get(function.name.as_str()) {
+            Some(old) if old.source == function.source => continue,
+            Some(old) => old.source.as_bytes(),
+            None => &[],
+        };
+        let patch = Patch::from_buffers(old_source, None, function.source.as_bytes(), None, None)?;
+        let (_, added, deleted) = patch.line_stats()?;
+        changes.push(FunctionChange {
+            name: function.name.clone(),
+            added: added as u64,
+            deleted: deleted as u64,
+        });
+    }
+    Ok(changes)
+}
+
+/// A commit changing functions of the file
+#[derive(Debug, Clone)]
+pub struct FunctionRevision {
+    pub commit: String,
+    /// Commit time in seconds since epoch
+    pub time: i64,
+    pub author: String,
+    pub message: String,
+    pub changes: Vec<FunctionChange>,
+}
+
+/// Function level history of a file within the commits selected by the git
+/// arguments
+pub struct FunctionHistory {
+    /// Number of commits analysed
+    pub commits: usize,
+    /// Commits changing at least one function of the file
+    pub revisions: Vec<FunctionRevision>,
+    /// The functions of the file in the newest analysed commit
+    pub functions: Vec<Function>,
+}
+
+/// Blob id and content of the file in the commit, if the commit has it
+fn file_source(
+    repo: &Repository,
+    commit: &Commit,
+    path: &Path,
+) -> Result<Option<(Oid, String)>, Box<dyn Error>> {
+    let entry = match commit.tree()?.get_path(path) {
+        Ok(entry) => entry,
+        Err(e) if e.code() == git2::ErrorCode::NotFound => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+    let blob = repo.find_blob(entry.id())?;
+    Ok(Some((
+        entry.id(),
+        String::from_utf8_lossy(blob.content()).into_owned(),
+    )))
+}
+
+pub fn function_history(
+    repo: &Repository,
+    path: &str,
+    git_args: GitArgs,
+) -> Result<FunctionHistory, Box<dyn Error>> {
+    let language = Language::of(path).ok_or_else(|| {
+        format!(
+            "No function parser for {}; supported are {}",
+            path,
+            Language::EXTENSIONS
+        )
+    })?;
+    let file = Path::new(path);
+    let mut commits = 0;
+    let mut revisions = Vec::new();
+    let mut newest: Option<(i64, Oid)> = None;
+
+    progress::start_commit_analysing();
+    for commit in determine_commits_to_analyse(repo, git_args)? {
+        progress::increment_commit_analysing();
+        let commit = commit?;
+        commits += 1;
+        let Some((id, source)) = file_source(repo, &commit, file)? else {
+            continue;
+        };
+        let time = commit.time().seconds();
+        match newest {
+            Some((newest_time, _)) if newest_time >= time => {}
+            _ => newest = Some((time, commit.id())),
+        }
+        if commit.parent_count() > 1 {
+            continue;
+        }
+        let parent = match commit.parents().next() {
+            Some(parent) => file_source(repo, &parent, file)?,
+            None => None,
+        };
+        if parent
+            .as_ref()
+            .is_some_and(|(parent_id, _)| *parent_id == id)
+        {
+            continue;
+        }
+        let old = match parent {
+            Some((_, source)) => functions(language, &source)?,
+            None => Vec::new(),
+        };
+        let changes = changed_functions(&old, &functions(language, &source)?)?;
+        if !changes.is_empty() {
+            revisions.push(FunctionRevision {
+                commit: commit.id().to_string(),
+                time: commit.time().seconds(),
+                author: commit.author().name().unwrap_or_default().to_owned(),
+                message: commit.message().unwrap_or_default().to_owned(),
+                changes,
+            });
+        }
+    }
+    progress::finish_commit_analysing();
+
+    let (_, newest) = newest.ok_or_else(|| format!("{} is not in the analysed commits", path))?;
+    let (_, source) = file_source(repo, &repo.find_commit(newest)?, file)?
+        .expect("the newest commit has the file");
+    Ok(FunctionHistory {
+        commits,
+        revisions,
+        functions: functions(language, &source)?,
+    })
+}
+
+/// Change log of the functions of several files, the entities named
+/// `<file>:<function>`; the file level analyses, e.g. coupling, work on it
+/// alike
+pub fn function_log(histories: &[(&str, FunctionHistory)]) -> Vec<CommitChanges> {
+    let mut log: BTreeMap<&str, CommitChanges> = BTreeMap::new();
+    for (entity, history) in histories {
+        for revision in &history.revisions {
+            let commit = log
+                .entry(&revision.commit)
+                .or_insert_with(|| CommitChanges {
+                    id: revision.commit.clone(),
+                    time: revision.time,
+                    offset: 0,
+                    author: revision.author.clone(),
+                    email: String::new(),
+                    message: revision.message.clone(),
+                    changes: Vec::new(),
+                });
+            commit
+                .changes
+                .extend(revision.changes.iter().map(|change| FileChange {
+                    path: format!("{}:{}", entity, change.name),
+                    added: change.added,
+                    deleted: change.deleted,
+                    submodule: false,
+                    binary: false,
+                }));
+        }
+    }
+    let mut log: Vec<CommitChanges> = log.into_values().collect();
+    log.sort_by_key(|commit| Reverse(commit.time));
+    log
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const SOURCE: &str = "\
+struct Parser;
+
+impl Parser {
+    fn parse(&self, x: u32) -> u32 {
+        if x > 1 && x < 5 {
+            return 1;
+        }
+        for _ in 0..x {}
+        0
+    }
+}
+
+fn main() {
+    let p = Parser;
+    p.parse(3);
+}
+";
+
+    #[test]
+    fn verify_rust_functions() {
+        let functions = functions(Language::Rust, SOURCE).unwrap();
+        let summary: Vec<(&str, usize, usize, u64)> = functions
+            .iter()
+            .map(|f| (f.name.as_str(), f.start, f.lines(), f.complexity))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![("Parser::parse", 4, 7, 4), ("main", 13, 4, 1)]
+        );
+    }
+
+    #[test]
+    fn verify_changed_functions() {
+        let old = functions(
+            Language::Python,
+            "def a():\n    return 1\n\ndef b():\n    pass\n",
+        )
+        .unwrap();
+        // b moves down without changing, a changes, c is new
+        let new = functions(
+            Language::Python,
+            "def a():\n    x = 2\n    return x\n\ndef b():\n    pass\n\ndef c():\n    pass\n",
+        )
+        .unwrap();
+        assert_eq!(
+            changed_functions(&old, &new).unwrap(),
+            vec![
+                FunctionChange {
+                    name: "a".to_owned(),
+                    added: 2,
+                    deleted: 1
+                },
+                FunctionChange {
+                    name: "c".to_owned(),
+                    added: 2,
+                    deleted: 0
+                },
+            ]
+        );
+    }
+}